@@ -2,18 +2,33 @@ use vizia::prelude::*;
 
 use crate::{
     events::{
-        EditorEvent, GridEvent, GroupEvent, MaterialEvent, RuleEvent, RulesetEvent, UpdateEvent,
+        DeletionTarget, EditorEvent, GridEvent, GroupEvent, MaterialEvent, RuleEvent, RulesetEvent,
+        UpdateEvent,
+    },
+    graph::{PopulationGraph, PopulationPlot},
+    grid::{Boundary, Cell, Grid, VisualGridState},
+    id::{Identifiable, UniqueId},
+    material::{MaterialColor, MaterialId},
+    preset::{Orientation, Preset},
+    ruleset::{
+        ConflictPolicy, MetadataField, Ruleset, RulesetFormat, RulesetTemplate, Transformation,
+        ValidationIssue,
     },
-    grid::{Cell, Grid, GridDisplay, VisualGridState},
-    id::Identifiable,
-    ruleset::Ruleset,
     AppData,
 };
+use grid::GridDisplay;
+
+mod condition;
+mod grid;
+mod material;
+mod pattern;
+mod ruleset;
 
 pub fn ruleset_editor(cx: &mut Context) {
     VStack::new(cx, |cx| {
         VStack::new(cx, |cx| {
             toolbar(cx);
+            metadata_editor(cx);
             tabs(cx);
         })
         .class(style::EDITOR_PANEL)
@@ -21,12 +36,13 @@ pub fn ruleset_editor(cx: &mut Context) {
         .row_between(Pixels(5.0));
 
         // Materials
-        HStack::new(cx, |cx| {
-            material_editor(cx);
-            group_editor(cx);
-        })
-        .space(Percentage(1.0))
-        .display(AppData::selected_tab.map(|&tab| tab == EditorTab::Materials));
+        HStack::new(cx, material_editor)
+            .space(Percentage(1.0))
+            .display(AppData::selected_tab.map(|&tab| tab == EditorTab::Materials));
+        // Groups
+        HStack::new(cx, group_editor)
+            .space(Percentage(1.0))
+            .display(AppData::selected_tab.map(|&tab| tab == EditorTab::Groups));
         // Rules
         HStack::new(cx, rule_editor)
             .display(AppData::selected_tab.map(|&tab| tab == EditorTab::Rules));
@@ -34,6 +50,75 @@ pub fn ruleset_editor(cx: &mut Context) {
     .class(style::BACKGROUND);
 }
 
+pub fn status_banner(cx: &mut Context) {
+    Label::new(
+        cx,
+        AppData::status_message.map(|status| {
+            status
+                .as_ref()
+                .map_or_else(String::new, |(message, _)| message.clone())
+        }),
+    )
+    .background_color(AppData::status_message.map(|status| {
+        status
+            .as_ref()
+            .map_or_else(Color::transparent, |(_, color)| *color)
+    }))
+    .class(style::STATUS_BANNER)
+    .display(AppData::status_message.map(Option::is_some));
+}
+
+/// Asks whether to save the open ruleset's edits before switching to another one.
+pub fn unsaved_prompt(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Label::new(
+            cx,
+            AppData::screen
+                .map(|screen| format!("Save changes to '{}' first?", screen.ruleset().name)),
+        )
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Save"))
+            .on_press(|cx| cx.emit(RulesetEvent::SwitchSaved));
+        Button::new(cx, |cx| Label::new(cx, "Discard"))
+            .on_press(|cx| cx.emit(RulesetEvent::SwitchDiscarded));
+        Button::new(cx, |cx| Label::new(cx, "Cancel"))
+            .on_press(|cx| cx.emit(RulesetEvent::SwitchCancelled));
+    })
+    .col_between(Pixels(5.0))
+    .class(style::STATUS_BANNER)
+    .background_color(style::PRESSED_BUTTON_COLOR)
+    .top(Pixels(10.0))
+    .bottom(Stretch(1.0))
+    .display(AppData::pending_switch.map(Option::is_some));
+}
+
+/// Offers to restore the first autosave found on launch that's newer than its ruleset.
+pub fn recovery_prompt(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Label::new(
+            cx,
+            AppData::pending_recoveries.map(|rulesets| {
+                rulesets.first().map_or_else(String::new, |ruleset| {
+                    format!("Recover unsaved edits to '{}'?", ruleset.name)
+                })
+            }),
+        )
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Recover"))
+            .on_press(|cx| cx.emit(RulesetEvent::RecoveryAccepted));
+        Button::new(cx, |cx| Label::new(cx, "Discard"))
+            .on_press(|cx| cx.emit(RulesetEvent::RecoveryDismissed));
+    })
+    .col_between(Pixels(5.0))
+    .class(style::STATUS_BANNER)
+    .background_color(style::PRESSED_BUTTON_COLOR)
+    .top(Pixels(10.0))
+    .bottom(Stretch(1.0))
+    .display(AppData::pending_recoveries.map(|rulesets| !rulesets.is_empty()));
+}
+
 fn toolbar(cx: &mut Context) {
     HStack::new(cx, |cx| {
         Button::new(cx, |cx| Label::new(cx, "Back"))
@@ -53,7 +138,22 @@ fn toolbar(cx: &mut Context) {
         )
         .on_select(|cx, index| cx.emit(RulesetEvent::Selected(index)))
         .top(Stretch(1.0))
-        .bottom(Stretch(1.0));
+        .bottom(Stretch(1.0))
+        .tooltip(|cx| {
+            Tooltip::new(cx, |cx| {
+                Label::new(
+                    cx,
+                    AppData::screen.map(|screen| {
+                        let description = &screen.ruleset().metadata.description;
+                        if description.is_empty() {
+                            String::from("No description")
+                        } else {
+                            description.clone()
+                        }
+                    }),
+                );
+            })
+        });
 
         Textbox::new(cx, AppData::screen.map(|s| s.ruleset().name.clone()))
             .on_submit(|cx, text, _| {
@@ -62,51 +162,322 @@ fn toolbar(cx: &mut Context) {
             .min_width(Pixels(100.0))
             .top(Stretch(1.0))
             .bottom(Stretch(1.0));
+        Label::new(cx, "*")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0))
+            .display(AppData::dirty)
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(cx, "Unsaved changes");
+                })
+            });
+        Button::new(cx, |cx| Label::new(cx, "Info"))
+            .on_press(|cx| cx.emit(EditorEvent::MetadataToggled))
+            .toggle_class(style::PRESSED_BUTTON, AppData::metadata_shown)
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0))
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(cx, "Edit the ruleset's author, description, and notes");
+                })
+            });
 
         Button::new(cx, |cx| Label::new(cx, "New"))
             .on_press(|cx| cx.emit(RulesetEvent::Created))
             .top(Stretch(1.0))
             .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Duplicate"))
+            .on_press(|cx| cx.emit(RulesetEvent::Duplicated(AppData::selected_ruleset.get(cx))))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        ComboBox::new(
+            cx,
+            AppData::template.map(|_| RulesetTemplate::names()),
+            AppData::template.map(|template| template.index()),
+        )
+        .on_select(|cx, index| cx.emit(RulesetEvent::TemplateSelected(index)))
+        .width(Pixels(120.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Label::new(cx, "Background:")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Textbox::new(
+            cx,
+            AppData::new_default_name.map(|name| name.clone().unwrap_or_default()),
+        )
+        .on_submit(|cx, text, _| cx.emit(RulesetEvent::NewDefaultNamed(text)))
+        .width(Pixels(100.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .tooltip(|cx| {
+            Tooltip::new(cx, |cx| {
+                Label::new(cx, "Name of the default material in new rulesets");
+            })
+        });
+        Textbox::new(
+            cx,
+            AppData::new_default_color
+                .map(|color| color.map_or_else(String::new, |color| color.to_string())),
+        )
+        .on_submit(|cx, text, _| cx.emit(RulesetEvent::NewDefaultRecolored(text)))
+        .width(Pixels(80.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .tooltip(|cx| {
+            Tooltip::new(cx, |cx| {
+                Label::new(cx, "Hex color of the default material in new rulesets");
+            })
+        });
 
         Button::new(cx, |cx| Label::new(cx, "Save"))
             .on_press(|cx| cx.emit(RulesetEvent::Saved))
             .top(Stretch(1.0))
             .bottom(Stretch(1.0));
+        ComboBox::new(
+            cx,
+            AppData::ruleset_format.map(|_| RulesetFormat::names()),
+            AppData::ruleset_format.map(|format| format.index()),
+        )
+        .on_select(|cx, index| cx.emit(RulesetEvent::FormatSelected(index)))
+        .width(Pixels(80.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Autosave"))
+            .on_press(|cx| cx.emit(RulesetEvent::AutosaveToggled))
+            .toggle_class(style::PRESSED_BUTTON, AppData::autosave_enabled)
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0))
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(
+                        cx,
+                        "Keep unsaved edits in a separate file, recoverable on launch",
+                    );
+                })
+            });
 
         Button::new(cx, |cx| Label::new(cx, "Reload"))
             .on_press(|cx| cx.emit(RulesetEvent::Reloaded))
             .top(Stretch(1.0))
             .bottom(Stretch(1.0));
+
+        Button::new(cx, |cx| Label::new(cx, "Copy Share Code"))
+            .on_press(|cx| cx.emit(RulesetEvent::ShareCodeCopied))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+
+        Button::new(cx, |cx| Label::new(cx, "Describe"))
+            .on_press(|cx| cx.emit(RulesetEvent::Described))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0))
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(cx, "Write the rules out as sentences to a text file");
+                })
+            });
+
+        Button::new(cx, |cx| Label::new(cx, "Import Share Code"))
+            .on_press(|cx| cx.emit(RulesetEvent::ShareCodeImported))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+
+        Label::new(cx, "Life Rule: ")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Textbox::new(cx, AppData::life_rule)
+            .on_submit(|cx, text, enter_pressed| {
+                if enter_pressed {
+                    cx.emit(RulesetEvent::LifeRuleImported(text));
+                }
+            })
+            .width(Pixels(80.0))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+
+        Button::new(cx, |cx| Label::new(cx, "Merge Share Code"))
+            .on_press(|cx| cx.emit(RulesetEvent::ShareCodeMerged))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+
+        Label::new(cx, "Conflicts: ")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        ComboBox::new(
+            cx,
+            AppData::screen.map(|_| ConflictPolicy::NAMES.map(String::from).to_vec()),
+            AppData::screen.map(|screen| screen.ruleset().conflict_policy.index()),
+        )
+        .on_select(|cx, index| cx.emit(RulesetEvent::ConflictPolicySelected(index)))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Textbox::new(
+            cx,
+            AppData::screen.map(|screen| screen.ruleset().conflict_policy.seed().to_string()),
+        )
+        .on_submit(|cx, text, _| match text.trim().parse() {
+            Ok(seed) => cx.emit(RulesetEvent::ConflictSeedSet(seed)),
+            Err(err) => cx.emit(UpdateEvent::error(format!("Invalid seed '{text}': {err}"))),
+        })
+        .width(Pixels(100.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .display(
+            AppData::screen.map(|screen| {
+                matches!(screen.ruleset().conflict_policy, ConflictPolicy::Random(_))
+            }),
+        );
+
+        Label::new(cx, "Fallback: ")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        ComboBox::new(
+            cx,
+            AppData::screen.map(|screen| {
+                let mut names = vec![String::from("None")];
+                names.extend(screen.ruleset().materials.iter().map(|m| m.name.clone()));
+                names
+            }),
+            AppData::screen.map(|screen| {
+                let ruleset = screen.ruleset();
+                ruleset
+                    .fallback
+                    .and_then(|id| ruleset.materials.index_of(id))
+                    .map_or(0, |index| index + 1)
+            }),
+        )
+        .on_select(|cx, index| cx.emit(RulesetEvent::FallbackSelected(index)))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+
+        Label::new(cx, "Default Size: ")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Textbox::new(
+            cx,
+            AppData::screen.map(|screen| {
+                screen
+                    .ruleset()
+                    .default_size
+                    .map(|size| size.to_string())
+                    .unwrap_or_default()
+            }),
+        )
+        .on_submit(|cx, text, _| {
+            let text = text.trim();
+            if text.is_empty() {
+                cx.emit(RulesetEvent::DefaultSizeSet(None));
+                return;
+            }
+            match text.parse() {
+                Ok(size) => cx.emit(RulesetEvent::DefaultSizeSet(Some(size))),
+                Err(err) => cx.emit(UpdateEvent::error(format!("Invalid size '{text}': {err}"))),
+            }
+        })
+        .width(Pixels(60.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Label::new(cx, "Boundary: ")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        ComboBox::new(
+            cx,
+            AppData::screen.map(|_| {
+                let mut names = vec![String::from("Keep")];
+                names.extend(Boundary::names());
+                names
+            }),
+            AppData::screen.map(|screen| {
+                screen
+                    .ruleset()
+                    .default_boundary
+                    .map_or(0, |boundary| boundary.index() + 1)
+            }),
+        )
+        .on_select(|cx, index| cx.emit(RulesetEvent::DefaultBoundarySelected(index)))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+
+        Button::new(cx, |cx| Label::new(cx, "Watch"))
+            .on_press(|cx| cx.emit(RulesetEvent::WatchToggled))
+            .toggle_class(
+                style::PRESSED_BUTTON,
+                AppData::ruleset_watcher.map(Option::is_some),
+            )
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+
+        overwrite_prompt(cx);
     })
     .height(Auto);
 }
 
+fn metadata_editor(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        for field in MetadataField::ALL {
+            Label::new(cx, field.name())
+                .top(Stretch(1.0))
+                .bottom(Stretch(1.0));
+            let text =
+                AppData::screen.map(move |screen| screen.ruleset().metadata.get(field).to_owned());
+            // Notes are free-form, so they get the room to span several lines.
+            if field == MetadataField::Notes {
+                Textbox::new_multiline(cx, text, true).width(Stretch(1.0))
+            } else {
+                Textbox::new(cx, text).width(Pixels(150.0))
+            }
+            .on_submit(move |cx, text, _| cx.emit(RulesetEvent::MetadataEdited(field, text)))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        }
+    })
+    .col_between(Pixels(5.0))
+    .height(Auto)
+    .display(AppData::metadata_shown);
+}
+
+fn overwrite_prompt(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Label::new(
+            cx,
+            AppData::pending_overwrite
+                .map(|name| format!("Overwrite '{}'?", name.as_deref().unwrap_or_default())),
+        )
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Overwrite"))
+            .on_press(|cx| cx.emit(RulesetEvent::OverwriteConfirmed));
+        Button::new(cx, |cx| Label::new(cx, "Cancel"))
+            .on_press(|cx| cx.emit(RulesetEvent::OverwriteCancelled));
+    })
+    .col_between(Pixels(5.0))
+    .width(Auto)
+    .height(Auto)
+    .top(Stretch(1.0))
+    .bottom(Stretch(1.0))
+    .display(AppData::pending_overwrite.map(Option::is_some));
+}
+
 fn tabs(cx: &mut Context) {
     HStack::new(cx, |cx| {
-        Button::new(cx, |cx| Label::new(cx, "Materials"))
-            .on_press(|cx| cx.emit(EditorEvent::TabSwitched(EditorTab::Materials)))
-            .toggle_class(
-                style::PRESSED_BUTTON,
-                AppData::selected_tab.map(|&tab| tab == EditorTab::Materials),
-            )
-            .width(Stretch(1.0))
-            .text_align(TextAlign::Center)
-            .child_space(Stretch(1.0));
-        Button::new(cx, |cx| Label::new(cx, "Rules"))
-            .on_press(|cx| cx.emit(EditorEvent::TabSwitched(EditorTab::Rules)))
-            .toggle_class(
-                style::PRESSED_BUTTON,
-                AppData::selected_tab.map(|&tab| tab == EditorTab::Rules),
-            )
-            .width(Stretch(1.0))
-            .text_align(TextAlign::Center)
-            .child_space(Stretch(1.0));
+        for tab in EditorTab::ALL {
+            Button::new(cx, move |cx| Label::new(cx, tab.name()))
+                .on_press(move |cx| cx.emit(EditorEvent::TabSwitched(tab)))
+                .toggle_class(
+                    style::PRESSED_BUTTON,
+                    AppData::selected_tab.map(move |&selected| selected == tab),
+                )
+                .width(Stretch(1.0))
+                .text_align(TextAlign::Center)
+                .child_space(Stretch(1.0));
+        }
     })
     .height(Auto);
 }
 
 fn material_editor(cx: &mut Context) {
     VStack::new(cx, |cx| {
+        deletion_prompt(cx);
         ScrollView::new(cx, 0.0, 0.0, true, true, move |cx| {
             Binding::new(cx, AppData::screen, |cx, screen| {
                 let screen = screen.get(cx);
@@ -124,10 +495,52 @@ fn material_editor(cx: &mut Context) {
             .width(Stretch(1.0))
             .text_align(TextAlign::Center)
             .child_space(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Generate Distinct Palette"))
+            .on_press(|cx| cx.emit(MaterialEvent::PaletteGenerated))
+            .width(Stretch(1.0))
+            .text_align(TextAlign::Center)
+            .child_space(Stretch(1.0));
     })
     .class(style::EDITOR_PANEL);
 }
 
+fn deletion_prompt(cx: &mut Context) {
+    VStack::new(cx, |cx| {
+        Label::new(
+            cx,
+            AppData::pending_deletion.map(|pending| {
+                pending
+                    .as_ref()
+                    .map_or_else(String::new, |(_, description)| description.clone())
+            }),
+        );
+        HStack::new(cx, |cx| {
+            Button::new(cx, |cx| Label::new(cx, "Remap to default and delete"))
+                .on_press(|cx| cx.emit(MaterialEvent::DeletionConfirmed));
+            Button::new(cx, |cx| Label::new(cx, "Cancel"))
+                .on_press(|cx| cx.emit(MaterialEvent::DeletionCancelled));
+        })
+        .col_between(Pixels(5.0))
+        .height(Auto);
+    })
+    .class(style::BASE_EDITOR)
+    .display(AppData::pending_deletion.map(Option::is_some));
+}
+/// Shows `normal` on the delete button for `target`, or `armed` once a first press has armed it.
+pub fn deletion_look(
+    target: DeletionTarget,
+    normal: &'static str,
+    armed: &'static str,
+) -> impl Lens<Target = &'static str> {
+    AppData::armed_deletion.map(move |armed_target| {
+        if *armed_target == Some(target) {
+            armed
+        } else {
+            normal
+        }
+    })
+}
+
 fn group_editor(cx: &mut Context) {
     VStack::new(cx, |cx| {
         ScrollView::new(cx, 0.0, 0.0, true, true, move |cx| {
@@ -156,8 +569,18 @@ fn rule_editor(cx: &mut Context) {
         ScrollView::new(cx, 0.0, 0.0, true, true, |cx| {
             Binding::new(cx, AppData::screen, |cx, screen| {
                 VStack::new(cx, move |cx| {
-                    for (index, rule) in screen.get(cx).ruleset().rules.iter().enumerate() {
-                        rule.display_editor(cx, index.into());
+                    let screen = screen.get(cx);
+                    let ruleset = screen.ruleset();
+                    let issues = ruleset.validate();
+                    let shadowed = ruleset.find_shadowed_rules();
+                    validation_panel(cx, &issues);
+                    for (index, rule) in ruleset.rules.iter().enumerate() {
+                        let invalid = issues.iter().any(|issue| issue.rule() == Some(index));
+                        let shadowed_by = shadowed
+                            .iter()
+                            .find(|&&(later, _)| later == index)
+                            .map(|&(_, earlier)| ruleset.rules[earlier].title(earlier));
+                        rule.display_editor(cx, index.into(), invalid, shadowed_by);
                     }
                 })
                 .row_between(Pixels(5.0))
@@ -174,38 +597,265 @@ fn rule_editor(cx: &mut Context) {
     .class(style::EDITOR_PANEL);
 }
 
+fn validation_panel(cx: &mut Context, issues: &[ValidationIssue]) {
+    if issues.is_empty() {
+        return;
+    }
+    let report = issues
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    VStack::new(cx, move |cx| {
+        Label::new(cx, report).class(style::ERROR_LABEL);
+        Button::new(cx, |cx| Label::new(cx, "Remap to default material"))
+            .on_press(|cx| cx.emit(RulesetEvent::ReferencesFixed));
+    })
+    .class(style::BASE_EDITOR)
+    .width(Percentage(50.0));
+}
+
 pub fn game_board(cx: &mut Context) {
-    HStack::new(cx, |cx| {
-        left_panel(cx);
-        center_panel(cx);
-        right_panel(cx);
-    })
-    .on_geo_changed(|cx, changes| {
-        if changes.contains(GeoChanged::WIDTH_CHANGED)
-            || changes.contains(GeoChanged::HEIGHT_CHANGED)
-        {
-            cx.emit(UpdateEvent::WindowSizeChanged);
-        }
+    VStack::new(cx, |cx| {
+        HStack::new(cx, |cx| {
+            left_panel(cx);
+            center_panel(cx);
+            right_panel(cx);
+        })
+        .on_geo_changed(|cx, changes| {
+            if changes.contains(GeoChanged::WIDTH_CHANGED)
+                || changes.contains(GeoChanged::HEIGHT_CHANGED)
+            {
+                cx.emit(UpdateEvent::WindowSizeChanged);
+            }
+        });
+        status_bar(cx);
     })
     .class(style::BACKGROUND);
 }
 
+/// A strip along the bottom of the game board summarizing what painting would do.
+fn status_bar(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Binding::new(cx, AppData::selected_material, |cx, id| {
+            let id = id.get(cx);
+            Element::new(cx)
+                .background_color(AppData::screen.map(move |screen| {
+                    let material = screen.ruleset().materials.get(id);
+                    Color::from(material.map_or(MaterialColor::NEUTRAL, |material| material.color))
+                }))
+                .class(style::SWATCH);
+            Label::new(
+                cx,
+                AppData::screen.map(move |screen| material_name(screen.ruleset(), id).to_owned()),
+            )
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        });
+        Binding::new(cx, AppData::secondary_material, |cx, id| {
+            let id = id.get(cx);
+            Label::new(cx, "Right-click:")
+                .top(Stretch(1.0))
+                .bottom(Stretch(1.0));
+            Element::new(cx)
+                .background_color(AppData::screen.map(move |screen| {
+                    Color::from(screen.ruleset().materials.get_or_default(id).color)
+                }))
+                .class(style::SWATCH);
+            Label::new(
+                cx,
+                AppData::screen
+                    .map(move |screen| screen.ruleset().materials.get_or_default(id).name.clone()),
+            )
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        });
+        Label::new(
+            cx,
+            AppData::paint_tool.map(|tool| format!("Tool: {}", tool.name())),
+        )
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Label::new(
+            cx,
+            AppData::screen.map(|screen| match screen {
+                Screen::Grid(grid) => format!("Grid: {0}x{0}", grid.size),
+                Screen::Editor(_) => String::new(),
+            }),
+        )
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+    })
+    .class(style::STATUS_BAR);
+}
+
 fn left_panel(cx: &mut Context) {
     VStack::new(cx, |cx| {
         editor_button(cx);
+        tool_controls(cx);
+        symmetry_controls(cx);
+        selection_controls(cx);
+        preset_controls(cx);
         step_controls(cx);
+        timeline_controls(cx);
+        transform_controls(cx);
         speed_controls(cx);
         size_controls(cx);
+        gradient_controls(cx);
+        seed_controls(cx);
         savestate_controls(cx);
+        comparison_controls(cx);
+        image_controls(cx);
+        period_display(cx);
+        activity_controls(cx);
+        population_graph(cx);
         Element::new(cx).height(Stretch(5.0));
     })
-    .class(style::SIDE_PANEL);
+    .class(style::SIDE_PANEL);
+}
+
+fn editor_button(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Button::new(cx, |cx| Label::new(cx, "Edit Ruleset"))
+            .on_press(|cx| cx.emit(EditorEvent::Enabled));
+    })
+    .class(style::MENU_ELEMENT);
+}
+fn tool_controls(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Button::new(cx, |cx| Label::new(cx, "Brush"))
+            .on_press(|cx| cx.emit(UpdateEvent::ToolSelected(PaintTool::Brush)))
+            .toggle_class(
+                style::PRESSED_BUTTON,
+                AppData::paint_tool.map(|&tool| tool == PaintTool::Brush),
+            )
+            .class(style::CONTROL_BUTTON);
+        Button::new(cx, |cx| Label::new(cx, "Eyedropper (Alt)"))
+            .on_press(|cx| cx.emit(UpdateEvent::ToolSelected(PaintTool::Eyedropper)))
+            .toggle_class(
+                style::PRESSED_BUTTON,
+                AppData::paint_tool.map(|&tool| tool == PaintTool::Eyedropper),
+            )
+            .class(style::CONTROL_BUTTON);
+        Button::new(cx, |cx| Label::new(cx, "Stamp"))
+            .on_press(|cx| cx.emit(UpdateEvent::ToolSelected(PaintTool::Stamp)))
+            .toggle_class(
+                style::PRESSED_BUTTON,
+                AppData::paint_tool.map(|&tool| tool == PaintTool::Stamp),
+            )
+            .class(style::CONTROL_BUTTON);
+        Button::new(cx, |cx| Label::new(cx, "Select"))
+            .on_press(|cx| cx.emit(UpdateEvent::ToolSelected(PaintTool::Select)))
+            .toggle_class(
+                style::PRESSED_BUTTON,
+                AppData::paint_tool.map(|&tool| tool == PaintTool::Select),
+            )
+            .class(style::CONTROL_BUTTON);
+        Button::new(cx, |cx| Label::new(cx, "Paste"))
+            .on_press(|cx| cx.emit(UpdateEvent::ToolSelected(PaintTool::Paste)))
+            .toggle_class(
+                style::PRESSED_BUTTON,
+                AppData::paint_tool.map(|&tool| tool == PaintTool::Paste),
+            )
+            .disabled(AppData::clipboard.map(Option::is_none))
+            .class(style::CONTROL_BUTTON);
+    })
+    .class(style::MENU_ELEMENT);
+}
+fn symmetry_controls(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Label::new(cx, "Symmetry:")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        ComboBox::new(
+            cx,
+            AppData::symmetry.map(|_| SymmetryMode::names()),
+            AppData::symmetry.map(|mode| mode.index()),
+        )
+        .on_select(|cx, index| {
+            if let Some(&mode) = SymmetryMode::ALL.get(index) {
+                cx.emit(UpdateEvent::SymmetrySelected(mode));
+            }
+        })
+        .width(Stretch(1.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+    })
+    .col_between(Pixels(5.0))
+    .class(style::MENU_ELEMENT);
+}
+fn selection_controls(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Label::new(
+            cx,
+            AppData::selection.map(|selection| {
+                selection.map_or_else(
+                    || String::from("No selection"),
+                    |rect| format!("{}x{} at ({}, {})", rect.width, rect.height, rect.x, rect.y),
+                )
+            }),
+        )
+        .width(Stretch(1.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Randomize"))
+            .on_press(|cx| {
+                let rect = AppData::selection.get(cx);
+                if let Some(rect) = rect {
+                    cx.emit(GridEvent::RandomizedRegion(rect));
+                }
+            })
+            .disabled(AppData::selection.map(Option::is_none))
+            .class(style::CONTROL_BUTTON);
+        Button::new(cx, |cx| Label::new(cx, "Step"))
+            .on_press(|cx| {
+                let rect = AppData::selection.get(cx);
+                if let Some(rect) = rect {
+                    cx.emit(GridEvent::RegionStepped(rect));
+                }
+            })
+            .disabled(AppData::selection.map(Option::is_none))
+            .class(style::CONTROL_BUTTON)
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(cx, "Advance only the selected cells a generation");
+                })
+            });
+        Button::new(cx, |cx| Label::new(cx, "Copy"))
+            .on_press(|cx| cx.emit(GridEvent::SelectionCopied))
+            .disabled(AppData::selection.map(Option::is_none))
+            .class(style::CONTROL_BUTTON);
+    })
+    .class(style::MENU_ELEMENT);
 }
-
-fn editor_button(cx: &mut Context) {
+fn preset_controls(cx: &mut Context) {
     HStack::new(cx, |cx| {
-        Button::new(cx, |cx| Label::new(cx, "Edit Ruleset"))
-            .on_press(|cx| cx.emit(EditorEvent::Enabled));
+        ComboBox::new(
+            cx,
+            AppData::selected_preset.map(|_| Preset::names()),
+            AppData::selected_preset.map(|preset| preset.index()),
+        )
+        .on_select(|cx, index| {
+            if let Some(&preset) = Preset::ALL.get(index) {
+                cx.emit(UpdateEvent::PresetSelected(preset));
+            }
+        })
+        .width(Stretch(1.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Rotate"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(UpdateEvent::PresetRotated));
+        Button::new(cx, |cx| Label::new(cx, "Flip"))
+            .class(style::CONTROL_BUTTON)
+            .toggle_class(
+                style::PRESSED_BUTTON,
+                AppData::preset_orientation.map(|orientation| orientation.flipped),
+            )
+            .on_press(|cx| cx.emit(UpdateEvent::PresetFlipped));
+        Button::new(cx, |cx| Label::new(cx, "Place"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::PresetPlaced));
     })
     .class(style::MENU_ELEMENT);
 }
@@ -222,18 +872,113 @@ fn step_controls(cx: &mut Context) {
         Button::new(cx, |cx| Label::new(cx, "Step"))
             .on_press(|cx| cx.emit(GridEvent::Stepped))
             .class(style::CONTROL_BUTTON);
+        Button::new(cx, |cx| Label::new(cx, "Clear"))
+            .on_press(|cx| cx.emit(GridEvent::Cleared))
+            .class(style::CONTROL_BUTTON);
+        Button::new(cx, |cx| Label::new(cx, "Auto Pause"))
+            .on_press(|cx| cx.emit(GridEvent::FocusPauseToggled))
+            .toggle_class(style::PRESSED_BUTTON, AppData::pause_unfocused)
+            .class(style::CONTROL_BUTTON)
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(cx, "Pause while the window is in the background");
+                })
+            });
+    })
+    .tooltip(|cx| {
+        Tooltip::new(cx, |cx| {
+            Label::new(
+                cx,
+                "Space: start/stop\nRight or .: step\nR: clear\n1-9: select material\n\
+                 Ctrl+C/Ctrl+V: copy selection/paste\nH/V: flip horizontally/vertically\n\
+                 [/]: rotate counterclockwise/clockwise",
+            );
+        })
+    })
+    .class(style::MENU_ELEMENT);
+}
+/// Scrubs through the generations kept in the timeline, pausing the run.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn timeline_controls(cx: &mut Context) {
+    // The slider's range is fixed when built, so it's rebuilt as the timeline fills.
+    Binding::new(
+        cx,
+        AppData::timeline.map(|timeline| timeline.len()),
+        |cx, length| {
+            let length = length.get(cx);
+            HStack::new(cx, move |cx| {
+                Label::new(cx, "Timeline: ")
+                    .top(Stretch(1.0))
+                    .bottom(Stretch(1.0));
+                Slider::new(
+                    cx,
+                    AppData::timeline_position.map(|&position| position as f32),
+                )
+                .top(Stretch(1.0))
+                .bottom(Stretch(1.0))
+                .space(Stretch(0.05))
+                .range(0.0..length.saturating_sub(1).max(1) as f32)
+                .on_changing(|cx, progress| {
+                    cx.emit(GridEvent::TimelineScrubbed(
+                        progress.round().max(0.0) as usize
+                    ));
+                });
+                Label::new(
+                    cx,
+                    AppData::timeline_position.map(move |&position| {
+                        if length == 0 {
+                            String::from("0/0")
+                        } else {
+                            format!("{}/{length}", position + 1)
+                        }
+                    }),
+                )
+                .width(Pixels(60.0))
+                .top(Stretch(1.0))
+                .bottom(Stretch(1.0));
+            })
+            .class(style::MENU_ELEMENT);
+        },
+    );
+}
+fn transform_controls(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Button::new(cx, |cx| Label::new(cx, "Flip H"))
+            .on_press(|cx| cx.emit(GridEvent::FlippedHorizontal))
+            .class(style::CONTROL_BUTTON);
+        Button::new(cx, |cx| Label::new(cx, "Flip V"))
+            .on_press(|cx| cx.emit(GridEvent::FlippedVertical))
+            .class(style::CONTROL_BUTTON);
+        Button::new(cx, |cx| Label::new(cx, "Rotate Left"))
+            .on_press(|cx| cx.emit(GridEvent::RotatedCounterclockwise))
+            .class(style::CONTROL_BUTTON);
+        Button::new(cx, |cx| Label::new(cx, "Rotate Right"))
+            .on_press(|cx| cx.emit(GridEvent::RotatedClockwise))
+            .class(style::CONTROL_BUTTON);
+    })
+    .tooltip(|cx| {
+        Tooltip::new(cx, |cx| {
+            Label::new(
+                cx,
+                "H: flip horizontally\nV: flip vertically\n[ and ]: rotate",
+            );
+        })
     })
     .class(style::MENU_ELEMENT);
 }
 fn speed_controls(cx: &mut Context) {
     HStack::new(cx, |cx: &mut Context| {
-        Slider::new(cx, AppData::speed.map(|speed| 0_f32.max(*speed).min(1.0)))
+        Slider::new(cx, AppData::speed.map(|speed| speed.clamp(1.0, 60.0)))
             .top(Stretch(1.0))
             .bottom(Stretch(1.0))
             .space(Stretch(0.05))
-            .range(0.01..1.0)
+            .range(1.0..60.0)
             .on_changing(|cx, progress| cx.emit(GridEvent::SpeedSet(progress)));
-        Textbox::new(cx, AppData::speed.map(|speed| format!("{speed:.2}")))
+        Textbox::new(cx, AppData::speed.map(|speed| format!("{speed:.1}")))
             .top(Stretch(1.0))
             .bottom(Stretch(1.0))
             .space(Stretch(0.05))
@@ -242,8 +987,30 @@ fn speed_controls(cx: &mut Context) {
                     cx.emit(GridEvent::SpeedSet(speed));
                 }
             });
+        Label::new(cx, "gen/s")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Smooth"))
+            .on_press(|cx| cx.emit(GridEvent::InterpolationToggled))
+            .toggle_class(style::PRESSED_BUTTON, AppData::interpolation)
+            .class(style::CONTROL_BUTTON)
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(cx, "Blend colors between generations while running");
+                })
+            });
     })
     .class(style::MENU_ELEMENT);
+    Label::new(
+        cx,
+        AppData::step_rate.map(|rate| format!("Actual: {rate:.1} gen/s")),
+    )
+    .display(AppData::running)
+    .class(style::MENU_ELEMENT);
+    Label::new(cx, "Steps take longer than the set speed allows")
+        .display(AppData::step_limited)
+        .class(style::WARNING_LABEL)
+        .class(style::MENU_ELEMENT);
 }
 fn size_controls(cx: &mut Context) {
     HStack::new(cx, |cx| {
@@ -257,7 +1024,92 @@ fn size_controls(cx: &mut Context) {
                 }
             },
         );
+        Label::new(cx, "Boundary: ");
+        ComboBox::new(
+            cx,
+            AppData::boundary.map(|_| Boundary::names()),
+            AppData::boundary.map(|boundary| boundary.index()),
+        )
+        .on_select(|cx, index| {
+            if let Some(&boundary) = Boundary::ALL.get(index) {
+                cx.emit(GridEvent::BoundarySelected(boundary));
+            }
+        })
+        .width(Stretch(1.0));
+    })
+    .class(style::MENU_ELEMENT);
+}
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn gradient_controls(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Label::new(cx, "Cell Gradient: ")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Slider::new(cx, AppData::cell_gradient.map(|&darken| f32::from(darken)))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0))
+            .space(Stretch(0.05))
+            .range(0.0..255.0)
+            .on_changing(|cx, progress| {
+                let darken = progress.round().clamp(0.0, 255.0) as u8;
+                cx.emit(UpdateEvent::CellGradientSet(darken));
+            });
+        Label::new(
+            cx,
+            AppData::cell_gradient.map(|&darken| {
+                if darken == 0 {
+                    String::from("Flat")
+                } else {
+                    darken.to_string()
+                }
+            }),
+        )
+        .width(Pixels(40.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Fill"))
+            .class(style::CONTROL_BUTTON)
+            .toggle_class(style::PRESSED_BUTTON, AppData::fill_panel)
+            .on_press(|cx| cx.emit(UpdateEvent::GridFillToggled))
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(
+                        cx,
+                        "Stretch the grid to fill its panel instead of keeping cells square",
+                    );
+                })
+            });
+    })
+    .class(style::MENU_ELEMENT);
+}
+fn seed_controls(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Label::new(cx, "Seed: ")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Textbox::new(cx, AppData::seed.map(ToString::to_string))
+            .on_submit(|cx, text, enter_pressed| {
+                if enter_pressed {
+                    match text.trim().parse() {
+                        Ok(seed) => cx.emit(GridEvent::SeedSet(seed)),
+                        Err(err) => {
+                            cx.emit(UpdateEvent::error(format!("Invalid seed '{text}': {err}")));
+                        }
+                    }
+                }
+            })
+            .width(Stretch(1.0))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "New Seed"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::SeedRerolled));
+        Button::new(cx, |cx| Label::new(cx, "Record"))
+            .class(style::CONTROL_BUTTON)
+            .toggle_class(style::PRESSED_BUTTON, AppData::record_seed)
+            .on_press(|cx| cx.emit(GridEvent::SeedRecordingToggled));
     })
+    .col_between(Pixels(5.0))
     .class(style::MENU_ELEMENT);
 }
 fn savestate_controls(cx: &mut Context) {
@@ -269,7 +1121,213 @@ fn savestate_controls(cx: &mut Context) {
             .class(style::CONTROL_BUTTON)
             .on_press(|cx| cx.emit(GridEvent::StateLoaded))
             .disabled(AppData::saved_state.map(Option::is_none));
+        Button::new(cx, |cx| Label::new(cx, "Keep Size"))
+            .class(style::CONTROL_BUTTON)
+            .toggle_class(style::PRESSED_BUTTON, AppData::clip_loaded_states)
+            .on_press(|cx| cx.emit(GridEvent::StateClipToggled))
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(cx, "Clip or pad loaded states to the current grid size");
+                })
+            });
+    })
+    .class(style::MENU_ELEMENT);
+    HStack::new(cx, |cx| {
+        Textbox::new(cx, AppData::state_file_name)
+            .on_submit(|cx, text, _| cx.emit(GridEvent::StateFileNamed(text)))
+            .width(Stretch(1.0))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Save File"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::StateWritten));
+        Button::new(cx, |cx| Label::new(cx, "Load File"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::StateRead));
+    })
+    .class(style::MENU_ELEMENT);
+    HStack::new(cx, |cx| {
+        Button::new(cx, |cx| Label::new(cx, "Export CSV"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::CsvExported));
+        Button::new(cx, |cx| Label::new(cx, "Import CSV"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::CsvImported));
+    })
+    .class(style::MENU_ELEMENT);
+}
+/// Runs the grid under another ruleset alongside its own, exporting where the two drift apart.
+fn comparison_controls(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Label::new(cx, "Compare: ")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        ComboBox::new(
+            cx,
+            AppData::rulesets.map(|rulesets| {
+                rulesets
+                    .iter()
+                    .map(|r| r.name.clone())
+                    .collect::<Vec<String>>()
+            }),
+            AppData::compared_ruleset,
+        )
+        .on_select(|cx, index| cx.emit(GridEvent::ComparedRulesetSelected(index)))
+        .width(Stretch(1.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+    })
+    .col_between(Pixels(5.0))
+    .class(style::MENU_ELEMENT);
+    HStack::new(cx, |cx| {
+        Label::new(cx, "Generations: ")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Textbox::new(cx, AppData::comparison_length.map(ToString::to_string))
+            .on_submit(|cx, text, enter_pressed| {
+                if enter_pressed {
+                    match text.trim().parse() {
+                        Ok(length) => cx.emit(GridEvent::ComparisonLengthSet(length)),
+                        Err(err) => cx.emit(UpdateEvent::error(format!(
+                            "Invalid generation count '{text}': {err}"
+                        ))),
+                    }
+                }
+            })
+            .width(Stretch(1.0))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Compare"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::RulesetsCompared))
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(
+                        cx,
+                        "Exports how many cells differ after each generation as CSV.",
+                    );
+                })
+            });
+    })
+    .col_between(Pixels(5.0))
+    .class(style::MENU_ELEMENT);
+}
+fn image_controls(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Textbox::new(cx, AppData::image_path)
+            .on_submit(|cx, text, _| cx.emit(GridEvent::ImagePathSet(text)))
+            .width(Stretch(1.0))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Import Image"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::ImageImported));
+    })
+    .class(style::MENU_ELEMENT);
+    HStack::new(cx, |cx| {
+        Button::new(cx, |cx| Label::new(cx, "Apply Image"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::ImageApplied));
+        Button::new(cx, |cx| Label::new(cx, "Discard Image"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::ImageDiscarded));
+    })
+    .class(style::MENU_ELEMENT)
+    .display(AppData::image_backup.map(Option::is_some));
+}
+fn period_display(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Label::new(
+            cx,
+            AppData::screen.map(|screen| match screen {
+                Screen::Grid(grid) => {
+                    let period = grid.period().map_or_else(
+                        || String::from("Period: none"),
+                        |period| format!("Period: {period} (detected)"),
+                    );
+                    format!("Generation {}\n{period}", grid.generation())
+                }
+                Screen::Editor(_) => String::new(),
+            }),
+        )
+        .width(Stretch(1.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Button::new(cx, |cx| Label::new(cx, "Reset"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::PeriodReset));
+    })
+    .class(style::MENU_ELEMENT);
+}
+
+fn activity_controls(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        Button::new(cx, |cx| Label::new(cx, "Heatmap"))
+            .class(style::CONTROL_BUTTON)
+            .toggle_class(style::PRESSED_BUTTON, AppData::heatmap_enabled)
+            .on_press(|cx| cx.emit(GridEvent::HeatmapToggled));
+        Label::new(cx, "Decay %")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0));
+        Textbox::new(
+            cx,
+            AppData::screen.map(|screen| match screen {
+                Screen::Grid(grid) => grid.activity_decay().to_string(),
+                Screen::Editor(_) => String::new(),
+            }),
+        )
+        .width(Pixels(40.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .on_submit(|cx, text, _| {
+            if let Ok(decay) = text.parse() {
+                cx.emit(GridEvent::ActivityDecaySet(decay));
+            }
+        });
+        Button::new(cx, |cx| Label::new(cx, "Reset"))
+            .class(style::CONTROL_BUTTON)
+            .on_press(|cx| cx.emit(GridEvent::ActivityReset));
+    })
+    .col_between(Pixels(5.0))
+    .class(style::MENU_ELEMENT);
+}
+
+fn population_graph(cx: &mut Context) {
+    VStack::new(cx, |cx| {
+        PopulationGraph::new(
+            cx,
+            AppData::screen.map(|screen| match screen {
+                Screen::Grid(grid) => PopulationPlot::new(grid),
+                Screen::Editor(_) => PopulationPlot::default(),
+            }),
+            AppData::hidden_plots,
+        )
+        .class(style::POPULATION_GRAPH);
+        HStack::new(cx, |cx| {
+            Binding::new(
+                cx,
+                AppData::screen.map(|s| s.ruleset().clone()),
+                |cx, ruleset| {
+                    for material in ruleset.get(cx).materials.iter() {
+                        let id = material.id();
+                        let name = material.name.clone();
+                        Button::new(cx, move |cx| Label::new(cx, name))
+                            .on_press(move |cx| cx.emit(GridEvent::PlotToggled(id)))
+                            .toggle_class(
+                                style::PRESSED_BUTTON,
+                                AppData::hidden_plots.map(move |hidden| !hidden.contains(&id)),
+                            );
+                    }
+                },
+            );
+            Button::new(cx, |cx| Label::new(cx, "Clear"))
+                .class(style::CONTROL_BUTTON)
+                .on_press(|cx| cx.emit(GridEvent::PopulationCleared));
+        })
+        .col_between(Pixels(5.0))
+        .height(Auto);
     })
+    .row_between(Pixels(5.0))
     .class(style::MENU_ELEMENT);
 }
 
@@ -285,6 +1343,10 @@ fn center_panel(cx: &mut Context) {
                 }
             }),
             AppData::hovered_index,
+            AppData::transition,
+            AppData::heatmap_enabled,
+            AppData::selection,
+            AppData::fill_panel,
         )
         .size(Stretch(1.0))
         .background_color(Color::rgba(255, 0, 0, 128));
@@ -299,20 +1361,39 @@ fn right_panel(cx: &mut Context) {
     ZStack::new(cx, |cx| {
         ScrollView::new(cx, 0., 0., true, true, |cx| {
             VStack::new(cx, |cx| {
-                Binding::new(cx, AppData::screen, |cx, screen| {
-                    let Screen::Grid(grid) = screen.get(cx) else {
-                        return;
-                    };
-                    let ruleset = grid.ruleset;
-                    let cells: Vec<Cell> = ruleset
-                        .materials
-                        .iter()
-                        .map(|material| Cell::new(material.id()))
-                        .collect();
-                    cells.chunks(style::MATERIAL_ROW_LENGTH).for_each(|chunk| {
-                        material_row(cx, chunk, &ruleset);
+                // Cells only read the gradient setting when they're built.
+                Binding::new(cx, AppData::cell_gradient, |cx, _| {
+                    Binding::new(cx, AppData::screen, |cx, screen| {
+                        let Screen::Grid(grid) = screen.get(cx) else {
+                            return;
+                        };
+                        let ruleset = grid.ruleset;
+                        let cells: Vec<Cell> = ruleset
+                            .materials
+                            .iter()
+                            .map(|material| Cell::new(material.id()))
+                            .collect();
+                        cells.chunks(style::MATERIAL_ROW_LENGTH).for_each(|chunk| {
+                            material_row(cx, chunk, &ruleset);
+                        });
                     });
                 });
+                Label::new(cx, AppData::tooltip)
+                    .display(AppData::tooltip.map(|tooltip| !tooltip.is_empty()))
+                    .class(style::MENU_ELEMENT);
+                HStack::new(cx, |cx| {
+                    Button::new(cx, |cx| Label::new(cx, "Inspect Cells"))
+                        .on_press(|cx| cx.emit(UpdateEvent::InspectorToggled))
+                        .toggle_class(style::PRESSED_BUTTON, AppData::inspector_enabled)
+                        .class(style::CONTROL_BUTTON);
+                    Button::new(cx, |cx| Label::new(cx, "Rule Counts"))
+                        .on_press(|cx| cx.emit(UpdateEvent::RuleCountsToggled))
+                        .toggle_class(style::PRESSED_BUTTON, AppData::rule_counts_enabled)
+                        .class(style::CONTROL_BUTTON);
+                })
+                .class(style::MENU_ELEMENT);
+                rule_counts(cx);
+                cell_inspector(cx);
             })
             .min_size(Auto);
         });
@@ -320,14 +1401,142 @@ fn right_panel(cx: &mut Context) {
     .class(style::SIDE_PANEL);
 }
 
+/// Lists how many cells each rule fired on last generation, so rules that never fire stand out.
+fn rule_counts(cx: &mut Context) {
+    Label::new(
+        cx,
+        AppData::screen.map(|screen| {
+            let Screen::Grid(grid) = screen else {
+                return String::new();
+            };
+            let counts = grid.rule_counts();
+            let lines: Vec<String> = grid
+                .ruleset
+                .rules
+                .iter()
+                .enumerate()
+                .map(|(index, rule)| {
+                    let count = counts
+                        .get(index)
+                        .map_or_else(|| String::from("-"), usize::to_string);
+                    format!("{}: {count}", rule.title(index))
+                })
+                .collect();
+            if lines.is_empty() {
+                String::from("The ruleset has no rules.")
+            } else {
+                lines.join("\n")
+            }
+        }),
+    )
+    .display(AppData::rule_counts_enabled)
+    .class(style::MENU_ELEMENT);
+}
+
+/// Shows the hovered cell, its neighbors, and every rule that matches it.
+fn cell_inspector(cx: &mut Context) {
+    Binding::new(cx, AppData::inspector_enabled, |cx, enabled| {
+        if !enabled.get(cx) {
+            return;
+        }
+        Binding::new(cx, AppData::cell_gradient, |cx, _| {
+            cell_inspector_contents(cx);
+        });
+    });
+}
+/// The inspector for the hovered cell, rebuilt as it or the grid changes.
+fn cell_inspector_contents(cx: &mut Context) {
+    Binding::new(cx, AppData::hovered_index, |cx, index| {
+        let Some(index) = index.get(cx) else {
+            Label::new(cx, "Hover over a cell to inspect it.");
+            return;
+        };
+        Binding::new(cx, AppData::screen, move |cx, screen| {
+            let Screen::Grid(grid) = screen.get(cx) else {
+                return;
+            };
+            let Some(explanation) = grid.explain(index) else {
+                return;
+            };
+            let ruleset = &grid.ruleset;
+            let (x, y) = grid.cell_coordinates(index);
+            VStack::new(cx, |cx| {
+                Label::new(
+                    cx,
+                    format!(
+                        "({x}, {y}): {}",
+                        material_name(ruleset, explanation.cell.material_id)
+                    ),
+                );
+                let mut neighborhood = explanation.neighbors.clone();
+                neighborhood.insert(4, Some(explanation.cell));
+                for row in neighborhood.chunks(3) {
+                    HStack::new(cx, |cx| {
+                        for cell in row {
+                            match cell {
+                                Some(cell) => {
+                                    cell.display(cx, ruleset).class(style::MATERIAL_DISPLAY);
+                                }
+                                None => {
+                                    Element::new(cx).class(style::MATERIAL_DISPLAY);
+                                }
+                            }
+                        }
+                    })
+                    .class(style::MATERIAL_ROW);
+                }
+                if explanation.matches.is_empty() {
+                    Label::new(cx, "No rules match.");
+                } else if explanation.applied.is_none() {
+                    Label::new(cx, "One of these is chosen at random.");
+                }
+                for (position, (rule_index, transformation)) in
+                    explanation.matches.iter().enumerate()
+                {
+                    let output = match *transformation {
+                        Transformation::Replace(cell) => {
+                            material_name(ruleset, cell.material_id).to_owned()
+                        }
+                        Transformation::Swap(target) => {
+                            let (x, y) = grid.cell_coordinates(target);
+                            format!("swap with ({x}, {y})")
+                        }
+                    };
+                    let title = ruleset.rules[*rule_index].title(*rule_index);
+                    let applied = if explanation.applied == Some(position) {
+                        " (applied)"
+                    } else {
+                        ""
+                    };
+                    Label::new(cx, format!("{title} -> {output}{applied}"));
+                }
+            })
+            .height(Auto)
+            .class(style::MENU_ELEMENT);
+        });
+    });
+}
+
+fn material_name(ruleset: &Ruleset, id: MaterialId) -> &str {
+    ruleset
+        .materials
+        .get(id)
+        .map_or("Unknown material", |material| material.name.as_str())
+}
+
 fn material_row(cx: &mut Context, row: &[Cell], ruleset: &Ruleset) {
     HStack::new(cx, |cx| {
         for &cell in row {
-            let border_color = border_color(cell.color(ruleset).to_rgba());
+            let border_color = border_color(RGBA::from(cell.color(ruleset)));
             cell.display(cx, ruleset)
                 .on_press(move |cx| {
                     cx.emit(UpdateEvent::MaterialSelected(cell.material_id));
                 })
+                .on_mouse_down(move |cx, button| {
+                    if button == MouseButton::Right {
+                        cx.emit(UpdateEvent::SecondaryMaterialSelected(cell.material_id));
+                    }
+                })
                 .border_color(AppData::selected_material.map(move |id| {
                     if *id == cell.material_id {
                         border_color
@@ -355,15 +1564,18 @@ fn border_color(color: RGBA) -> Color {
     }
 }
 
-pub fn rect_bounds(bounds: &BoundingBox) -> BoundingBox {
-    let target_size = bounds.width().min(bounds.height());
-    let left = (bounds.width() / 2.0) - (target_size / 2.0) + bounds.left();
-    let top = (bounds.height() / 2.0) - (target_size / 2.0) + bounds.top();
+/// The largest rectangle with `aspect_ratio` (width over height) that fits in `bounds`,
+/// centered within them.
+pub fn rect_bounds(bounds: &BoundingBox, aspect_ratio: f32) -> BoundingBox {
+    let width = bounds.width().min(bounds.height() * aspect_ratio);
+    let height = width / aspect_ratio;
+    let left = (bounds.width() / 2.0) - (width / 2.0) + bounds.left();
+    let top = (bounds.height() / 2.0) - (height / 2.0) + bounds.top();
     BoundingBox {
         x: left,
         y: top,
-        w: target_size,
-        h: target_size,
+        w: width,
+        h: height,
     }
 }
 
@@ -387,11 +1599,127 @@ impl Screen {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum PaintTool {
+    Brush,
+    /// Selects the material of the clicked cell instead of painting.
+    Eyedropper,
+    /// Places the selected preset centered on the clicked cell.
+    Stamp,
+    /// Drags out a rectangular selection for region commands; right click clears it.
+    Select,
+    /// Pastes the copied cells with their top left corner on the clicked cell.
+    Paste,
+}
+impl PaintTool {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Brush => "Brush",
+            Self::Eyedropper => "Eyedropper",
+            Self::Stamp => "Stamp",
+            Self::Select => "Select",
+            Self::Paste => "Paste",
+        }
+    }
+}
+
+/// Which mirror images of a painted cell are painted along with it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Data)]
+pub enum SymmetryMode {
+    #[default]
+    Off,
+    /// Mirrors across the vertical axis, left to right.
+    Vertical,
+    /// Mirrors across the horizontal axis, top to bottom.
+    Horizontal,
+    /// Mirrors across both axes.
+    Both,
+    /// Repeats each quarter turn about the center.
+    Rotational,
+}
+impl SymmetryMode {
+    pub const ALL: [Self; 5] = [
+        Self::Off,
+        Self::Vertical,
+        Self::Horizontal,
+        Self::Both,
+        Self::Rotational,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Off => "None",
+            Self::Vertical => "Vertical Axis",
+            Self::Horizontal => "Horizontal Axis",
+            Self::Both => "Both Axes",
+            Self::Rotational => "4-Fold Rotation",
+        }
+    }
+    pub fn names() -> Vec<String> {
+        Self::ALL
+            .iter()
+            .map(|mode| mode.name().to_owned())
+            .collect()
+    }
+    pub fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|&mode| mode == self)
+            .unwrap_or_default()
+    }
+
+    /// Every distinct cell painted along with `(x, y)` on a grid of `size` by `size` cells,
+    /// including itself. Cells on an axis are their own mirror image, so they appear only once.
+    pub fn images(self, (x, y): (usize, usize), size: usize) -> Vec<(usize, usize)> {
+        let last = size.saturating_sub(1);
+        let (mirror_x, mirror_y) = (last.saturating_sub(x), last.saturating_sub(y));
+        let mut images = match self {
+            Self::Off => vec![(x, y)],
+            Self::Vertical => vec![(x, y), (mirror_x, y)],
+            Self::Horizontal => vec![(x, y), (x, mirror_y)],
+            Self::Both => vec![(x, y), (mirror_x, y), (x, mirror_y), (mirror_x, mirror_y)],
+            // Clockwise quarter turns about the center of the grid.
+            Self::Rotational => vec![(x, y), (mirror_y, x), (mirror_x, mirror_y), (y, mirror_x)],
+        };
+        images.sort_unstable();
+        images.dedup();
+        images
+    }
+}
+
+impl<T: Identifiable + 'static> Data for UniqueId<T> {
+    fn same(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+impl Data for Preset {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+impl Data for Orientation {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
 pub enum EditorTab {
     Materials,
+    Groups,
     Rules,
 }
+impl EditorTab {
+    pub const ALL: [Self; 3] = [Self::Materials, Self::Groups, Self::Rules];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Materials => "Materials",
+            Self::Groups => "Groups",
+            Self::Rules => "Rules",
+        }
+    }
+}
 
 #[allow(dead_code)]
 pub mod style {
@@ -411,18 +1739,30 @@ pub mod style {
     pub const LIGHT_COMBOBOX: &str = "light-combobox";
     pub const MENU_ELEMENT: &str = "menu-element";
     pub const SVG: &str = "svg";
+    pub const ERROR_LABEL: &str = "error-label";
+    pub const WARNING_LABEL: &str = "warning-label";
+    pub const POPULATION_GRAPH: &str = "population-graph";
+    pub const STATUS_BANNER: &str = "status-banner";
+    pub const STATUS_BAR: &str = "status-bar";
 
     pub const EDITOR_PANEL: &str = "editor-panel";
     pub const BASE_EDITOR: &str = "base-editor";
+    pub const INVALID_EDITOR: &str = "invalid-editor";
+    pub const SHADOWED_EDITOR: &str = "shadowed-editor";
+    /// Outline a textbox whose text, as typed so far, would or wouldn't be accepted.
+    pub const VALID_INPUT: &str = "valid-input";
+    pub const INVALID_INPUT: &str = "invalid-input";
     pub const CONDITION_EDITOR: &str = "condition-editor";
     pub const CONDITION_CONTAINER: &str = "condition-container";
     pub const CONDITION_INVERT_BUTTON: &str = "condition-invert-button";
+    pub const SWATCH: &str = "swatch";
 
     /// The maximum percentage of the screen the center square can take up.
     pub const CENTER_MARGIN_FACTOR: f32 = 0.6;
     /// Mirrors '.backround/child-space' in 'style.css'.
     pub const BACKGROUND_PADDING: f32 = 10.0;
-    /// How much darker the corners of a cell should be compared to the center, as a number from 0-255
+    /// How much darker the corners of a cell are than the center by default, as a number from
+    /// 0-255, with 0 drawing flat cells.
     pub const CELL_GRADIENT_DARKEN: u8 = 92;
     /// How many materials display per row on the right panel.
     pub const MATERIAL_ROW_LENGTH: usize = 3;
@@ -452,9 +1792,46 @@ pub mod style {
         pub const NOT_EQUAL: &str = include_str!("../resources/svg/not_equal.svg");
         pub const LESS: &str = include_str!("../resources/svg/less.svg");
         pub const GREATER: &str = include_str!("../resources/svg/greater.svg");
+        pub const RANGE: &str = include_str!("../resources/svg/range.svg");
+        pub const PERCENT: &str = include_str!("../resources/svg/percent.svg");
 
         #[rustfmt::skip]
         pub const DIRECTIONAL_CONDITION: &str = include_str!("../resources/svg/directional_condition.svg");
         pub const NUMBERIC_CONDITION: &str = include_str!("../resources/svg/numeric_condition.svg");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symmetry_images() {
+        // Off-axis cells on a 5x5 grid get every image.
+        assert_eq!(SymmetryMode::Off.images((1, 0), 5), [(1, 0)]);
+        assert_eq!(SymmetryMode::Vertical.images((1, 0), 5), [(1, 0), (3, 0)]);
+        assert_eq!(SymmetryMode::Horizontal.images((1, 0), 5), [(1, 0), (1, 4)]);
+        assert_eq!(
+            SymmetryMode::Both.images((1, 0), 5),
+            [(1, 0), (1, 4), (3, 0), (3, 4)]
+        );
+        assert_eq!(
+            SymmetryMode::Rotational.images((1, 0), 5),
+            [(0, 3), (1, 0), (3, 4), (4, 1)]
+        );
+
+        // The center column and row are their own mirrors, so nothing is painted twice.
+        assert_eq!(SymmetryMode::Vertical.images((2, 1), 5), [(2, 1)]);
+        assert_eq!(SymmetryMode::Both.images((2, 1), 5), [(2, 1), (2, 3)]);
+        assert_eq!(SymmetryMode::Rotational.images((2, 2), 5), [(2, 2)]);
+
+        // Even sizes have no center cell; every image stays inside the grid.
+        for mode in SymmetryMode::ALL {
+            for position in [(0, 0), (1, 2), (3, 3)] {
+                let images = mode.images(position, 4);
+                assert!(images.contains(&position));
+                assert!(images.iter().all(|&(x, y)| x < 4 && y < 4));
+            }
+        }
+    }
+}
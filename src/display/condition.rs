@@ -0,0 +1,533 @@
+use vizia::prelude::*;
+
+use crate::{
+    condition::{Comparison, Condition, ConditionIndex, ConditionVariant, Direction, Operator},
+    display::{
+        deletion_look,
+        style::{self, svg},
+        Screen,
+    },
+    events::{ConditionEvent, DeletionTarget},
+    pattern::Pattern,
+    AppData,
+};
+
+impl ConditionVariant {
+    fn display_editor(&self, cx: &mut Context, index: ConditionIndex) {
+        match self {
+            Self::Directional(_) => Self::display_directional(cx, index),
+            Self::Count(variant) => Self::display_count(variant, cx, index),
+            Self::Generation { .. } => Self::display_generation(cx, index),
+            Self::Compared { other, .. } => Self::display_compared(*other, cx, index),
+            Self::CountAny {
+                alternatives,
+                counts,
+            } => {
+                Self::display_count(counts, cx, index);
+                Self::display_alternatives(alternatives, cx, index);
+            }
+            Self::OffsetPattern(offsets) => Self::display_offsets(offsets, cx, index),
+        }
+    }
+    fn display_offsets(offsets: &[(i8, i8)], cx: &mut Context, index: ConditionIndex) {
+        VStack::new(cx, |cx| {
+            for offset_index in 0..offsets.len() {
+                HStack::new(cx, |cx| {
+                    Textbox::new(
+                        cx,
+                        AppData::screen.map(move |screen| {
+                            match &index.condition(screen.ruleset()).variant {
+                                Self::OffsetPattern(offsets) => offsets
+                                    .get(offset_index)
+                                    .map(|(x, y)| format!("{x}, {y}"))
+                                    .unwrap_or_default(),
+                                _ => String::new(),
+                            }
+                        }),
+                    )
+                    .on_submit(move |cx, text, _| {
+                        cx.emit(ConditionEvent::OffsetSet(index, offset_index, text));
+                    })
+                    .tooltip(|cx| {
+                        Tooltip::new(cx, |cx| {
+                            Label::new(
+                                cx,
+                                "Cells to the right, then cells down. \
+                                     Negative values go left and up.",
+                            );
+                        })
+                    })
+                    .width(Pixels(60.0));
+                    Button::new(cx, |cx| Label::new(cx, "-"))
+                        .on_press(move |cx| {
+                            cx.emit(ConditionEvent::OffsetRemoved(index, offset_index));
+                        })
+                        .top(Stretch(1.0))
+                        .bottom(Stretch(1.0));
+                })
+                .col_between(Pixels(5.0))
+                .height(Auto);
+            }
+            Button::new(cx, |cx| Label::new(cx, "+ Offset"))
+                .on_press(move |cx| cx.emit(ConditionEvent::OffsetAdded(index)));
+        })
+        .row_between(Pixels(5.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .height(Auto);
+        Button::new(cx, |cx| {
+            Label::new(
+                cx,
+                AppData::screen
+                    .map(move |screen| index.condition(screen.ruleset()).directional_mode.name()),
+            )
+        })
+        .on_press(move |cx| cx.emit(ConditionEvent::DirectionalModeToggled(index)))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .left(Pixels(15.0));
+    }
+    fn display_alternatives(alternatives: &[Pattern], cx: &mut Context, index: ConditionIndex) {
+        VStack::new(cx, |cx| {
+            for (alternative_index, alternative) in alternatives.iter().enumerate() {
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "or")
+                        .top(Stretch(1.0))
+                        .bottom(Stretch(1.0))
+                        .right(Pixels(5.0));
+                    alternative.display_editor(cx, move |cx, selected_index| {
+                        cx.emit(ConditionEvent::AlternativeSet(
+                            index,
+                            alternative_index,
+                            selected_index,
+                        ));
+                    });
+                    Button::new(cx, |cx| Label::new(cx, "-"))
+                        .on_press(move |cx| {
+                            cx.emit(ConditionEvent::AlternativeRemoved(index, alternative_index));
+                        })
+                        .top(Stretch(1.0))
+                        .bottom(Stretch(1.0))
+                        .left(Pixels(5.0));
+                })
+                .col_between(Pixels(5.0))
+                .height(Auto);
+            }
+            Button::new(cx, |cx| Label::new(cx, "+ Or"))
+                .on_press(move |cx| cx.emit(ConditionEvent::AlternativeAdded(index)));
+        })
+        .row_between(Pixels(5.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .left(Pixels(15.0))
+        .height(Auto);
+    }
+    fn display_compared(other: Pattern, cx: &mut Context, index: ConditionIndex) {
+        Button::new(cx, |cx| {
+            Label::new(
+                cx,
+                AppData::screen.map(move |screen| {
+                    match index.condition(screen.ruleset()).variant {
+                        Self::Compared { comparison, .. } => comparison.symbol(),
+                        _ => "",
+                    }
+                }),
+            )
+        })
+        .on_press(move |cx| cx.emit(ConditionEvent::ComparisonToggled(index)))
+        .tooltip(|cx| {
+            Tooltip::new(cx, |cx| {
+                Label::new(
+                    cx,
+                    "Compares how many neighbors match the pattern on the right \
+                     against how many match this one.",
+                );
+            })
+        })
+        .size(Pixels(35.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .right(Pixels(15.0));
+        other.display_editor(cx, move |cx, selected_index| {
+            cx.emit(ConditionEvent::ComparedPatternSet(index, selected_index));
+        });
+        Self::display_neighborhood(cx, index);
+    }
+    fn display_generation(cx: &mut Context, index: ConditionIndex) {
+        let generation_values =
+            move |screen: &Screen| match index.condition(screen.ruleset()).variant {
+                Self::Generation { modulus, remainder } => (modulus, remainder),
+                _ => (1, 0),
+            };
+        Label::new(cx, "Generation %")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0))
+            .right(Pixels(5.0));
+        Textbox::new(
+            cx,
+            AppData::screen.map(move |screen| generation_values(screen).0.to_string()),
+        )
+        .on_submit(move |cx, text, _| {
+            if let Ok(modulus) = text.parse() {
+                cx.emit(ConditionEvent::ModulusSet(index, modulus));
+            }
+        })
+        .width(Pixels(40.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Label::new(cx, "=")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0))
+            .left(Pixels(5.0))
+            .right(Pixels(5.0));
+        Textbox::new(
+            cx,
+            AppData::screen.map(move |screen| generation_values(screen).1.to_string()),
+        )
+        .on_submit(move |cx, text, _| {
+            if let Ok(remainder) = text.parse() {
+                cx.emit(ConditionEvent::RemainderSet(index, remainder));
+            }
+        })
+        .width(Pixels(40.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+    }
+    fn display_directional(cx: &mut Context, index: ConditionIndex) {
+        HStack::new(cx, |cx| {
+            VStack::new(cx, |cx| {
+                Self::direction_button(cx, index, svg::ARROW_NORTHWEST, Direction::Northwest);
+                Self::direction_button(cx, index, svg::ARROW_WEST, Direction::West);
+                Self::direction_button(cx, index, svg::ARROW_SOUTHWEST, Direction::Southwest);
+            })
+            .size(Stretch(1.0))
+            .min_size(Auto);
+            VStack::new(cx, |cx| {
+                Self::direction_button(cx, index, svg::ARROW_NORTH, Direction::North);
+                Self::direction_button(cx, index, svg::DIRECTIONAL_CONDITION, Direction::North)
+                    .background_color(Color::transparent())
+                    .border_color(Color::transparent())
+                    .hoverable(false);
+                Self::direction_button(cx, index, svg::ARROW_SOUTH, Direction::South);
+            })
+            .size(Stretch(1.0))
+            .min_size(Auto);
+            VStack::new(cx, |cx| {
+                Self::direction_button(cx, index, svg::ARROW_NORTHEAST, Direction::Northeast);
+                Self::direction_button(cx, index, svg::ARROW_EAST, Direction::East);
+                Self::direction_button(cx, index, svg::ARROW_SOUTHEAST, Direction::Southeast);
+            })
+            .size(Stretch(1.0))
+            .min_size(Auto);
+        })
+        .size(Pixels(100.0))
+        .top(Pixels(15.0))
+        .bottom(Pixels(15.0))
+        .min_size(Auto);
+        Button::new(cx, |cx| {
+            Label::new(
+                cx,
+                AppData::screen
+                    .map(move |screen| index.condition(screen.ruleset()).directional_mode.name()),
+            )
+        })
+        .on_press(move |cx| cx.emit(ConditionEvent::DirectionalModeToggled(index)))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .left(Pixels(15.0));
+    }
+    fn direction_button<'c>(
+        cx: &'c mut Context,
+        index: ConditionIndex,
+        svg: &'static str,
+        direction: Direction,
+    ) -> vizia::view::Handle<'c, Button> {
+        Button::new(cx, |cx| {
+            Svg::new(cx, svg)
+                .max_size(Percentage(80.0))
+                .space(Stretch(1.0))
+        })
+        .toggle_class(
+            style::PRESSED_BUTTON,
+            AppData::screen.map(move |screen| {
+                let variant = &index.condition(screen.ruleset()).variant;
+                matches!(variant, Self::Directional(ref vec) if vec.contains(&direction))
+            }),
+        )
+        .on_press(move |cx| {
+            cx.emit(ConditionEvent::DirectionToggled(index, direction));
+        })
+        .min_size(Auto)
+        .size(Stretch(1.0))
+    }
+    fn display_count(variant: &Operator, cx: &mut Context, index: ConditionIndex) {
+        Button::new(cx, |cx| match variant {
+            Operator::List(_) => Svg::new(cx, svg::EQUAL).class(style::SVG),
+            Operator::Greater(_) => Svg::new(cx, svg::GREATER).class(style::SVG),
+            Operator::Less(_) => Svg::new(cx, svg::LESS).class(style::SVG),
+            Operator::Range(..) => Svg::new(cx, svg::RANGE).class(style::SVG),
+            Operator::Percent(..) => Svg::new(cx, svg::PERCENT).class(style::SVG),
+        })
+        .on_press(move |cx| cx.emit(ConditionEvent::OperatorChanged(index)))
+        .size(Pixels(35.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .right(Pixels(15.0));
+        if let Operator::Percent(..) = variant {
+            Button::new(cx, |cx| {
+                Label::new(
+                    cx,
+                    AppData::screen.map(move |screen| {
+                        match index.condition(screen.ruleset()).variant.operator() {
+                            Some(Operator::Percent(_, comparison)) => comparison.symbol(),
+                            _ => "",
+                        }
+                    }),
+                )
+            })
+            .on_press(move |cx| cx.emit(ConditionEvent::ComparisonToggled(index)))
+            .tooltip(|cx| {
+                Tooltip::new(cx, |cx| {
+                    Label::new(
+                        cx,
+                        "Compares the percentage of neighbors inside the grid that match \
+                         against the one typed in.",
+                    );
+                })
+            })
+            .size(Pixels(35.0))
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0))
+            .right(Pixels(15.0));
+        }
+        Textbox::new(
+            cx,
+            AppData::screen.map(move |screen| {
+                let condition = index.condition(screen.ruleset());
+                let Some(variant) = condition.variant.operator() else {
+                    return String::new();
+                };
+                match variant {
+                    Operator::List(vec) => {
+                        vec.iter().map(u8::to_string).collect::<Vec<_>>().join(" ")
+                    }
+                    Operator::Greater(value) | Operator::Less(value) => value.to_string(),
+                    Operator::Range(min, max) => format!("{min}..{max}"),
+                    Operator::Percent(percent, _) => format!("{percent}%"),
+                }
+            }),
+        )
+        .on_edit(move |cx, text| {
+            // Text without any count, like letters alone, gives an operator that never matches.
+            let condition = AppData::screen
+                .map(move |screen| index.condition(screen.ruleset()).clone())
+                .get(cx);
+            let valid = condition
+                .variant
+                .operator()
+                .is_some_and(|operator| operator.accepts(&text, condition.max_count()));
+            cx.toggle_class(style::VALID_INPUT, valid);
+            cx.toggle_class(style::INVALID_INPUT, !valid);
+        })
+        .on_submit(move |cx, text, _| {
+            cx.toggle_class(style::VALID_INPUT, false);
+            cx.toggle_class(style::INVALID_INPUT, false);
+            cx.emit(ConditionEvent::CountUpdated(index, text));
+        })
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+        Self::display_neighborhood(cx, index);
+        Binding::new(
+            cx,
+            AppData::screen.map(move |screen| index.condition(screen.ruleset()).fixed_outcome()),
+            |cx, outcome| {
+                let message = match outcome.get(cx) {
+                    Some(true) => "Always true: no neighbor count falls outside these counts.",
+                    Some(false) => "Never true: no neighbor count can match these counts.",
+                    None => return,
+                };
+                Label::new(cx, message)
+                    .class(style::WARNING_LABEL)
+                    .top(Stretch(1.0))
+                    .bottom(Stretch(1.0))
+                    .left(Pixels(15.0));
+            },
+        );
+    }
+    fn display_neighborhood(cx: &mut Context, index: ConditionIndex) {
+        Button::new(cx, |cx| {
+            Label::new(
+                cx,
+                AppData::screen
+                    .map(move |screen| index.condition(screen.ruleset()).neighborhood.name()),
+            )
+        })
+        .on_press(move |cx| cx.emit(ConditionEvent::NeighborhoodToggled(index)))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .left(Pixels(15.0));
+        Label::new(cx, "Radius:")
+            .top(Stretch(1.0))
+            .bottom(Stretch(1.0))
+            .left(Pixels(15.0));
+        Textbox::new(
+            cx,
+            AppData::screen.map(move |screen| index.condition(screen.ruleset()).radius.to_string()),
+        )
+        .on_submit(move |cx, text, _| {
+            if let Ok(radius) = text.parse() {
+                cx.emit(ConditionEvent::RadiusSet(index, radius));
+            }
+        })
+        .width(Pixels(40.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+    }
+}
+
+impl Condition {
+    pub fn display_editor(&self, cx: &mut Context, index: ConditionIndex) {
+        HStack::new(cx, move |cx| {
+            VStack::new(cx, move |cx| {
+                Button::new(cx, move |cx| {
+                    Svg::new(cx, svg::NUMBERIC_CONDITION).class(style::SVG)
+                })
+                .size(Pixels(50.0))
+                .toggle_class(
+                    style::PRESSED_BUTTON,
+                    AppData::screen.map(move |screen| {
+                        let variant = &index.condition(screen.ruleset()).variant;
+                        matches!(variant, ConditionVariant::Count(_))
+                    }),
+                )
+                .on_press(move |cx| {
+                    cx.emit(ConditionEvent::VariantChanged(
+                        index,
+                        ConditionVariant::Count(Operator::List(vec![0])),
+                    ));
+                });
+                Button::new(cx, move |cx| {
+                    Svg::new(cx, svg::DIRECTIONAL_CONDITION)
+                        .max_size(Percentage(80.0))
+                        .space(Stretch(1.0))
+                })
+                .size(Pixels(50.0))
+                .toggle_class(
+                    style::PRESSED_BUTTON,
+                    AppData::screen.map(move |screen| {
+                        let variant = &index.condition(screen.ruleset()).variant;
+                        matches!(variant, ConditionVariant::Directional(_))
+                    }),
+                )
+                .on_press(move |cx| {
+                    cx.emit(ConditionEvent::VariantChanged(
+                        index,
+                        ConditionVariant::Directional(vec![]),
+                    ));
+                });
+                let pattern = self.pattern;
+                Button::new(cx, |cx| Label::new(cx, "<>"))
+                    .size(Pixels(50.0))
+                    .toggle_class(
+                        style::PRESSED_BUTTON,
+                        AppData::screen.map(move |screen| {
+                            let variant = &index.condition(screen.ruleset()).variant;
+                            matches!(variant, ConditionVariant::Compared { .. })
+                        }),
+                    )
+                    .on_press(move |cx| {
+                        cx.emit(ConditionEvent::VariantChanged(
+                            index,
+                            ConditionVariant::Compared {
+                                other: pattern,
+                                comparison: Comparison::default(),
+                            },
+                        ));
+                    });
+                Button::new(cx, |cx| Label::new(cx, "Any"))
+                    .size(Pixels(50.0))
+                    .toggle_class(
+                        style::PRESSED_BUTTON,
+                        AppData::screen.map(move |screen| {
+                            let variant = &index.condition(screen.ruleset()).variant;
+                            matches!(variant, ConditionVariant::CountAny { .. })
+                        }),
+                    )
+                    .on_press(move |cx| {
+                        cx.emit(ConditionEvent::VariantChanged(
+                            index,
+                            ConditionVariant::CountAny {
+                                alternatives: vec![pattern],
+                                counts: Operator::List(vec![0]),
+                            },
+                        ));
+                    });
+                Button::new(cx, |cx| Label::new(cx, "x,y"))
+                    .size(Pixels(50.0))
+                    .toggle_class(
+                        style::PRESSED_BUTTON,
+                        AppData::screen.map(move |screen| {
+                            let variant = &index.condition(screen.ruleset()).variant;
+                            matches!(variant, ConditionVariant::OffsetPattern(_))
+                        }),
+                    )
+                    .on_press(move |cx| {
+                        cx.emit(ConditionEvent::VariantChanged(
+                            index,
+                            ConditionVariant::OffsetPattern(vec![(0, 1)]),
+                        ));
+                    });
+                Button::new(cx, |cx| Label::new(cx, "%"))
+                    .size(Pixels(50.0))
+                    .toggle_class(
+                        style::PRESSED_BUTTON,
+                        AppData::screen.map(move |screen| {
+                            let variant = &index.condition(screen.ruleset()).variant;
+                            matches!(variant, ConditionVariant::Generation { .. })
+                        }),
+                    )
+                    .on_press(move |cx| {
+                        cx.emit(ConditionEvent::VariantChanged(
+                            index,
+                            ConditionVariant::Generation {
+                                modulus: 2,
+                                remainder: 0,
+                            },
+                        ));
+                    });
+            })
+            .space(Pixels(15.0))
+            .min_size(Auto)
+            .size(Auto);
+            self.variant.display_editor(cx, index);
+            Button::new(cx, |cx| {
+                if self.inverted {
+                    Svg::new(cx, svg::NOT_EQUAL).class(style::SVG)
+                } else {
+                    Svg::new(cx, svg::EQUAL).class(style::SVG)
+                }
+            })
+            .class(style::CONDITION_INVERT_BUTTON)
+            .on_press(move |cx| cx.emit(ConditionEvent::Inverted(index)));
+            self.pattern.display_editor(cx, move |cx, selected_index| {
+                cx.emit(ConditionEvent::PatternSet(index, selected_index));
+            });
+            VStack::new(cx, |cx| {
+                Button::new(cx, |cx| Svg::new(cx, style::svg::COPY).class(style::SVG))
+                    .on_press(move |cx| cx.emit(ConditionEvent::Copied(index)))
+                    .size(Pixels(50.0));
+                let target = DeletionTarget::Condition(index);
+                Button::new(cx, |cx| {
+                    Svg::new(cx, deletion_look(target, svg::TRASH, svg::TRASH_OPEN))
+                        .class(style::SVG)
+                })
+                .on_press(move |cx| cx.emit(ConditionEvent::Deleted(index)))
+                .size(Pixels(50.0));
+            })
+            .space(Pixels(15.0))
+            .min_size(Auto)
+            .size(Auto);
+        })
+        .class(style::CONDITION_EDITOR);
+    }
+}
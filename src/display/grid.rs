@@ -0,0 +1,426 @@
+use std::time::{Duration, Instant};
+
+use vizia::{prelude::*, vg};
+
+use crate::{
+    display::style,
+    events::UpdateEvent,
+    grid::{Boundary, Cell, Grid, GridRect, GridRegion, VisualGridState},
+    material::MaterialColor,
+    ruleset::Ruleset,
+    AppData,
+};
+
+impl Data for Grid {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+impl Data for Boundary {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+impl Data for GridRect {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+impl Data for GridRegion {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+impl Data for VisualGridState {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+pub struct GridDisplay<L1, L2, L3, L4, L5, L6>
+where
+    L1: Lens<Target = VisualGridState>,
+    L2: Lens<Target = Option<usize>>,
+    L3: Lens<Target = f32>,
+    L4: Lens<Target = bool>,
+    L5: Lens<Target = Option<GridRect>>,
+    L6: Lens<Target = bool>,
+{
+    grid: L1,
+    hovered: L2,
+    /// How long each change of the grid blends in over, in seconds; zero to snap instantly.
+    transition: L3,
+    /// Whether cells are tinted by how active they have been recently.
+    heatmap: L4,
+    /// The selected region, outlined over the grid.
+    selection: L5,
+    /// Whether the grid stretches to fill the view rather than keeping square cells.
+    fill: L6,
+    /// How many times larger than its fitted size the grid is drawn.
+    scale: f32,
+    /// How far the zoomed grid is shifted from its fitted position, in pixels.
+    offset: (f32, f32),
+    /// The cursor position the current middle-drag pan last moved from.
+    pan_origin: Option<(f32, f32)>,
+    /// The state being blended away from, and the latest state seen.
+    previous: VisualGridState,
+    shown: VisualGridState,
+    /// When the current blend began, if one is underway.
+    transition_start: Option<Instant>,
+    /// Redraws the grid while a blend is underway.
+    frame_timer: Timer,
+}
+impl<L1, L2, L3, L4, L5, L6> GridDisplay<L1, L2, L3, L4, L5, L6>
+where
+    L1: Lens<Target = VisualGridState>,
+    L2: Lens<Target = Option<usize>>,
+    L3: Lens<Target = f32>,
+    L4: Lens<Target = bool>,
+    L5: Lens<Target = Option<GridRect>>,
+    L6: Lens<Target = bool>,
+{
+    const PADDING_MARGIN: f32 = 0.1;
+    const MIN_SCALE: f32 = 1.0;
+    const MAX_SCALE: f32 = 40.0;
+    /// The zoom factor applied per step of the mouse wheel.
+    const ZOOM_STEP: f32 = 1.2;
+    /// How often the grid is redrawn while blending between states.
+    const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+    /// The color the most active cells are tinted toward, and how strongly.
+    const HEAT_COLOR: MaterialColor = MaterialColor::new(255, 64, 0);
+    const HEAT_OPACITY: f32 = 0.8;
+    /// The selection is outlined in this color and shaded with a translucent fill of it.
+    const SELECTION_COLOR: MaterialColor = MaterialColor::new(0, 160, 255);
+    const SELECTION_FILL_ALPHA: u8 = 48;
+    pub fn new(
+        cx: &mut Context,
+        grid: L1,
+        hovered: L2,
+        transition: L3,
+        heatmap: L4,
+        selection: L5,
+        fill: L6,
+    ) -> Handle<Self> {
+        let frame_timer = cx.add_timer(Self::FRAME_INTERVAL, None, |cx, action| {
+            if let TimerAction::Tick(_) | TimerAction::Stop = action {
+                cx.needs_redraw();
+            }
+        });
+        Self {
+            grid,
+            hovered,
+            transition,
+            heatmap,
+            selection,
+            fill,
+            scale: Self::MIN_SCALE,
+            offset: (0.0, 0.0),
+            pan_origin: None,
+            previous: VisualGridState::default(),
+            shown: VisualGridState::default(),
+            transition_start: None,
+            frame_timer,
+        }
+        .build(cx, move |_| {})
+        .bind(grid, |mut handle, _| {
+            let entity = handle.entity();
+            handle.context().emit_to(entity, GridDisplayEvent::Changed);
+        })
+        .bind(hovered, |mut cx, _| cx.needs_redraw())
+        .bind(heatmap, |mut cx, _| cx.needs_redraw())
+        .bind(selection, |mut cx, _| cx.needs_redraw())
+        .bind(fill, |mut cx, _| cx.needs_redraw())
+    }
+
+    /// How far the display has blended from `previous` to the latest state, from 0 to 1.
+    fn progress(&self, transition: f32) -> f32 {
+        match self.transition_start {
+            Some(start) if transition > 0.0 => {
+                (start.elapsed().as_secs_f32() / transition).min(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// The width and height of each cell within `bounds`, and the gap left around each.
+    #[allow(clippy::cast_precision_loss)]
+    fn cell_size(grid_size: usize, bounds: BoundingBox) -> (f32, f32, f32) {
+        let original_width = bounds.width() / grid_size as f32;
+        let original_height = bounds.height() / grid_size as f32;
+        let padding = 1.0_f32.max(Self::PADDING_MARGIN * original_width.min(original_height));
+        (original_width - padding, original_height - padding, padding)
+    }
+
+    /// The rectangle the grid fits into before zooming: all of `full_bounds` when filling it,
+    /// and otherwise the largest centered one with the grid's own square shape.
+    fn fitted_bounds(&self, cx: &impl DataContext, full_bounds: &BoundingBox) -> BoundingBox {
+        if self.fill.get(cx) {
+            *full_bounds
+        } else {
+            super::rect_bounds(full_bounds, 1.0)
+        }
+    }
+
+    /// The rectangle the whole grid occupies after zooming and panning,
+    /// which may extend past `full_bounds`.
+    fn view_bounds(&self, cx: &impl DataContext, full_bounds: &BoundingBox) -> BoundingBox {
+        let bounds = self.fitted_bounds(cx, full_bounds);
+        BoundingBox {
+            x: bounds.x + self.offset.0,
+            y: bounds.y + self.offset.1,
+            w: bounds.w * self.scale,
+            h: bounds.h * self.scale,
+        }
+    }
+
+    /// Multiplies the scale by `factor`, keeping the point under `cursor` in place.
+    fn zoom(
+        &mut self,
+        cx: &impl DataContext,
+        factor: f32,
+        cursor: (f32, f32),
+        full_bounds: &BoundingBox,
+    ) {
+        let bounds = self.fitted_bounds(cx, full_bounds);
+        let scale = (self.scale * factor).clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+        let ratio = scale / self.scale;
+        let cursor_x = cursor.0 - bounds.x;
+        let cursor_y = cursor.1 - bounds.y;
+        self.offset = (
+            (self.offset.0 - cursor_x).mul_add(ratio, cursor_x),
+            (self.offset.1 - cursor_y).mul_add(ratio, cursor_y),
+        );
+        self.scale = scale;
+        self.clamp_offset(cx, full_bounds);
+    }
+
+    /// Keeps the zoomed grid covering its fitted rectangle, so it can't be panned out of view.
+    fn clamp_offset(&mut self, cx: &impl DataContext, full_bounds: &BoundingBox) {
+        let bounds = self.fitted_bounds(cx, full_bounds);
+        self.offset = (
+            self.offset.0.clamp(-bounds.w * (self.scale - 1.0), 0.0),
+            self.offset.1.clamp(-bounds.h * (self.scale - 1.0), 0.0),
+        );
+    }
+}
+impl<L1, L2, L3, L4, L5, L6> View for GridDisplay<L1, L2, L3, L4, L5, L6>
+where
+    L1: Lens<Target = VisualGridState>,
+    L2: Lens<Target = Option<usize>>,
+    L3: Lens<Target = f32>,
+    L4: Lens<Target = bool>,
+    L5: Lens<Target = Option<GridRect>>,
+    L6: Lens<Target = bool>,
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn draw(&self, cx: &mut vizia::context::DrawContext, canvas: &vizia::vg::Canvas) {
+        let mut main_paint = vg::Paint::default();
+        main_paint.set_color(cx.background_color());
+        let mut border_paint = vg::Paint::default();
+
+        let state = self.grid.get(cx);
+        let grid_size = state.size();
+        let hovered = self.hovered.get(cx);
+        let cells = state.cells();
+        let activity = state.activity();
+        let heatmap = self.heatmap.get(cx);
+        let progress = if self.previous.size() == grid_size {
+            self.progress(self.transition.get(cx))
+        } else {
+            1.0
+        };
+
+        let full_bounds = cx.bounds();
+        let bounds = self.view_bounds(cx, &full_bounds);
+        let (cell_width, cell_height, padding) = Self::cell_size(grid_size, bounds);
+        canvas.save();
+        canvas.clip_rect(vg::Rect::from(full_bounds), vg::ClipOp::Intersect, false);
+        for y in 0..grid_size {
+            for x in 0..grid_size {
+                let cell_x =
+                    (x as f32).mul_add(padding + cell_width, bounds.left()) + padding / 2.0;
+                //(x * (padding + cell_size) + bounds.left) + padding / 2.0
+                let cell_y =
+                    (y as f32).mul_add(padding + cell_height, bounds.top()) + padding / 2.0;
+                if cell_x + cell_width < full_bounds.left()
+                    || cell_x > full_bounds.right()
+                    || cell_y + cell_height < full_bounds.top()
+                    || cell_y > full_bounds.bottom()
+                {
+                    continue;
+                }
+                let rect = vg::Rect::from_xywh(cell_x, cell_y, cell_width, cell_height);
+
+                let mut color: MaterialColor = *cells
+                    .get((y * grid_size) + x)
+                    .unwrap_or(&MaterialColor::DEFAULT);
+                if progress < 1.0 {
+                    if let Some(&previous) = self.previous.cells().get((y * grid_size) + x) {
+                        color = previous.lerp(color, progress);
+                    }
+                }
+                if heatmap {
+                    let heat = activity
+                        .get((y * grid_size) + x)
+                        .copied()
+                        .unwrap_or_default();
+                    let heat = f32::from(heat) / f32::from(u16::MAX);
+                    color = color.lerp(Self::HEAT_COLOR, heat * Self::HEAT_OPACITY);
+                }
+                main_paint.set_color(color);
+                border_paint.set_color(color.invert_grayscale());
+
+                if hovered.is_some_and(|s| s == (y * grid_size) + x) {
+                    let border = rect.with_outset((cell_width * 0.05, cell_height * 0.05));
+                    canvas.draw_rect(border, &border_paint);
+                }
+                canvas.draw_rect(rect, &main_paint);
+            }
+        }
+        let selection = self
+            .selection
+            .get(cx)
+            .map(|selection| selection.clipped(grid_size))
+            .filter(|selection| !selection.is_empty());
+        if let Some(selection) = selection {
+            let (step_x, step_y) = (padding + cell_width, padding + cell_height);
+            let rect = vg::Rect::from_xywh(
+                (selection.x as f32).mul_add(step_x, bounds.left()),
+                (selection.y as f32).mul_add(step_y, bounds.top()),
+                selection.width as f32 * step_x,
+                selection.height as f32 * step_y,
+            );
+            let mut fill_paint = vg::Paint::default();
+            fill_paint.set_color(
+                vg::Color::from(Self::SELECTION_COLOR).with_a(Self::SELECTION_FILL_ALPHA),
+            );
+            canvas.draw_rect(rect, &fill_paint);
+            let mut selection_paint = vg::Paint::default();
+            selection_paint.set_color(Self::SELECTION_COLOR);
+            selection_paint.set_style(vg::PaintStyle::Stroke);
+            selection_paint.set_stroke_width(2.0);
+            canvas.draw_rect(rect, &selection_paint);
+        }
+        canvas.restore();
+    }
+
+    fn event(&mut self, cx: &mut vizia::context::EventContext, event: &mut vizia::events::Event) {
+        event.map(|event: &GridDisplayEvent, _| match event {
+            GridDisplayEvent::Changed => {
+                let state = self.grid.get(cx);
+                self.previous = std::mem::replace(&mut self.shown, state);
+                let transition = self.transition.get(cx);
+                if transition > 0.0 && self.previous.size() == self.shown.size() {
+                    self.transition_start = Some(Instant::now());
+                    cx.start_timer(self.frame_timer);
+                    cx.modify_timer(self.frame_timer, |timer| {
+                        timer.set_duration(Some(Duration::from_secs_f32(transition)));
+                    });
+                } else {
+                    self.transition_start = None;
+                }
+                cx.needs_redraw();
+            }
+        });
+        event.map(|event: &WindowEvent, meta| match event {
+            WindowEvent::MouseMove(x, y) => {
+                if meta.target != cx.current() {
+                    return;
+                }
+                if let Some((origin_x, origin_y)) = self.pan_origin {
+                    self.offset.0 += x - origin_x;
+                    self.offset.1 += y - origin_y;
+                    self.pan_origin = Some((*x, *y));
+                    self.clamp_offset(cx, &cx.bounds());
+                    cx.needs_redraw();
+                    return;
+                }
+                let full_bounds = cx.bounds();
+                if !full_bounds.contains_point(*x, *y) {
+                    return;
+                }
+                let bounds = self.view_bounds(cx, &full_bounds);
+                if !bounds.contains_point(*x, *y) {
+                    return;
+                }
+                let grid_size = self.grid.get(cx).size();
+                let (cell_width, cell_height, padding) = Self::cell_size(grid_size, bounds);
+                let x = x - bounds.left() - (padding / 2.0);
+                let y = y - bounds.top() - (padding / 2.0);
+                // let grid_size = grid_size as f32;
+                // println!("Pos: {x}, {y}");
+                let normalized_x = x / (cell_width + padding);
+                let normalized_y = y / (cell_height + padding);
+                // println!(
+                //     "Divided: {}, {}",
+                //     x / (padding + cell_size),
+                //     y / (padding + cell_size)
+                // );
+                let in_cell = normalized_x - normalized_x.floor() < 1.0 - Self::PADDING_MARGIN
+                    && normalized_y - normalized_y.floor() < 1.0 - Self::PADDING_MARGIN;
+                // println!("In cell: {in_cell}",);
+                //(x * (padding + cell_size) + bounds.left) + padding / 2.0
+
+                // let index_x = x / self.grid.get(cx).size as f32 +  ;
+                // let index_y = x / self.grid.get(cx).size as f32;
+                if in_cell {
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    cx.emit(UpdateEvent::CellHovered {
+                        x: normalized_x as usize,
+                        y: normalized_y as usize,
+                    });
+                } else {
+                    cx.emit(UpdateEvent::CellUnhovered);
+                }
+            }
+            WindowEvent::MouseScroll(_, y) => {
+                let cursor = (cx.mouse().cursor_x, cx.mouse().cursor_y);
+                self.zoom(cx, Self::ZOOM_STEP.powf(*y), cursor, &cx.bounds());
+                cx.needs_redraw();
+                meta.consume();
+            }
+            WindowEvent::MouseDown(MouseButton::Middle) => {
+                self.pan_origin = Some((cx.mouse().cursor_x, cx.mouse().cursor_y));
+                cx.capture();
+            }
+            WindowEvent::MouseUp(MouseButton::Middle) => {
+                self.pan_origin = None;
+                cx.release();
+            }
+            WindowEvent::MouseDown(button) => {
+                cx.emit(UpdateEvent::CellClicked(*button));
+            }
+            _ => {}
+        });
+    }
+}
+
+enum GridDisplayEvent {
+    /// The displayed grid state changed, so a new blend may begin.
+    Changed,
+}
+
+impl Cell {
+    /// Draws the cell with a radial gradient as dark as the app's cell gradient setting.
+    pub fn display<'c>(self, cx: &'c mut Context, ruleset: &Ruleset) -> Handle<'c, Button> {
+        let darken_value = AppData::cell_gradient.get(cx);
+        Button::new(cx, Element::new)
+            .class(style::CELL)
+            .background_gradient(self.gradient(ruleset, darken_value).as_str())
+            .on_hover_out(|cx| cx.emit(UpdateEvent::CellUnhovered))
+    }
+    #[rustfmt::skip]
+    fn gradient(self, ruleset: &Ruleset, darken_value: u8) -> String {
+        let color = RGBA::from(self.color(ruleset));
+        let dark_color = RGBA::rgb(
+            color.r().saturating_sub(darken_value),
+            color.g().saturating_sub(darken_value),
+            color.b().saturating_sub(darken_value)
+        );
+        format!(
+            "radial-gradient(rgba({}, {}, {}), rgba({}, {}, {}))",
+            color.r(),      color.g(),      color.b(),
+            dark_color.r(), dark_color.g(), dark_color.b()
+        )
+    }
+}
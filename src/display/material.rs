@@ -0,0 +1,312 @@
+use vizia::prelude::*;
+
+use crate::{
+    display::{deletion_look, style},
+    events::{DeletionTarget, GroupEvent, MaterialEvent},
+    grid::Cell,
+    id::Identifiable,
+    material::{ColorChannel, GroupId, Material, MaterialColor, MaterialGroup},
+    ruleset::Ruleset,
+    AppData,
+};
+
+impl Material {
+    pub fn display_editor(&self, cx: &mut Context, index: usize, ruleset: &Ruleset) {
+        VStack::new(cx, |cx| {
+            let cell = Cell::new(self.id());
+            let id = self.id();
+            let material_count = ruleset.materials.len();
+            cell.display(cx, ruleset).size(Pixels(256.0));
+            HStack::new(cx, move |cx| {
+                let target = DeletionTarget::Material(id);
+                Button::new(cx, |cx| {
+                    Label::new(cx, deletion_look(target, "Delete", "Confirm Delete"))
+                })
+                .on_press(move |cx| cx.emit(MaterialEvent::Deleted(id)));
+                if index > 1 {
+                    Button::new(cx, |cx| Label::new(cx, "<"))
+                        .on_press(move |cx| cx.emit(MaterialEvent::Moved(index, index - 1)));
+                }
+                if index > 0 && index + 1 < material_count {
+                    Button::new(cx, |cx| Label::new(cx, ">"))
+                        .on_press(move |cx| cx.emit(MaterialEvent::Moved(index, index + 1)));
+                }
+                Button::new(cx, |cx| Label::new(cx, "Pick"))
+                    .on_press(move |cx| cx.emit(MaterialEvent::PickerToggled(index)))
+                    .toggle_class(
+                        style::PRESSED_BUTTON,
+                        AppData::color_picker.map(move |picker| *picker == Some(index)),
+                    );
+                Button::new(cx, |cx| Label::new(cx, "Lock"))
+                    .on_press(move |cx| cx.emit(MaterialEvent::LockToggled(index)))
+                    .toggle_class(
+                        style::PRESSED_BUTTON,
+                        AppData::screen.map(move |screen| {
+                            screen
+                                .ruleset()
+                                .materials
+                                .get_at(index)
+                                .is_some_and(|material| material.locked)
+                        }),
+                    );
+                Button::new(cx, |cx| Label::new(cx, "Immovable"))
+                    .on_press(move |cx| cx.emit(MaterialEvent::ImmovableToggled(index)))
+                    .toggle_class(
+                        style::PRESSED_BUTTON,
+                        AppData::screen.map(move |screen| {
+                            screen
+                                .ruleset()
+                                .materials
+                                .get_at(index)
+                                .is_some_and(|material| material.immovable)
+                        }),
+                    );
+                Textbox::new(
+                    cx,
+                    AppData::screen.map(move |screen| {
+                        screen
+                            .ruleset()
+                            .materials
+                            .get_at(index)
+                            .expect("The specified index did not contain a material")
+                            .color
+                            .to_string()
+                    }),
+                )
+                .width(Stretch(1.0))
+                .on_submit(move |cx, text, _| cx.emit(MaterialEvent::Recolored(index, text)))
+                .min_height(Pixels(30.0));
+                Textbox::new(
+                    cx,
+                    AppData::screen.map(move |screen| {
+                        screen
+                            .ruleset()
+                            .materials
+                            .get_at(index)
+                            .expect("The specified index did not contain a material")
+                            .name
+                            .clone()
+                    }),
+                )
+                .width(Stretch(1.0))
+                .on_submit(move |cx, text, _| cx.emit(MaterialEvent::Renamed(index, text)));
+            })
+            .width(Stretch(1.0))
+            .height(Auto);
+            if !ruleset.groups.is_empty() {
+                HStack::new(cx, move |cx| {
+                    for (group_index, group) in ruleset.groups.iter().enumerate() {
+                        let name = group.name.clone();
+                        Button::new(cx, move |cx| Label::new(cx, name))
+                            .on_press(move |cx| {
+                                cx.emit(GroupEvent::MembershipToggled {
+                                    group_index,
+                                    material_id: id,
+                                });
+                            })
+                            .toggle_class(
+                                style::PRESSED_BUTTON,
+                                AppData::screen.map(move |screen| {
+                                    screen
+                                        .ruleset()
+                                        .groups
+                                        .get(group_index)
+                                        .is_some_and(|group| group.contains(id))
+                                }),
+                            );
+                    }
+                })
+                .width(Stretch(1.0))
+                .height(Auto);
+            }
+            Label::new(
+                cx,
+                AppData::color_error.map(move |error| match error {
+                    Some((error_index, error)) if *error_index == index => error.clone(),
+                    _ => String::new(),
+                }),
+            )
+            .class(style::ERROR_LABEL)
+            .display(AppData::color_error.map(move |error| {
+                error
+                    .as_ref()
+                    .is_some_and(|(error_index, _)| *error_index == index)
+            }));
+            VStack::new(cx, move |cx| {
+                for channel in ColorChannel::ALL {
+                    Self::channel_slider(cx, index, channel);
+                }
+            })
+            .height(Auto)
+            .display(AppData::color_picker.map(move |picker| *picker == Some(index)));
+        })
+        .width(Auto)
+        .height(Auto)
+        .space(Percentage(1.0))
+        .child_space(Percentage(5.0));
+    }
+    fn channel_slider(cx: &mut Context, index: usize, channel: ColorChannel) {
+        HStack::new(cx, move |cx| {
+            Label::new(cx, channel.name()).width(Pixels(20.0));
+            Slider::new(
+                cx,
+                AppData::screen.map(move |screen| {
+                    screen
+                        .ruleset()
+                        .materials
+                        .get_at(index)
+                        .map_or(0.0, |material| f32::from(material.color.channel(channel)))
+                }),
+            )
+            .range(0.0..255.0)
+            .on_changing(move |cx, value| {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let value = value.round().clamp(0.0, 255.0) as u8;
+                cx.emit(MaterialEvent::ChannelSet(index, channel, value));
+            })
+            .width(Stretch(1.0));
+        })
+        .height(Pixels(30.0));
+    }
+}
+
+impl MaterialGroup {
+    pub fn display_editor(&self, cx: &mut Context, index: usize, ruleset: &Ruleset) {
+        let id = self.id();
+        VStack::new(cx, move |cx| {
+            HStack::new(cx, move |cx| {
+                Textbox::new(
+                    cx,
+                    AppData::screen.map(move |s| {
+                        s.ruleset()
+                            .group(id)
+                            .expect("Group should exist.")
+                            .name
+                            .clone()
+                    }),
+                )
+                .on_submit(move |cx, text, _| cx.emit(GroupEvent::Renamed(index, text)));
+                Element::new(cx)
+                    .background_color(AppData::screen.map(move |screen| {
+                        let color = screen
+                            .ruleset()
+                            .group(id)
+                            .map_or(MaterialColor::NEUTRAL, |group| {
+                                group.representative_color(screen.ruleset())
+                            });
+                        Color::from(color)
+                    }))
+                    .class(style::SWATCH);
+                Button::new(cx, |cx| Label::new(cx, "New Material"))
+                    .on_press(move |cx| cx.emit(GroupEvent::EntryAdded(index)));
+                let target = DeletionTarget::Group(index);
+                Button::new(cx, |cx| {
+                    Label::new(cx, deletion_look(target, "Delete", "Confirm Delete"))
+                })
+                .on_press(move |cx| cx.emit(GroupEvent::Deleted(index)));
+            })
+            .height(Auto);
+            let others: Vec<(GroupId, String)> = ruleset
+                .groups
+                .iter()
+                .filter(|group| group.id() != id)
+                .map(|group| (group.id(), group.name.clone()))
+                .collect();
+            if !others.is_empty() {
+                HStack::new(cx, move |cx| {
+                    Label::new(cx, "Contains:");
+                    for (subgroup, name) in others {
+                        Button::new(cx, move |cx| Label::new(cx, format!("#{name}")))
+                            .on_press(move |cx| {
+                                cx.emit(GroupEvent::SubgroupToggled {
+                                    group_index: index,
+                                    subgroup,
+                                });
+                            })
+                            .toggle_class(
+                                style::PRESSED_BUTTON,
+                                AppData::screen.map(move |screen| {
+                                    screen
+                                        .ruleset()
+                                        .group(id)
+                                        .is_some_and(|group| group.subgroups().contains(&subgroup))
+                                }),
+                            );
+                    }
+                })
+                .width(Stretch(1.0))
+                .height(Auto);
+            }
+
+            self.materials()
+                .iter()
+                .enumerate()
+                .filter_map(|(index, id)| ruleset.materials.get(*id).map(|_| index))
+                .enumerate()
+                .for_each(|(entry_index, material_index)| {
+                    Self::display_entry(cx, index, material_index, entry_index);
+                });
+        })
+        .width(Percentage(100.0))
+        .class(style::BASE_EDITOR);
+    }
+    fn display_entry(
+        cx: &mut Context,
+        group_index: usize,
+        material_index: usize,
+        entry_index: usize,
+    ) {
+        HStack::new(cx, |cx| {
+            Button::new(cx, |cx| Label::new(cx, "-")).on_press(move |cx| {
+                cx.emit(GroupEvent::EntryDeleted {
+                    group_index,
+                    entry_index,
+                });
+            });
+            ComboBox::new(
+                cx,
+                AppData::screen.map(|screen| screen.ruleset().materials.names()),
+                AppData::screen.map(move |screen| {
+                    let Some(group) = screen.ruleset().groups.get(group_index) else {
+                        return 0;
+                    };
+                    let Some(material) = group.materials().get(material_index) else {
+                        return 0;
+                    };
+                    let Some(index) = screen.ruleset().materials.index_of(*material) else {
+                        return 0;
+                    };
+                    index
+                }),
+            )
+            .on_select(move |cx, selected_index| {
+                cx.emit(GroupEvent::Edited {
+                    group_index,
+                    entry_index: material_index,
+                    new_material_index: selected_index,
+                });
+            });
+        })
+        .height(Auto)
+        .width(Stretch(1.0));
+    }
+}
+
+impl From<MaterialColor> for Color {
+    fn from(value: MaterialColor) -> Self {
+        let (r, g, b) = value.rgb();
+        Self::rgb(r, g, b)
+    }
+}
+impl From<MaterialColor> for RGBA {
+    fn from(value: MaterialColor) -> Self {
+        let (r, g, b) = value.rgb();
+        Self::rgb(r, g, b)
+    }
+}
+impl From<MaterialColor> for vizia::vg::Color {
+    fn from(value: MaterialColor) -> Self {
+        let (r, g, b) = value.rgb();
+        Self::from_rgb(r, g, b)
+    }
+}
@@ -0,0 +1,34 @@
+use vizia::prelude::*;
+
+use crate::{display::style, pattern::Pattern, AppData};
+
+impl Pattern {
+    pub fn display_editor<F>(self, cx: &mut Context, on_select: F)
+    where
+        F: Fn(&mut EventContext, usize) + 'static,
+    {
+        Element::new(cx)
+            .background_color(
+                AppData::screen.map(move |screen| Color::from(self.color(screen.ruleset()))),
+            )
+            .class(style::SWATCH);
+        ComboBox::new(
+            cx,
+            AppData::screen.map(|screen| screen.ruleset().pattern_values()),
+            // Dangling patterns are reported by `Ruleset::validate`.
+            AppData::screen.map(move |screen| match self {
+                Self::Material(id) => screen.ruleset().materials.index_of(id).unwrap_or_default(),
+                Self::Group(id) => screen
+                    .ruleset()
+                    .index_of_group(id)
+                    .map_or(0, |index| screen.ruleset().materials.len() + index),
+                Self::SelfSame => screen.ruleset().materials.len() + screen.ruleset().groups.len(),
+            }),
+        )
+        .width(Stretch(1.0))
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0))
+        .on_select(on_select)
+        .class(style::LIGHT_COMBOBOX);
+    }
+}
@@ -0,0 +1,185 @@
+use vizia::prelude::*;
+
+use crate::{
+    display::{
+        deletion_look,
+        style::{self, svg},
+    },
+    events::{ConditionEvent, DeletionTarget, RuleEvent},
+    ruleset::{Rule, RuleIndex, Ruleset, RulesetFormat, RulesetTemplate},
+    AppData,
+};
+
+impl Data for Ruleset {
+    fn same(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.conflict_policy == other.conflict_policy
+            && self.default_size == other.default_size
+            && self.default_boundary == other.default_boundary
+            && self.fallback == other.fallback
+            && self.rules == other.rules
+            && self.materials == other.materials
+            && self.groups == other.groups
+    }
+}
+impl Data for RulesetTemplate {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+impl Data for RulesetFormat {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl Rule {
+    /// Displays the rule's editor. `shadowed_by` is the title of an earlier rule that always
+    /// applies in this one's place; see [`Ruleset::find_shadowed_rules`].
+    pub fn display_editor(
+        &self,
+        cx: &mut Context,
+        index: RuleIndex,
+        invalid: bool,
+        shadowed_by: Option<String>,
+    ) {
+        let shadowed = shadowed_by.is_some();
+        VStack::new(cx, move |cx| {
+            if let Some(earlier) = shadowed_by {
+                Label::new(
+                    cx,
+                    format!("Never fires: {earlier} always applies to these cells first."),
+                )
+                .class(style::WARNING_LABEL);
+            }
+            Textbox::new(
+                cx,
+                AppData::screen.map(move |screen| {
+                    screen
+                        .ruleset()
+                        .rules
+                        .get(index.value())
+                        .map_or_else(String::new, |rule| rule.label.clone())
+                }),
+            )
+            .width(Stretch(1.0))
+            .on_submit(move |cx, text, _| cx.emit(RuleEvent::Renamed(index, text)));
+            HStack::new(cx, move |cx| {
+                Button::new(cx, |cx| Svg::new(cx, svg::COPY).class(style::SVG))
+                    .on_press(move |cx| cx.emit(RuleEvent::Copied(index)))
+                    .size(Pixels(50.0))
+                    .top(Stretch(1.0))
+                    .right(Pixels(15.0))
+                    .bottom(Stretch(1.0));
+
+                self.input.display_editor(cx, move |cx, selected| {
+                    cx.emit(RuleEvent::InputSet(index, selected));
+                });
+                ZStack::new(cx, |cx| {
+                    Svg::new(cx, svg::TRANSFORM_ARROW)
+                        .size(Percentage(80.0))
+                        .space(Stretch(1.0));
+                })
+                .size(Pixels(80.0));
+                // .background_color("green");
+                self.display_outputs(cx, index);
+
+                let target = DeletionTarget::Rule(index);
+                Button::new(cx, |cx| {
+                    Svg::new(cx, deletion_look(target, svg::TRASH, svg::TRASH_OPEN))
+                        .class(style::SVG)
+                })
+                .on_press(move |cx| cx.emit(RuleEvent::Deleted(index)))
+                .size(Pixels(50.0))
+                .top(Stretch(1.0))
+                .left(Pixels(15.0))
+                .bottom(Stretch(1.0));
+            })
+            // .background_color("red")
+            .top(Pixels(-5.0))
+            .height(Auto);
+            VStack::new(cx, move |cx| {
+                for (condition_index, condition) in self.conditions.iter().enumerate() {
+                    condition.display_editor(cx, index.with_condition(condition_index));
+                }
+                Button::new(cx, |cx| Label::new(cx, "New Condition").space(Stretch(1.0)))
+                    .width(Stretch(1.0))
+                    .on_press(move |cx| cx.emit(ConditionEvent::Created(index)));
+            })
+            .class(style::CONDITION_CONTAINER);
+        })
+        .class(style::BASE_EDITOR)
+        .toggle_class(style::INVALID_EDITOR, invalid)
+        .toggle_class(style::SHADOWED_EDITOR, shadowed)
+        .width(Percentage(50.0));
+    }
+    fn display_outputs(&self, cx: &mut Context, index: RuleIndex) {
+        let weighted = self.outputs.len() > 1;
+        VStack::new(cx, |cx| {
+            for (position, &(output, _)) in self.outputs.iter().enumerate() {
+                HStack::new(cx, |cx| {
+                    ComboBox::new(
+                        cx,
+                        AppData::screen.map(|screen| screen.ruleset().output_values()),
+                        AppData::screen.map(move |screen| {
+                            // Dangling outputs are reported by `Ruleset::validate`.
+                            output.index(screen.ruleset()).unwrap_or_default()
+                        }),
+                    )
+                    .class(style::LIGHT_COMBOBOX)
+                    .width(Stretch(1.0))
+                    .top(Stretch(1.0))
+                    .bottom(Stretch(1.0))
+                    .on_select(move |cx, selected| {
+                        cx.emit(RuleEvent::OutputSet(index, position, selected));
+                    });
+                    if weighted {
+                        Textbox::new(
+                            cx,
+                            AppData::screen.map(move |screen| {
+                                screen
+                                    .ruleset()
+                                    .rules
+                                    .get(index.value())
+                                    .and_then(|rule| rule.outputs.get(position))
+                                    .map(|&(_, weight)| weight.to_string())
+                                    .unwrap_or_default()
+                            }),
+                        )
+                        .on_submit(move |cx, text, _| {
+                            if let Ok(weight) = text.trim().parse() {
+                                cx.emit(RuleEvent::OutputWeightSet(index, position, weight));
+                            }
+                        })
+                        .tooltip(|cx| {
+                            Tooltip::new(cx, |cx| {
+                                Label::new(
+                                    cx,
+                                    "How likely this output is, relative to the others' weights.",
+                                );
+                            })
+                        })
+                        .width(Pixels(50.0))
+                        .top(Stretch(1.0))
+                        .bottom(Stretch(1.0));
+                        Button::new(cx, |cx| Label::new(cx, "-"))
+                            .on_press(move |cx| {
+                                cx.emit(RuleEvent::OutputRemoved(index, position));
+                            })
+                            .top(Stretch(1.0))
+                            .bottom(Stretch(1.0));
+                    }
+                })
+                .col_between(Pixels(5.0))
+                .height(Auto);
+            }
+            Button::new(cx, |cx| Label::new(cx, "+ Output"))
+                .on_press(move |cx| cx.emit(RuleEvent::OutputAdded(index)));
+        })
+        .row_between(Pixels(5.0))
+        .width(Stretch(1.0))
+        .height(Auto)
+        .top(Stretch(1.0))
+        .bottom(Stretch(1.0));
+    }
+}
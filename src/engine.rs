@@ -0,0 +1,11 @@
+//! The simulation itself: grids, the rulesets that advance them, and the materials, patterns and
+//! conditions rules are built from. Nothing here depends on the UI, so it can run headless;
+//! editors and views for these types live under [`crate::display`].
+
+pub mod condition;
+pub mod grid;
+pub mod id;
+pub mod material;
+pub mod pattern;
+pub mod preset;
+pub mod ruleset;
@@ -0,0 +1,1069 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    grid::{Cell, CellNeighbors},
+    id::Identifiable,
+    pattern::Pattern,
+    ruleset::{Rule, Ruleset},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConditionIndex {
+    rule_index: usize,
+    condition_index: usize,
+}
+impl ConditionIndex {
+    pub const fn new(rule_index: usize, condition_index: usize) -> Self {
+        Self {
+            rule_index,
+            condition_index,
+        }
+    }
+    pub const fn values(&self) -> (usize, usize) {
+        (self.rule_index, self.condition_index)
+    }
+
+    pub fn rule<'a>(&self, ruleset: &'a Ruleset) -> &'a Rule {
+        ruleset
+            .rules
+            .get(self.rule_index)
+            .expect("invalid rule index")
+    }
+    pub fn condition<'a>(&self, ruleset: &'a Ruleset) -> &'a Condition {
+        self.rule(ruleset)
+            .conditions
+            .get(self.condition_index)
+            .expect("invalid condition index")
+    }
+
+    pub fn rule_mut<'a>(&self, ruleset: &'a mut Ruleset) -> &'a mut Rule {
+        ruleset
+            .rules
+            .get_mut(self.rule_index)
+            .expect("invalid rule index")
+    }
+    pub fn condition_mut<'a>(&self, ruleset: &'a mut Ruleset) -> &'a mut Condition {
+        self.rule_mut(ruleset)
+            .conditions
+            .get_mut(self.condition_index)
+            .expect("invalid condition index")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operator {
+    List(Vec<u8>),
+    Greater(u8),
+    Less(u8),
+    Range(u8, u8),
+    /// Compares the percentage of neighbors that match against the one given, so the same rule
+    /// works across neighborhood sizes. Only neighbors inside the grid count towards the total,
+    /// and a cell without any never matches.
+    Percent(u8, Comparison),
+}
+impl Operator {
+    /// Whether `count` matching neighbors, out of `present` neighbors inside the grid, satisfy
+    /// the operator.
+    fn contains(&self, count: u8, present: u8) -> bool {
+        match self {
+            Self::List(vec) => vec.contains(&count),
+            Self::Greater(bound) => count > *bound,
+            Self::Less(bound) => count < *bound,
+            Self::Range(min, max) => (*min..=*max).contains(&count),
+            Self::Percent(percent, comparison) => {
+                let matching = u32::from(count) * 100;
+                let threshold = u32::from(*percent) * u32::from(present);
+                present > 0
+                    && match comparison {
+                        Comparison::Greater => matching > threshold,
+                        Comparison::Less => matching < threshold,
+                        Comparison::Equal => matching == threshold,
+                    }
+            }
+        }
+    }
+    pub fn clamp(&mut self, max: u8) {
+        match self {
+            Self::List(vec) => vec.retain(|&n| n <= max),
+            Self::Greater(bound) | Self::Less(bound) => *bound = (*bound).min(max),
+            Self::Range(min, range_max) => {
+                *min = (*min).min(max);
+                *range_max = (*range_max).min(max);
+            }
+            Self::Percent(percent, _) => *percent = (*percent).min(100),
+        }
+    }
+    /// Whether the operator matches every possible count, out of every possible number of
+    /// neighbors from 0 to `max` (`Some(true)`), or none of them (`Some(false)`).
+    pub fn fixed_outcome(&self, max: u8) -> Option<bool> {
+        let outcomes: Vec<bool> = (0..=max)
+            .flat_map(|present| (0..=present).map(move |count| self.contains(count, present)))
+            .collect();
+        if !outcomes.contains(&true) {
+            Some(false)
+        } else if !outcomes.contains(&false) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+    /// Describes the counts in prose, such as `exactly 3` or `between 2 and 4`.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::List(vec) => match vec.as_slice() {
+                [] => String::from("an impossible number of"),
+                [count] => format!("exactly {count}"),
+                counts => {
+                    let counts: Vec<String> = counts.iter().map(u8::to_string).collect();
+                    join_phrases(&counts, "or")
+                }
+            },
+            Self::Greater(bound) => format!("more than {bound}"),
+            Self::Less(bound) => format!("fewer than {bound}"),
+            Self::Range(min, max) => format!("between {min} and {max}"),
+            Self::Percent(percent, Comparison::Greater) => format!("more than {percent}% of"),
+            Self::Percent(percent, Comparison::Less) => format!("under {percent}% of"),
+            Self::Percent(percent, Comparison::Equal) => format!("exactly {percent}% of"),
+        }
+    }
+    /// Replaces the counts with those typed into the editor, clamped to `max`.
+    /// A bound past `max` becomes `max` rather than disappearing, so the editor can flag it.
+    /// Percentages are read as a single number instead, and left alone if there isn't one.
+    pub fn update_counts(&mut self, text: &str, max: u8) {
+        if let Self::Percent(percent, _) = self {
+            if let Some(value) = Self::parse_percent(text) {
+                *percent = value;
+            }
+            return;
+        }
+        *self = self.with_elements(Self::parse_counts(text, max));
+        self.clamp(max);
+    }
+    /// Whether `text` typed into the editor gives this operator something to match.
+    pub fn accepts(&self, text: &str, max: u8) -> bool {
+        match self {
+            Self::Percent(..) => Self::parse_percent(text).is_some(),
+            _ => !Self::parse_counts(text, max).is_empty(),
+        }
+    }
+    /// Parses a percentage such as `50` or `50%`, capped at 100.
+    pub fn parse_percent(text: &str) -> Option<u8> {
+        let text = text.trim().trim_end_matches('%').trim_end();
+        text.parse::<u8>().ok().map(|percent| percent.min(100))
+    }
+    /// Parses the counts typed into the editor.
+    /// While every count is a single digit, each digit is its own count (`"23"` is `[2, 3]`);
+    /// otherwise counts must be separated by spaces, commas, or a range's `..`.
+    pub fn parse_counts(text: &str, max: u8) -> Vec<u8> {
+        let mut elements: Vec<u8> = if max < 10 {
+            text.chars()
+                .filter_map(|char| char.to_digit(10).and_then(|num| num.try_into().ok()))
+                .collect()
+        } else {
+            text.split(|char: char| char.is_whitespace() || char == ',' || char == '.')
+                .filter_map(|number| number.parse().ok())
+                .collect()
+        };
+        elements.sort_unstable();
+        elements.dedup();
+        elements
+    }
+    pub fn with_elements(&self, elements: Vec<u8>) -> Self {
+        match self {
+            Self::List(_) => Self::List(elements),
+            Self::Greater(_) => Self::Greater(elements.into_iter().min().unwrap_or(0)),
+            Self::Less(_) => Self::Less(elements.into_iter().max().unwrap_or(0)),
+            Self::Range(..) => Self::Range(
+                elements.iter().copied().min().unwrap_or(0),
+                elements.iter().copied().max().unwrap_or(0),
+            ),
+            Self::Percent(_, comparison) => Self::Percent(
+                elements.into_iter().min().unwrap_or(0).min(100),
+                *comparison,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Northwest,
+    North,
+    Northeast,
+    West,
+    East,
+    Southwest,
+    South,
+    Southeast,
+}
+impl Direction {
+    pub const ALL: [Self; 8] = [
+        Self::Northwest,
+        Self::North,
+        Self::Northeast,
+        Self::West,
+        Self::East,
+        Self::Southwest,
+        Self::South,
+        Self::Southeast,
+    ];
+
+    pub const fn offset(self) -> (i8, i8) {
+        match self {
+            Self::Northwest => (-1, -1),
+            Self::North => (0, -1),
+            Self::Northeast => (1, -1),
+            Self::West => (-1, 0),
+            Self::East => (1, 0),
+            Self::Southwest => (-1, 1),
+            Self::South => (0, 1),
+            Self::Southeast => (1, 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Neighborhood {
+    #[default]
+    Moore,
+    VonNeumann,
+}
+impl Neighborhood {
+    pub const fn contains(self, (x, y): (isize, isize), radius: usize) -> bool {
+        match self {
+            Self::Moore => true,
+            Self::VonNeumann => x.unsigned_abs() + y.unsigned_abs() <= radius,
+        }
+    }
+    /// The largest neighbor count a condition using this neighborhood can observe.
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn max_count(self, radius: usize) -> u8 {
+        let count = match self {
+            Self::Moore => (2 * radius + 1) * (2 * radius + 1) - 1,
+            Self::VonNeumann => 2 * radius * (radius + 1),
+        };
+        count as u8
+    }
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Moore => Self::VonNeumann,
+            Self::VonNeumann => Self::Moore,
+        }
+    }
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Moore => "Moore",
+            Self::VonNeumann => "Von Neumann",
+        }
+    }
+}
+
+/// How the chosen directions of a directional condition must match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DirectionalMode {
+    /// At least one chosen direction matches.
+    #[default]
+    Any,
+    /// Every chosen direction matches.
+    All,
+    /// Exactly the chosen directions match. Neighbors outside the grid never match.
+    Exact,
+}
+impl DirectionalMode {
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Any => Self::All,
+            Self::All => Self::Exact,
+            Self::Exact => Self::Any,
+        }
+    }
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Any => "Any",
+            Self::All => "All",
+            Self::Exact => "Exact",
+        }
+    }
+}
+
+/// How a compared condition relates the neighbor counts of its two patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Comparison {
+    /// Strictly more neighbors match the first pattern.
+    #[default]
+    Greater,
+    /// Strictly fewer neighbors match the first pattern.
+    Less,
+    /// The same number of neighbors match both patterns.
+    Equal,
+}
+impl Comparison {
+    pub const fn holds(self, first: u8, second: u8) -> bool {
+        match self {
+            Self::Greater => first > second,
+            Self::Less => first < second,
+            Self::Equal => first == second,
+        }
+    }
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Greater => Self::Less,
+            Self::Less => Self::Equal,
+            Self::Equal => Self::Greater,
+        }
+    }
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Greater => ">",
+            Self::Less => "<",
+            Self::Equal => "=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConditionVariant {
+    Directional(Vec<Direction>),
+    Count(Operator),
+    /// Matches when the generation number divided by `modulus` leaves `remainder`,
+    /// regardless of the neighbors. A modulus of 2 alternates between even and odd steps.
+    Generation {
+        modulus: usize,
+        remainder: usize,
+    },
+    /// Matches when the number of neighbors fitting the condition's pattern relates to the
+    /// number fitting `other` as `comparison` says, such as more sand than water.
+    Compared {
+        other: Pattern,
+        comparison: Comparison,
+    },
+    /// Like [`ConditionVariant::Count`], but counts neighbors fitting the condition's pattern or
+    /// any of `alternatives`, each neighbor only once.
+    CountAny {
+        alternatives: Vec<Pattern>,
+        counts: Operator,
+    },
+    /// Like [`ConditionVariant::Directional`], but checks the cells at arbitrary `(x, y)`
+    /// offsets from the center, with `y` pointing down, such as `(0, 2)` for two cells below.
+    OffsetPattern(Vec<(i8, i8)>),
+}
+impl ConditionVariant {
+    pub fn directions(&mut self) -> Option<&mut Vec<Direction>> {
+        match self {
+            Self::Directional(vec) => Some(vec),
+            Self::Count(_)
+            | Self::Generation { .. }
+            | Self::Compared { .. }
+            | Self::CountAny { .. }
+            | Self::OffsetPattern(_) => None,
+        }
+    }
+    /// Parses an offset written as `x, y` or `x y`, clamped to [`Condition::MAX_RADIUS`].
+    /// The center itself is not a valid offset.
+    pub fn parse_offset(text: &str) -> Option<(i8, i8)> {
+        let mut parts = text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty());
+        let max = i8::try_from(Condition::MAX_RADIUS).unwrap_or(i8::MAX);
+        let mut parse = || -> Option<i8> {
+            let value: i64 = parts.next()?.parse().ok()?;
+            i8::try_from(value.clamp(-i64::from(max), i64::from(max))).ok()
+        };
+        let offset = (parse()?, parse()?);
+        (parts.next().is_none() && offset != (0, 0)).then_some(offset)
+    }
+    /// The neighbor counts the condition accepts, for the variants that count neighbors.
+    pub const fn operator(&self) -> Option<&Operator> {
+        match self {
+            Self::Count(operator)
+            | Self::CountAny {
+                counts: operator, ..
+            } => Some(operator),
+            Self::Directional(_)
+            | Self::Generation { .. }
+            | Self::Compared { .. }
+            | Self::OffsetPattern(_) => None,
+        }
+    }
+    pub fn operator_mut(&mut self) -> Option<&mut Operator> {
+        match self {
+            Self::Count(operator)
+            | Self::CountAny {
+                counts: operator, ..
+            } => Some(operator),
+            Self::Directional(_)
+            | Self::Generation { .. }
+            | Self::Compared { .. }
+            | Self::OffsetPattern(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Condition {
+    pub variant: ConditionVariant,
+    pub pattern: Pattern,
+    pub inverted: bool,
+    #[serde(default)]
+    pub neighborhood: Neighborhood,
+    #[serde(default = "Condition::default_radius")]
+    pub radius: usize,
+    #[serde(default)]
+    pub directional_mode: DirectionalMode,
+}
+impl Condition {
+    /// Keeps the largest possible neighbor count within a `u8`.
+    pub const MAX_RADIUS: usize = 7;
+
+    const fn default_radius() -> usize {
+        1
+    }
+
+    pub fn new(ruleset: &Ruleset) -> Self {
+        Self {
+            variant: ConditionVariant::Count(Operator::List(vec![0])),
+            pattern: Pattern::Material(ruleset.materials.default().id()),
+            inverted: false,
+            neighborhood: Neighborhood::Moore,
+            radius: 1,
+            directional_mode: DirectionalMode::Any,
+        }
+    }
+    /// The radius of the neighborhood this condition needs to inspect.
+    /// Directional conditions only ever look at the adjacent cells, offset conditions reach as
+    /// far as their furthest offset, and generation conditions ignore their neighbors entirely.
+    pub fn neighbor_radius(&self) -> usize {
+        match &self.variant {
+            ConditionVariant::Directional(_) | ConditionVariant::Generation { .. } => 1,
+            ConditionVariant::Count(_)
+            | ConditionVariant::Compared { .. }
+            | ConditionVariant::CountAny { .. } => self.radius,
+            ConditionVariant::OffsetPattern(offsets) => offsets
+                .iter()
+                .map(|&(x, y)| usize::from(x.unsigned_abs().max(y.unsigned_abs())))
+                .max()
+                .unwrap_or(1)
+                .clamp(1, Self::MAX_RADIUS),
+        }
+    }
+    pub const fn max_count(&self) -> u8 {
+        self.neighborhood.max_count(self.radius)
+    }
+    /// Whether the condition's counts hold for every possible neighbor count, or for none.
+    /// Returns `None` for conditions without counts.
+    pub fn fixed_outcome(&self) -> Option<bool> {
+        let outcome = self.variant.operator()?.fixed_outcome(self.max_count())?;
+        Some(outcome != self.inverted)
+    }
+    /// Describes the condition in prose as a clause about the cell, such as
+    /// `has exactly 3 Alive neighbors` or, when inverted, `does not have exactly 3 Alive neighbors`.
+    pub fn describe(&self, ruleset: &Ruleset) -> String {
+        let has = if self.inverted {
+            "does not have"
+        } else {
+            "has"
+        };
+        let pattern = self.pattern.describe(ruleset);
+        let neighborhood = match (self.neighborhood, self.radius) {
+            (Neighborhood::Moore, 1) => String::new(),
+            (neighborhood, radius) => {
+                format!(
+                    " in its {} neighborhood of radius {radius}",
+                    neighborhood.name()
+                )
+            }
+        };
+        let places = |places: Vec<String>, conjunction| {
+            if places.is_empty() {
+                String::from("nowhere")
+            } else {
+                join_phrases(&places, conjunction)
+            }
+        };
+        match &self.variant {
+            ConditionVariant::Directional(directions) => {
+                let directions = || {
+                    directions
+                        .iter()
+                        .map(|dir| format!("the {dir:?}"))
+                        .collect()
+                };
+                match self.directional_mode {
+                    DirectionalMode::Any => format!(
+                        "{has} at least one {pattern} neighbor to {}",
+                        places(directions(), "or")
+                    ),
+                    DirectionalMode::All => {
+                        format!(
+                            "{has} {pattern} neighbors to {}",
+                            places(directions(), "and")
+                        )
+                    }
+                    DirectionalMode::Exact => format!(
+                        "{has} {pattern} neighbors to {} and nowhere else",
+                        places(directions(), "and")
+                    ),
+                }
+            }
+            ConditionVariant::Count(counts) => {
+                format!(
+                    "{has} {} {pattern} neighbors{neighborhood}",
+                    counts.describe()
+                )
+            }
+            ConditionVariant::Generation { modulus, remainder } => {
+                let is = if self.inverted { "is not" } else { "is" };
+                format!("{is} on a generation that leaves {remainder} when divided by {modulus}")
+            }
+            ConditionVariant::Compared { other, comparison } => {
+                let other = other.describe(ruleset);
+                let (amount, than) = match comparison {
+                    Comparison::Greater => ("more", "than"),
+                    Comparison::Less => ("fewer", "than"),
+                    Comparison::Equal => ("as many", "as"),
+                };
+                format!("{has} {amount} {pattern} neighbors {than} {other} neighbors{neighborhood}")
+            }
+            ConditionVariant::CountAny { counts, .. } => {
+                let patterns: Vec<String> = self
+                    .patterns()
+                    .map(|pattern| pattern.describe(ruleset))
+                    .collect();
+                format!(
+                    "{has} {} neighbors that are {}{neighborhood}",
+                    counts.describe(),
+                    join_phrases(&patterns, "or")
+                )
+            }
+            ConditionVariant::OffsetPattern(offsets) => {
+                let offsets = || offsets.iter().map(|(x, y)| format!("({x}, {y})")).collect();
+                match self.directional_mode {
+                    DirectionalMode::Any => {
+                        format!(
+                            "{has} at least one {pattern} cell at {}",
+                            places(offsets(), "or")
+                        )
+                    }
+                    DirectionalMode::All => {
+                        format!("{has} {pattern} cells at {}", places(offsets(), "and"))
+                    }
+                    DirectionalMode::Exact => format!(
+                        "{has} {pattern} cells at {} and nowhere else nearby",
+                        places(offsets(), "and")
+                    ),
+                }
+            }
+        }
+    }
+    /// Every pattern the condition refers to.
+    pub fn patterns(&self) -> impl Iterator<Item = Pattern> + '_ {
+        let others: &[Pattern] = match &self.variant {
+            ConditionVariant::Compared { other, .. } => std::slice::from_ref(other),
+            ConditionVariant::CountAny { alternatives, .. } => alternatives,
+            _ => &[],
+        };
+        std::iter::once(self.pattern).chain(others.iter().copied())
+    }
+    pub fn patterns_mut(&mut self) -> impl Iterator<Item = &mut Pattern> + '_ {
+        let others: &mut [Pattern] = match &mut self.variant {
+            ConditionVariant::Compared { other, .. } => std::slice::from_mut(other),
+            ConditionVariant::CountAny { alternatives, .. } => alternatives,
+            _ => &mut [],
+        };
+        std::iter::once(&mut self.pattern).chain(others)
+    }
+    /// Whether the condition holds for `center` with these neighbors during `generation`.
+    pub fn matches(
+        &self,
+        center: Cell,
+        neighbors: &CellNeighbors,
+        ruleset: &Ruleset,
+        generation: usize,
+    ) -> bool {
+        let matches = match &self.variant {
+            ConditionVariant::Directional(directions) => {
+                let matches_direction = |&dir: &Direction| {
+                    neighbors
+                        .in_direction(dir)
+                        .is_some_and(|cell| self.pattern.matches(ruleset, cell, center))
+                };
+                match self.directional_mode {
+                    DirectionalMode::Any => directions.iter().any(matches_direction),
+                    DirectionalMode::All => directions.iter().all(matches_direction),
+                    DirectionalMode::Exact => Direction::ALL
+                        .iter()
+                        .all(|dir| matches_direction(dir) == directions.contains(dir)),
+                }
+            }
+            ConditionVariant::Count(counts) => counts.contains(
+                neighbors.count_matching(ruleset, self.pattern, self.neighborhood, center),
+                neighbors.count_present(self.neighborhood),
+            ),
+            ConditionVariant::Generation { modulus, remainder } => {
+                generation % (*modulus).max(1) == *remainder
+            }
+            ConditionVariant::Compared { other, comparison } => comparison.holds(
+                neighbors.count_matching(ruleset, self.pattern, self.neighborhood, center),
+                neighbors.count_matching(ruleset, *other, self.neighborhood, center),
+            ),
+            ConditionVariant::CountAny { counts, .. } => counts.contains(
+                neighbors.count_where(self.neighborhood, |cell| {
+                    self.patterns()
+                        .any(|pattern| pattern.matches(ruleset, cell, center))
+                }),
+                neighbors.count_present(self.neighborhood),
+            ),
+            ConditionVariant::OffsetPattern(offsets) => {
+                let matches_offset = |&(x, y): &(i8, i8)| {
+                    neighbors
+                        .at_offset(x, y)
+                        .is_some_and(|cell| self.pattern.matches(ruleset, cell, center))
+                };
+                match self.directional_mode {
+                    DirectionalMode::Any => offsets.iter().any(matches_offset),
+                    DirectionalMode::All => offsets.iter().all(matches_offset),
+                    DirectionalMode::Exact => {
+                        let radius = i8::try_from(self.neighbor_radius()).unwrap_or(1);
+                        (-radius..=radius)
+                            .flat_map(|y| (-radius..=radius).map(move |x| (x, y)))
+                            .filter(|&offset| offset != (0, 0))
+                            .all(|offset| matches_offset(&offset) == offsets.contains(&offset))
+                    }
+                }
+            }
+        };
+        matches != self.inverted
+    }
+}
+
+/// Joins phrases into a list for prose, such as `2, 3 or 5`.
+fn join_phrases(phrases: &[String], conjunction: &str) -> String {
+    match phrases {
+        [] => String::new(),
+        [phrase] => phrase.clone(),
+        [rest @ .., last] => format!("{} {conjunction} {last}", rest.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{
+        grid::{Cell, Grid, GridBackend},
+        id::UniqueId,
+    };
+
+    use super::*;
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn serde_range() {
+        let condition = Condition {
+            variant: ConditionVariant::Count(Operator::Range(2, 3)),
+            pattern: Pattern::Material(UniqueId::new_unchecked(10)),
+            inverted: false,
+            neighborhood: Neighborhood::Moore,
+            radius: 1,
+            directional_mode: DirectionalMode::Any,
+        };
+
+        let condition_string = toml::to_string(&condition).unwrap();
+        println!("{condition_string:?}");
+
+        let new_condition: Condition = toml::from_str(&condition_string).unwrap();
+        assert_eq!(condition, new_condition);
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn serde_generation() {
+        let condition = Condition {
+            variant: ConditionVariant::Generation {
+                modulus: 2,
+                remainder: 1,
+            },
+            ..Condition::new(&Ruleset::blank())
+        };
+
+        let condition_string = toml::to_string(&condition).unwrap();
+        let new_condition: Condition = toml::from_str(&condition_string).unwrap();
+        assert_eq!(condition, new_condition);
+    }
+
+    #[test]
+    fn range_elements() {
+        let range = Operator::Range(0, 0);
+        assert!(!range.contains(2, 8));
+
+        let range = range.with_elements(Operator::parse_counts("2..3", 8));
+        assert_eq!(range, Operator::Range(2, 3));
+        assert!(!range.contains(1, 8));
+        assert!(range.contains(2, 8));
+        assert!(range.contains(3, 8));
+        assert!(!range.contains(4, 8));
+
+        let range = range.with_elements(Operator::parse_counts("10..12", 24));
+        assert_eq!(range, Operator::Range(10, 12));
+        assert_eq!(range.with_elements(vec![]), Operator::Range(0, 0));
+    }
+
+    #[test]
+    fn clamped_counts() {
+        // A Von Neumann neighborhood of radius 1 never sees more than four neighbors.
+        let max = Neighborhood::VonNeumann.max_count(1);
+        assert_eq!(max, 4);
+
+        let mut list = Operator::List(vec![]);
+        list.update_counts("2 6 7", max);
+        assert_eq!(list, Operator::List(vec![2]));
+
+        let mut greater = Operator::Greater(0);
+        greater.update_counts("6", max);
+        assert_eq!(greater, Operator::Greater(4));
+
+        let mut range = Operator::Range(0, 0);
+        range.update_counts("3..7", max);
+        assert_eq!(range, Operator::Range(3, 4));
+
+        // Larger neighborhoods can match counts above eight.
+        let max = Neighborhood::Moore.max_count(2);
+        greater.update_counts("8", max);
+        assert_eq!(greater, Operator::Greater(8));
+        assert!(greater.contains(9, max));
+    }
+
+    #[test]
+    fn fixed_outcomes() {
+        assert_eq!(Operator::List(vec![]).fixed_outcome(4), Some(false));
+        assert_eq!(
+            Operator::List(vec![0, 1, 2, 3, 4]).fixed_outcome(4),
+            Some(true)
+        );
+        assert_eq!(Operator::List(vec![0, 1, 2, 3]).fixed_outcome(4), None);
+        assert_eq!(Operator::Greater(4).fixed_outcome(4), Some(false));
+        assert_eq!(Operator::Greater(3).fixed_outcome(4), None);
+        assert_eq!(Operator::Less(0).fixed_outcome(8), Some(false));
+        assert_eq!(Operator::Less(5).fixed_outcome(4), Some(true));
+        assert_eq!(Operator::Range(0, 4).fixed_outcome(4), Some(true));
+
+        let condition = Condition {
+            variant: ConditionVariant::Count(Operator::Greater(4)),
+            neighborhood: Neighborhood::VonNeumann,
+            ..Condition::new(&Ruleset::blank())
+        };
+        assert_eq!(condition.fixed_outcome(), Some(false));
+        let inverted = Condition {
+            inverted: true,
+            ..condition
+        };
+        assert_eq!(inverted.fixed_outcome(), Some(true));
+        let moore = Condition {
+            neighborhood: Neighborhood::Moore,
+            ..inverted
+        };
+        assert_eq!(moore.fixed_outcome(), None);
+    }
+
+    #[test]
+    fn compared_counts() {
+        let water = Cell::new(UniqueId::new_unchecked(1));
+        let fire = Cell::new(UniqueId::new_unchecked(2));
+        let stone = Cell::new(UniqueId::new_unchecked(3));
+        let ruleset = Ruleset::blank();
+        let compared = |comparison| Condition {
+            variant: ConditionVariant::Compared {
+                other: Pattern::Material(fire.material_id),
+                comparison,
+            },
+            pattern: Pattern::Material(water.material_id),
+            inverted: false,
+            neighborhood: Neighborhood::Moore,
+            radius: 1,
+            directional_mode: DirectionalMode::Any,
+        };
+        let neighbors = |water_count: usize, fire_count: usize| {
+            let cells = (0..8)
+                .map(|i| {
+                    Some(if i < water_count {
+                        water
+                    } else if i < water_count + fire_count {
+                        fire
+                    } else {
+                        stone
+                    })
+                })
+                .collect();
+            CellNeighbors::new(1, cells)
+        };
+
+        // Three water, two fire, three stone.
+        let more_water = neighbors(3, 2);
+        assert!(compared(Comparison::Greater).matches(stone, &more_water, &ruleset, 0));
+        assert!(!compared(Comparison::Less).matches(stone, &more_water, &ruleset, 0));
+        assert!(!compared(Comparison::Equal).matches(stone, &more_water, &ruleset, 0));
+
+        // Both comparisons are strict, so a tie only satisfies `Equal`.
+        let tied = neighbors(3, 3);
+        assert!(!compared(Comparison::Greater).matches(stone, &tied, &ruleset, 0));
+        assert!(!compared(Comparison::Less).matches(stone, &tied, &ruleset, 0));
+        assert!(compared(Comparison::Equal).matches(stone, &tied, &ruleset, 0));
+        assert_eq!(compared(Comparison::Greater).patterns().count(), 2);
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn count_any() {
+        let water = Cell::new(UniqueId::new_unchecked(1));
+        let fire = Cell::new(UniqueId::new_unchecked(2));
+        let lava = Cell::new(UniqueId::new_unchecked(3));
+        let ruleset = Ruleset::blank();
+        let condition = Condition {
+            variant: ConditionVariant::CountAny {
+                // Listing a pattern twice still counts each neighbor once.
+                alternatives: vec![
+                    Pattern::Material(lava.material_id),
+                    Pattern::Material(fire.material_id),
+                ],
+                counts: Operator::List(vec![3]),
+            },
+            pattern: Pattern::Material(fire.material_id),
+            ..Condition::new(&ruleset)
+        };
+        let neighbors = |cells: [Cell; 8]| CellNeighbors::new(1, cells.map(Some).to_vec());
+
+        let three = neighbors([fire, lava, fire, water, water, water, water, water]);
+        assert!(condition.matches(water, &three, &ruleset, 0));
+        let four = neighbors([fire, lava, lava, fire, water, water, water, water]);
+        assert!(!condition.matches(water, &four, &ruleset, 0));
+        assert_eq!(condition.patterns().count(), 3);
+
+        let condition_string = toml::to_string(&condition).unwrap();
+        let new_condition: Condition = toml::from_str(&condition_string).unwrap();
+        assert_eq!(condition, new_condition);
+    }
+
+    #[test]
+    fn percent() {
+        let alive = Cell::new(UniqueId::new_unchecked(1));
+        let dead = Cell::new(UniqueId::new_unchecked(2));
+        let ruleset = Ruleset::blank();
+        let condition = Condition {
+            variant: ConditionVariant::Count(Operator::Percent(50, Comparison::Greater)),
+            pattern: Pattern::Material(alive.material_id),
+            ..Condition::new(&ruleset)
+        };
+
+        // Five of eight neighbors is over half, but four is not.
+        let full = |alive_count| {
+            let cells = (0..8)
+                .map(|i| Some(if i < alive_count { alive } else { dead }))
+                .collect();
+            CellNeighbors::new(1, cells)
+        };
+        assert!(condition.matches(dead, &full(5), &ruleset, 0));
+        assert!(!condition.matches(dead, &full(4), &ruleset, 0));
+
+        // In a corner only three neighbors are inside the grid, so two of them is over half.
+        let corner = CellNeighbors::new(
+            1,
+            vec![
+                None,
+                None,
+                None,
+                None,
+                Some(alive),
+                None,
+                Some(alive),
+                Some(dead),
+            ],
+        );
+        assert!(condition.matches(dead, &corner, &ruleset, 0));
+
+        // Without any neighbors there is no percentage to compare, so it never matches.
+        let alone = CellNeighbors::new(1, vec![None; 8]);
+        assert!(!condition.matches(dead, &alone, &ruleset, 0));
+        let inverted = Condition {
+            inverted: true,
+            ..condition
+        };
+        assert!(inverted.matches(dead, &alone, &ruleset, 0));
+
+        let mut operator = Operator::Percent(50, Comparison::Equal);
+        assert!(operator.contains(4, 8));
+        assert!(!operator.contains(1, 3));
+        operator.update_counts("75%", 8);
+        assert_eq!(operator, Operator::Percent(75, Comparison::Equal));
+        operator.update_counts("250", 8);
+        assert_eq!(operator, Operator::Percent(100, Comparison::Equal));
+        assert!(!operator.accepts("half", 8));
+        assert_eq!(
+            Operator::Percent(100, Comparison::Greater).fixed_outcome(8),
+            Some(false)
+        );
+        assert_eq!(
+            Operator::Percent(50, Comparison::Less).fixed_outcome(8),
+            None
+        );
+    }
+
+    #[test]
+    fn inverted_count() {
+        let alive = Cell::new(UniqueId::new_unchecked(1));
+        let dead = Cell::new(UniqueId::new_unchecked(2));
+        let ruleset = Ruleset::blank();
+
+        let condition = Condition {
+            variant: ConditionVariant::Count(Operator::List(vec![2, 3])),
+            pattern: Pattern::Material(alive.material_id),
+            inverted: false,
+            neighborhood: Neighborhood::Moore,
+            radius: 1,
+            directional_mode: DirectionalMode::Any,
+        };
+        let inverted = Condition {
+            inverted: true,
+            ..condition.clone()
+        };
+
+        for count in 0..=8 {
+            let cells = (0..8)
+                .map(|i| Some(if i < count { alive } else { dead }))
+                .collect();
+            let neighbors = CellNeighbors::new(1, cells);
+            let matches = condition.matches(dead, &neighbors, &ruleset, 0);
+            assert_eq!(matches, count == 2 || count == 3);
+            assert_eq!(inverted.matches(dead, &neighbors, &ruleset, 0), !matches);
+        }
+    }
+
+    #[test]
+    fn directional_mode() {
+        let alive = Cell::new(UniqueId::new_unchecked(1));
+        let dead = Cell::new(UniqueId::new_unchecked(2));
+        let ruleset = Ruleset::blank();
+        // Row-major order without the center: North is index 1, South is index 6.
+        let mut cells = vec![Some(dead); 8];
+        cells[1] = Some(alive);
+        let neighbors = CellNeighbors::new(1, cells);
+
+        let any = Condition {
+            variant: ConditionVariant::Directional(vec![Direction::North, Direction::South]),
+            pattern: Pattern::Material(alive.material_id),
+            inverted: false,
+            neighborhood: Neighborhood::Moore,
+            radius: 1,
+            directional_mode: DirectionalMode::Any,
+        };
+        let all = Condition {
+            directional_mode: DirectionalMode::All,
+            ..any.clone()
+        };
+        assert!(any.matches(dead, &neighbors, &ruleset, 0));
+        assert!(!all.matches(dead, &neighbors, &ruleset, 0));
+
+        let mut cells = vec![Some(dead); 8];
+        cells[1] = Some(alive);
+        cells[6] = Some(alive);
+        let neighbors = CellNeighbors::new(1, cells);
+        assert!(any.matches(dead, &neighbors, &ruleset, 0));
+        assert!(all.matches(dead, &neighbors, &ruleset, 0));
+
+        let legacy: Condition = toml::from_str(
+            "variant = { Directional = [\"North\"] }\npattern = \"1m\"\ninverted = false",
+        )
+        .expect("Conditions without a mode should still deserialize.");
+        assert_eq!(legacy.directional_mode, DirectionalMode::Any);
+    }
+
+    #[test]
+    fn exact_directions() {
+        use Direction::{East, North, Northeast, Northwest, South, Southeast, Southwest, West};
+
+        let ruleset = Ruleset::blank();
+        let alive = Cell::new(ruleset.materials.default().id());
+        let grid = Grid::new(ruleset.clone(), 3);
+        let exact = |directions: &[Direction]| Condition {
+            variant: ConditionVariant::Directional(directions.to_vec()),
+            pattern: Pattern::Material(alive.material_id),
+            inverted: false,
+            neighborhood: Neighborhood::Moore,
+            radius: 1,
+            directional_mode: DirectionalMode::Exact,
+        };
+
+        // Each corner of a full 3x3 grid, with the directions that stay inside it.
+        let corners = [
+            ((0, 0), [East, South, Southeast]),
+            ((2, 0), [West, South, Southwest]),
+            ((0, 2), [East, North, Northeast]),
+            ((2, 2), [West, North, Northwest]),
+        ];
+        for ((x, y), inside) in corners {
+            let neighbors = grid.neighbors(grid.cell_index(x, y), 1);
+            assert!(exact(&inside).matches(alive, &neighbors, &ruleset, 0));
+            // A missing matching direction fails.
+            assert!(!exact(&inside[..2]).matches(alive, &neighbors, &ruleset, 0));
+            // Out-of-bounds neighbors never match, so selecting one fails.
+            let outside = *Direction::ALL
+                .iter()
+                .find(|direction| !inside.contains(direction))
+                .expect("A corner has neighbors outside the grid.");
+            let mut too_many = inside.to_vec();
+            too_many.push(outside);
+            assert!(!exact(&too_many).matches(alive, &neighbors, &ruleset, 0));
+        }
+
+        // The center sees all eight neighbors.
+        let center = grid.neighbors(grid.cell_index(1, 1), 1);
+        assert!(exact(&Direction::ALL).matches(alive, &center, &ruleset, 0));
+        assert!(!exact(&[North]).matches(alive, &center, &ruleset, 0));
+    }
+
+    #[test]
+    fn offset_pattern() {
+        use crate::engine::{grid::Boundary, material::Material};
+
+        let mut ruleset = Ruleset::blank();
+        let dead = Cell::new(ruleset.materials.default().id());
+        let material = Material::new(&ruleset);
+        let alive = Cell::new(material.id());
+        ruleset.materials.push(material);
+        let mut grid = Grid::new(ruleset.clone(), 5);
+        grid.set_cell(2, 4, alive);
+        let condition = |offsets: Vec<(i8, i8)>| Condition {
+            variant: ConditionVariant::OffsetPattern(offsets),
+            pattern: Pattern::Material(alive.material_id),
+            inverted: false,
+            neighborhood: Neighborhood::Moore,
+            radius: 1,
+            directional_mode: DirectionalMode::Any,
+        };
+        let matches = |grid: &Grid, condition: &Condition| {
+            let neighbors = grid.neighbors(grid.cell_index(2, 2), condition.neighbor_radius());
+            condition.matches(dead, &neighbors, &ruleset, 0)
+        };
+
+        let below = condition(vec![(0, 2)]);
+        assert_eq!(below.neighbor_radius(), 2);
+        assert!(matches(&grid, &below));
+        assert!(!matches(&grid, &condition(vec![(0, -2), (1, 2)])));
+        let all = Condition {
+            directional_mode: DirectionalMode::All,
+            ..condition(vec![(0, 2), (1, 2)])
+        };
+        assert!(!matches(&grid, &all));
+
+        // Three cells up leaves the grid, so it only reaches the bottom row when wrapping.
+        let above = condition(vec![(0, -3)]);
+        assert!(!matches(&grid, &above));
+        grid.set_boundary(Boundary::Wrap);
+        assert!(matches(&grid, &above));
+
+        let text = toml::to_string(&above).unwrap_or_default();
+        assert_eq!(toml::from_str::<Condition>(&text).ok(), Some(above));
+
+        assert_eq!(ConditionVariant::parse_offset("1, -2"), Some((1, -2)));
+        assert_eq!(ConditionVariant::parse_offset("-20 3"), Some((-7, 3)));
+        assert_eq!(ConditionVariant::parse_offset("0,0"), None);
+        assert_eq!(ConditionVariant::parse_offset("1"), None);
+    }
+}
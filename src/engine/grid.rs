@@ -0,0 +1,2429 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use image::{imageops::FilterType, DynamicImage};
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    rngs::StdRng,
+    SeedableRng,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    condition::{Condition, Direction, Neighborhood},
+    id::{Identifiable, UniqueId},
+    material::{MaterialColor, MaterialId},
+    pattern::Pattern,
+    ruleset::{check_file_name, ConflictPolicy, Ruleset, Transformation},
+};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Grid {
+    pub ruleset: Ruleset,
+    cells: Vec<Cell>,
+    pub size: usize,
+    /// How many generations have run since the grid was created.
+    generation: usize,
+    /// Hashes of the most recent generations, oldest first.
+    history: VecDeque<u64>,
+    period: Option<usize>,
+    /// Per-material populations of the most recent generations, oldest first,
+    /// indexed in the same order as `ruleset.materials`.
+    population: VecDeque<Vec<usize>>,
+    /// How recently and often each cell has changed, from 0 for idle cells up to
+    /// `u16::MAX` for cells that change every generation.
+    activity: Vec<u16>,
+    /// The percentage of each cell's activity kept from one generation to the next.
+    activity_decay: u8,
+    /// What neighbors past the edges of the grid are.
+    boundary: Boundary,
+    /// Mixed into every random choice the rules make, so runs can be replayed.
+    seed: u64,
+    /// How many cells each rule, by index, fired on in the last generation.
+    rule_counts: Vec<usize>,
+}
+impl Grid {
+    /// How many generations are remembered when looking for cycles.
+    /// Cycles with a longer period won't be detected.
+    pub const PERIOD_HISTORY: usize = 256;
+    /// How many generations of population counts are kept for plotting.
+    pub const POPULATION_HISTORY: usize = 200;
+    /// The highest activity decay; at 100% activity would never fade or build up.
+    pub const MAX_ACTIVITY_DECAY: u8 = 99;
+    const DEFAULT_ACTIVITY_DECAY: u8 = 90;
+    /// The smallest grid; an empty grid has nothing to show or step.
+    pub const MIN_SIZE: usize = 1;
+    /// The largest grid, beyond which stepping and drawing become impractically slow.
+    pub const MAX_SIZE: usize = 1000;
+
+    /// Creates a grid filled with the default material. Sizes outside
+    /// [`MIN_SIZE`](Self::MIN_SIZE)..=[`MAX_SIZE`](Self::MAX_SIZE) are clamped into range.
+    pub fn new(ruleset: Ruleset, size: usize) -> Self {
+        let size = size.clamp(Self::MIN_SIZE, Self::MAX_SIZE);
+        let material = ruleset.materials.default();
+        let cell = Cell::new(material.id());
+        let cells = vec![cell; size * size];
+        Self {
+            ruleset,
+            cells,
+            size,
+            generation: 0,
+            history: VecDeque::new(),
+            period: None,
+            population: VecDeque::new(),
+            activity: vec![0; size * size],
+            activity_decay: Self::DEFAULT_ACTIVITY_DECAY,
+            boundary: Boundary::default(),
+            seed: 0,
+            rule_counts: Vec::new(),
+        }
+    }
+
+    /// Creates a grid by scaling `image` to `size` and giving each cell the material whose
+    /// color is nearest to its pixel.
+    pub fn from_image(image: &DynamicImage, ruleset: Ruleset, size: usize) -> Self {
+        let size = size.clamp(Self::MIN_SIZE, Self::MAX_SIZE);
+        let length = u32::try_from(size).unwrap_or(u32::MAX);
+        let cells = image
+            .resize_exact(length, length, FilterType::Triangle)
+            .to_rgb8()
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b] = pixel.0;
+                Cell::new(ruleset.materials.nearest(MaterialColor::new(r, g, b)).id())
+            })
+            .collect();
+        let mut grid = Self::new(ruleset, size);
+        grid.cells = cells;
+        grid
+    }
+
+    /// Writes the grid as RFC 4180 CSV, one row per line, with each cell as its material's name.
+    pub fn to_csv(&self) -> String {
+        self.cells
+            .chunks(self.size)
+            .map(|row| {
+                let names: Vec<String> = row
+                    .iter()
+                    .map(|cell| {
+                        let name = self
+                            .ruleset
+                            .materials
+                            .get(cell.material_id)
+                            .map_or("", |material| material.name.as_str());
+                        csv::escape(name)
+                    })
+                    .collect();
+                names.join(",") + "\r\n"
+            })
+            .collect()
+    }
+    /// Reads a grid from CSV written by [`Grid::to_csv`], looking up each material by name.
+    pub fn from_csv(text: &str, ruleset: Ruleset) -> Result<Self, String> {
+        let rows = csv::parse(text)?;
+        let size = rows.len();
+        if size == 0 {
+            return Err(String::from("Could not import CSV; it contains no rows."));
+        }
+        if size > Self::MAX_SIZE {
+            return Err(format!(
+                "Could not import CSV; it has {size} rows, more than the largest grid size of {}.",
+                Self::MAX_SIZE
+            ));
+        }
+        let mut cells = Vec::with_capacity(size * size);
+        for (y, row) in rows.iter().enumerate() {
+            if row.len() != size {
+                return Err(format!(
+                    "Could not import CSV; row {y} has {} entries, expected {size}.",
+                    row.len()
+                ));
+            }
+            for (x, name) in row.iter().enumerate() {
+                let material = ruleset
+                    .materials
+                    .iter()
+                    .find(|material| &material.name == name)
+                    .ok_or_else(|| {
+                        format!("Could not import CSV; unknown material '{name}' at ({x}, {y}).")
+                    })?;
+                cells.push(Cell::new(material.id()));
+            }
+        }
+        let mut grid = Self::new(ruleset, size);
+        grid.cells = cells;
+        Ok(grid)
+    }
+
+    /// Fills the part of `rect` inside the grid with materials drawn at random, each in proportion
+    /// to its weight. Does nothing if that part is empty or every weight is zero.
+    pub fn randomize_region(&mut self, rect: GridRect, weights: &[(MaterialId, u32)], seed: u64) {
+        let rect = rect.clipped(self.size);
+        if rect.is_empty() {
+            return;
+        }
+        let Ok(distribution) = WeightedIndex::new(weights.iter().map(|&(_, weight)| weight)) else {
+            return;
+        };
+        let mut random = StdRng::seed_from_u64(seed);
+        for (x, y) in rect.cells() {
+            let index = self.cell_index(x, y);
+            self.cells[index] = Cell::new(weights[distribution.sample(&mut random)].0);
+        }
+        self.reset_period();
+    }
+
+    /// The cells of the part of `rect` inside the grid, or `None` if that part is empty.
+    pub fn copy_region(&self, rect: GridRect) -> Option<GridRegion> {
+        let rect = rect.clipped(self.size);
+        if rect.is_empty() {
+            return None;
+        }
+        Some(GridRegion {
+            width: rect.width,
+            height: rect.height,
+            cells: rect
+                .cells()
+                .map(|(x, y)| self.cells[self.cell_index(x, y)])
+                .collect(),
+        })
+    }
+    /// Overwrites cells with `region`, its top left corner at `origin`, skipping any that fall
+    /// outside the grid. Cells whose material isn't in this grid's ruleset become the default
+    /// material; returns how many did.
+    pub fn paste_region(&mut self, origin: (usize, usize), region: &GridRegion) -> usize {
+        let default = Cell::new(self.ruleset.materials.default().id());
+        let mut missing = 0;
+        let rect = GridRect {
+            x: origin.0,
+            y: origin.1,
+            width: region.width,
+            height: region.height,
+        };
+        for ((x, y), &cell) in rect.cells().zip(&region.cells) {
+            if x >= self.size || y >= self.size {
+                continue;
+            }
+            let index = self.cell_index(x, y);
+            self.cells[index] = if self.ruleset.materials.get(cell.material_id).is_some() {
+                cell
+            } else {
+                missing += 1;
+                default
+            };
+        }
+        self.reset_period();
+        missing
+    }
+
+    /// Sets each of `offsets` to `new`, with the pattern they form centered on `center`.
+    /// Cells that fall outside the grid are skipped.
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    pub fn stamp(&mut self, offsets: &[(usize, usize)], center: (usize, usize), new: Cell) {
+        let width = offsets.iter().map(|&(x, _)| x).max().unwrap_or_default();
+        let height = offsets.iter().map(|&(_, y)| y).max().unwrap_or_default();
+        let left = center.0 as isize - (width / 2) as isize;
+        let top = center.1 as isize - (height / 2) as isize;
+        for &(x, y) in offsets {
+            let (x, y) = (left + x as isize, top + y as isize);
+            if (0..self.size as isize).contains(&x) && (0..self.size as isize).contains(&y) {
+                let index = self.cell_index(x as usize, y as usize);
+                self.cells[index] = new;
+            }
+        }
+        self.reset_period();
+    }
+
+    /// Advances only the part of `rect` inside the grid by one generation, leaving every other
+    /// cell frozen. Cells outside still count as neighbors, so the region changes exactly as it
+    /// would in a full step, except that a swap across its edge only moves the half inside.
+    /// Does nothing if that part is empty.
+    pub fn step_region(&mut self, rect: GridRect) {
+        let rect = rect.clipped(self.size);
+        if rect.is_empty() {
+            return;
+        }
+        let (new_cells, _) = self.stepped_cells(0..self.cells.len());
+        let old_cells = self.cells.clone();
+        for (x, y) in rect.cells() {
+            let index = self.cell_index(x, y);
+            self.cells[index] = new_cells[index];
+        }
+        self.record_activity(&old_cells);
+        self.generation += 1;
+        self.reset_period();
+        self.record_population();
+    }
+
+    pub const fn generation(&self) -> usize {
+        self.generation
+    }
+    pub const fn period(&self) -> Option<usize> {
+        self.period
+    }
+    pub fn reset_period(&mut self) {
+        self.history.clear();
+        self.period = None;
+    }
+    /// How many cells each rule, by index, fired on in the last generation. Empty before the
+    /// first generation; rules that didn't exist then have no count.
+    pub fn rule_counts(&self) -> &[usize] {
+        &self.rule_counts
+    }
+    pub fn reset_rule_counts(&mut self) {
+        self.rule_counts.clear();
+    }
+    fn cells_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.cells.hash(&mut hasher);
+        hasher.finish()
+    }
+    fn record_generation(&mut self) {
+        let hash = self.cells_hash();
+        if let Some(position) = self.history.iter().rposition(|&h| h == hash) {
+            self.period = Some(self.history.len() - position);
+        }
+        self.history.push_back(hash);
+        if self.history.len() > Self::PERIOD_HISTORY {
+            self.history.pop_front();
+        }
+    }
+
+    /// The indices of the cells on a straight line from `from` to `to`, both included.
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    pub fn line(&self, from: usize, to: usize) -> Vec<usize> {
+        let (x0, y0) = self.cell_coordinates(from);
+        let (x1, y1) = self.cell_coordinates(to);
+        let (mut x, mut y) = (x0 as isize, y0 as isize);
+        let (x1, y1) = (x1 as isize, y1 as isize);
+        let dx = (x1 - x).abs();
+        let dy = -(y1 - y).abs();
+        let step_x = if x < x1 { 1 } else { -1 };
+        let step_y = if y < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        let mut indices = vec![self.cell_index(x as usize, y as usize)];
+        while (x, y) != (x1, y1) {
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+            indices.push(self.cell_index(x as usize, y as usize));
+        }
+        indices
+    }
+
+    /// Counts how many cells hold each material, in the same order as `ruleset.materials`.
+    pub fn population_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.ruleset.materials.len()];
+        for cell in &self.cells {
+            if let Some(index) = self.ruleset.materials.index_of(cell.material_id) {
+                counts[index] += 1;
+            }
+        }
+        counts
+    }
+    fn record_population(&mut self) {
+        self.population.push_back(self.population_counts());
+        if self.population.len() > Self::POPULATION_HISTORY {
+            self.population.pop_front();
+        }
+    }
+    /// Per-material populations of the most recent generations, oldest first, indexed in the
+    /// same order as `ruleset.materials`.
+    pub const fn population(&self) -> &VecDeque<Vec<usize>> {
+        &self.population
+    }
+    pub fn clear_population(&mut self) {
+        self.population.clear();
+    }
+
+    /// Decays every cell's activity, then raises it for each cell that differs from `old_cells`.
+    /// A cell that changes every generation approaches full activity.
+    fn record_activity(&mut self, old_cells: &[Cell]) {
+        self.activity.resize(self.cells.len(), 0);
+        let decay = u32::from(self.activity_decay);
+        let gain = u32::from(u16::MAX) * (100 - decay) / 100;
+        for ((activity, old), new) in self.activity.iter_mut().zip(old_cells).zip(&self.cells) {
+            let mut heat = u32::from(*activity) * decay / 100;
+            if old != new {
+                heat += gain;
+            }
+            *activity = u16::try_from(heat).unwrap_or(u16::MAX);
+        }
+    }
+    pub fn reset_activity(&mut self) {
+        self.activity = vec![0; self.cells.len()];
+    }
+    pub const fn activity_decay(&self) -> u8 {
+        self.activity_decay
+    }
+    pub fn set_activity_decay(&mut self, decay: u8) {
+        self.activity_decay = decay.min(Self::MAX_ACTIVITY_DECAY);
+    }
+    pub const fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+    pub const fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+    }
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+    pub const fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Changes the size of the grid, keeping the cells in the overlapping top-left region.
+    /// New cells use the default material. Fails, leaving the grid as it was, if `new_size` is
+    /// outside [`MIN_SIZE`](Self::MIN_SIZE)..=[`MAX_SIZE`](Self::MAX_SIZE).
+    pub fn resize(&mut self, new_size: usize) -> Result<(), String> {
+        if !(Self::MIN_SIZE..=Self::MAX_SIZE).contains(&new_size) {
+            return Err(format!(
+                "Grid size must be between {} and {}, not {new_size}",
+                Self::MIN_SIZE,
+                Self::MAX_SIZE
+            ));
+        }
+        self.resize_cells(new_size);
+        Ok(())
+    }
+    fn resize_cells(&mut self, new_size: usize) {
+        let default = Cell::new(self.ruleset.materials.default().id());
+        self.cells = (0..new_size * new_size)
+            .map(|index| {
+                let (x, y) = (index % new_size, index / new_size);
+                if x < self.size && y < self.size {
+                    self.cells[self.cell_index(x, y)]
+                } else {
+                    default
+                }
+            })
+            .collect();
+        self.size = new_size;
+        self.reset_period();
+        self.reset_activity();
+    }
+
+    /// Mirrors the grid left to right.
+    pub fn flip_horizontal(&mut self) {
+        let last = self.size.saturating_sub(1);
+        self.rearrange(|x, y| (last - x, y));
+    }
+    /// Mirrors the grid top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let last = self.size.saturating_sub(1);
+        self.rearrange(|x, y| (x, last - y));
+    }
+    /// Turns the grid a quarter turn clockwise.
+    pub fn rotate_cw(&mut self) {
+        let last = self.size.saturating_sub(1);
+        self.rearrange(|x, y| (y, last - x));
+    }
+    /// Turns the grid a quarter turn counterclockwise.
+    pub fn rotate_ccw(&mut self) {
+        let last = self.size.saturating_sub(1);
+        self.rearrange(|x, y| (last - y, x));
+    }
+    /// Rebuilds the grid so each position takes the cell, and its activity, from the position
+    /// `source` maps it to.
+    fn rearrange(&mut self, source: impl Fn(usize, usize) -> (usize, usize)) {
+        let sources: Vec<usize> = (0..self.cells.len())
+            .map(|index| {
+                let (x, y) = self.cell_coordinates(index);
+                let (x, y) = source(x, y);
+                self.cell_index(x, y)
+            })
+            .collect();
+        self.cells = sources.iter().map(|&index| self.cells[index]).collect();
+        if self.activity.len() == self.cells.len() {
+            self.activity = sources.iter().map(|&index| self.activity[index]).collect();
+        }
+        self.reset_period();
+    }
+
+    pub const fn cell_index(&self, x: usize, y: usize) -> usize {
+        y * self.size + x
+    }
+    pub const fn cell_coordinates(&self, index: usize) -> (usize, usize) {
+        (index % self.size, index / self.size)
+    }
+
+    /// Lists every rule that matches the cell at `index` and what each would do to it,
+    /// or `None` if the index is outside the grid.
+    pub fn explain(&self, index: usize) -> Option<CellExplanation> {
+        let cell = *self.cells.get(index)?;
+        let matches = matching_rules(self, index, cell, None);
+        let applied =
+            self.ruleset
+                .conflict_policy
+                .choose::<StdRng>(&self.ruleset.rules, &matches, None);
+        Some(CellExplanation {
+            cell,
+            neighbors: self.neighbors(index, 1).cells,
+            matches,
+            applied,
+        })
+    }
+    /// Every cell after one generation of the ruleset, evaluating only the cells at `indices`
+    /// (in ascending order) and leaving the rest as they are, and how many cells each rule fired
+    /// on.
+    fn stepped_cells(&self, indices: impl IntoIterator<Item = usize>) -> (Vec<Cell>, Vec<usize>) {
+        let (transformations, rule_counts) = transformations(self, indices, self.seed);
+        let mut new_cells = self.cells.clone();
+        for (index, cell) in committed(&transformations, |index| self.cells[index]) {
+            new_cells[index] = cell;
+        }
+        (new_cells, rule_counts)
+    }
+
+    /// Runs this grid and a copy of it under `other` in lockstep for `generations` generations,
+    /// counting the cells that differ between them along the way. The copy starts from the same
+    /// cells, generation, boundary and seed, so the two runs only differ through their rules.
+    /// Cells whose materials `other` lacks start as its default material.
+    pub fn divergence(&self, other: Ruleset, generations: usize) -> Divergence {
+        let mut first = self.clone();
+        let mut second = Self::new(other, self.size);
+        second.load_state(self.functional_state());
+        second.set_boundary(self.boundary);
+        second.set_seed(self.seed);
+        let differing = |first: &Self, second: &Self| {
+            first
+                .cells
+                .iter()
+                .zip(&second.cells)
+                .filter(|(a, b)| a != b)
+                .count()
+        };
+        let mut counts = Vec::with_capacity(generations + 1);
+        counts.push(differing(&first, &second));
+        for _ in 0..generations {
+            first.next_generation();
+            second.next_generation();
+            counts.push(differing(&first, &second));
+        }
+        Divergence {
+            start: self.generation,
+            counts,
+        }
+    }
+
+    pub fn visual_state(&self) -> VisualGridState {
+        VisualGridState {
+            size: self.size,
+            cells: self.cells.iter().map(|&c| c.color(&self.ruleset)).collect(),
+            activity: self.activity.clone(),
+        }
+    }
+    pub fn functional_state(&self) -> FunctionalGridState {
+        FunctionalGridState {
+            size: self.size,
+            cells: self.cells.clone(),
+            generation: self.generation,
+            seed: None,
+        }
+    }
+
+    /// Loads the given state, replacing any cells whose material doesn't exist in this grid's
+    /// ruleset with the default material; returns how many were replaced.
+    pub fn load_state(&mut self, state: FunctionalGridState) -> usize {
+        let default = self.ruleset.materials.default().id();
+        let mut missing = 0;
+        self.size = state.size;
+        self.generation = state.generation;
+        self.cells = state
+            .cells
+            .into_iter()
+            .map(|cell| {
+                if self.ruleset.materials.get(cell.material_id).is_some() {
+                    cell
+                } else {
+                    missing += 1;
+                    Cell::new(default)
+                }
+            })
+            .collect();
+        self.reset_period();
+        self.reset_activity();
+        self.reset_rule_counts();
+        missing
+    }
+    /// Loads the given state like [`load_state`](Self::load_state), but keeps this grid's size:
+    /// a larger state is clipped to its top-left corner, and a smaller one is padded with the
+    /// default material.
+    pub fn load_state_clipped(&mut self, state: FunctionalGridState) -> usize {
+        let size = self.size;
+        let missing = self.load_state(state);
+        if self.size != size {
+            self.resize_cells(size);
+        }
+        missing
+    }
+}
+
+/// Storage for a square grid of cells that rules can be evaluated on and advanced.
+pub trait GridBackend {
+    fn ruleset(&self) -> &Ruleset;
+    /// How many cells wide and tall the grid is.
+    fn size(&self) -> usize;
+    /// How many generations have run since the grid was created.
+    fn generation(&self) -> usize;
+    /// What neighbors past the edges of the grid are.
+    fn boundary(&self) -> Boundary;
+    /// The cell at these coordinates, or `None` if they're outside the grid. Coordinates past the
+    /// right edge are rejected rather than wrapping onto the next row.
+    fn cell_at(&self, x: usize, y: usize) -> Option<Cell>;
+    fn set_cell(&mut self, x: usize, y: usize, new: Cell);
+    /// Advances the grid by one generation.
+    fn next_generation(&mut self);
+
+    fn neighbors(&self, index: usize, radius: usize) -> CellNeighbors {
+        let radius = radius.clamp(1, Condition::MAX_RADIUS);
+        let offset = i8::try_from(radius).unwrap_or(1);
+        let cells = (-offset..=offset)
+            .flat_map(|y| (-offset..=offset).map(move |x| (x, y)))
+            .filter(|&offsets| offsets != (0, 0))
+            .map(|(x, y)| self.get_neighbor(index, x, y))
+            .collect();
+        CellNeighbors::new(radius, cells)
+    }
+    /// Like [`neighbors`](Self::neighbors), but also records the grid index of each neighbor, so
+    /// whatever a condition matched can be traced back to the cells involved.
+    fn neighbors_with_indices(&self, index: usize, radius: usize) -> CellNeighbors {
+        let radius = radius.clamp(1, Condition::MAX_RADIUS);
+        let offset = i8::try_from(radius).unwrap_or(1);
+        let indices = (-offset..=offset)
+            .flat_map(|y| (-offset..=offset).map(move |x| (x, y)))
+            .filter(|&offsets| offsets != (0, 0))
+            .map(|(x, y)| self.neighbor_index(index, x, y))
+            .collect();
+        self.neighbors(index, radius).with_indices(indices)
+    }
+    /// The grid indices of the eight adjacent cells, in the order of [`Direction::ALL`], with
+    /// `None` for those past a dead boundary.
+    fn neighbor_indices(&self, index: usize) -> [Option<usize>; 8] {
+        Direction::ALL.map(|direction| {
+            let (x, y) = direction.offset();
+            self.neighbor_index(index, x, y)
+        })
+    }
+    fn get_neighbor(&self, index: usize, x_offset: i8, y_offset: i8) -> Option<Cell> {
+        let size = self.size();
+        self.neighbor_index(index, x_offset, y_offset)
+            .and_then(|index| self.cell_at(index % size, index / size))
+    }
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+    fn neighbor_index(&self, index: usize, x_offset: i8, y_offset: i8) -> Option<usize> {
+        let size = self.size() as isize;
+        if size == 0 {
+            return None;
+        }
+        let x = (index as isize % size) + x_offset as isize;
+        let y = (index as isize / size) + y_offset as isize;
+        match self.boundary() {
+            Boundary::Wrap => Some((y.rem_euclid(size) * size + x.rem_euclid(size)) as usize),
+            Boundary::Dead if x < 0 || x >= size || y < 0 || y >= size => None,
+            Boundary::Dead => Some((y * size + x) as usize),
+        }
+    }
+}
+impl GridBackend for Grid {
+    fn ruleset(&self) -> &Ruleset {
+        &self.ruleset
+    }
+    fn size(&self) -> usize {
+        self.size
+    }
+    fn generation(&self) -> usize {
+        self.generation
+    }
+    fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    fn cell_at(&self, x: usize, y: usize) -> Option<Cell> {
+        if x >= self.size || y >= self.size {
+            return None;
+        }
+        self.cells.get(self.cell_index(x, y)).copied()
+    }
+    fn set_cell(&mut self, x: usize, y: usize, new: Cell) {
+        let index = self.cell_index(x, y);
+        if x >= self.size || y >= self.size || self.cells.get(index).is_none() {
+            println!("Tried setting value of non-existent cell. Aborting.");
+            return;
+        }
+        let _ = std::mem::replace(&mut self.cells[index], new);
+        self.reset_period();
+    }
+
+    /// Advances the grid by one generation.
+    ///
+    /// Every cell is transformed by one of the rules that match it, chosen by the ruleset's
+    /// [`ConflictPolicy`], or becomes the ruleset's fallback material if none do. Each cell draws
+    /// its random choices separately, seeded from the grid's seed, the policy's seed, the
+    /// generation and the cell's index, so they vary between generations but the same seeds,
+    /// ruleset and starting state always replay identically. Swaps are then committed in
+    /// ascending order of the index of the cell that proposed them: a swap is only accepted if
+    /// neither of its cells is already part of an accepted swap, so the lowest index wins any
+    /// conflict and rejected cells keep their current material. An accepted swap overrides
+    /// whatever transformation its target cell would otherwise have had.
+    ///
+    /// Large grids that are mostly the default material only evaluate the cells that can change,
+    /// the way a [`SparseGrid`] does. Since no cell's random choices depend on the others, that
+    /// plays out exactly like evaluating every cell, except that the rule counts leave out the
+    /// idle cells it skips.
+    fn next_generation(&mut self) {
+        if self.history.is_empty() {
+            self.history.push_back(self.cells_hash());
+        }
+        if self.population.is_empty() {
+            self.record_population();
+        }
+        let default = Cell::new(self.ruleset.materials.default().id());
+        let populated = || {
+            self.cells
+                .iter()
+                .enumerate()
+                .filter(move |&(_, &cell)| cell != default)
+                .map(|(index, _)| index)
+        };
+        let (new_cells, rule_counts) = if SparseGrid::suits(self.size, populated().count())
+            && is_quiescent(&self.ruleset, default, self.generation)
+        {
+            self.stepped_cells(fringe(self, populated()))
+        } else {
+            self.stepped_cells(0..self.cells.len())
+        };
+        self.rule_counts = rule_counts;
+        let old_cells = std::mem::replace(&mut self.cells, new_cells);
+        self.record_activity(&old_cells);
+        self.generation += 1;
+        self.record_generation();
+        self.record_population();
+    }
+}
+
+/// What each of the cells at `indices` transforms into this generation, by index in the same
+/// order, and how many of them each rule fired on. Every cell draws from its own random number
+/// generator, seeded from `seed`, the policy's seed, the generation and the cell's index, so what
+/// happens to it doesn't depend on which other cells are evaluated.
+fn transformations(
+    grid: &impl GridBackend,
+    indices: impl IntoIterator<Item = usize>,
+    seed: u64,
+) -> (Vec<(usize, Option<Transformation>)>, Vec<usize>) {
+    let size = grid.size();
+    let seed = seed ^ u64::from(grid.ruleset().conflict_policy.seed());
+    let generation = (grid.generation() as u64).rotate_left(32);
+    let mut rule_counts = vec![0; grid.ruleset().rules.len()];
+    let transformations = indices
+        .into_iter()
+        .filter_map(|index| {
+            let cell = grid.cell_at(index % size, index / size)?;
+            let mut random = StdRng::seed_from_u64(seed ^ generation ^ index as u64);
+            let transformation = transformation(grid, index, cell, &mut random, &mut rule_counts);
+            Some((index, transformation))
+        })
+        .collect();
+    (transformations, rule_counts)
+}
+
+/// The cells that `transformations`, in ascending order of index, set this generation, in the
+/// order they're set: a later entry for the same cell overrides an earlier one. A swap is only
+/// accepted if neither of its cells is already part of an accepted swap. `old` gives each cell as
+/// it was before any of them.
+fn committed(
+    transformations: &[(usize, Option<Transformation>)],
+    old: impl Fn(usize) -> Cell,
+) -> Vec<(usize, Cell)> {
+    let mut swapped = HashSet::new();
+    let mut changes = Vec::with_capacity(transformations.len());
+    for &(index, transformation) in transformations {
+        match transformation {
+            Some(Transformation::Replace(cell)) if !swapped.contains(&index) => {
+                changes.push((index, cell));
+            }
+            Some(Transformation::Swap(target))
+                if !swapped.contains(&index) && !swapped.contains(&target) =>
+            {
+                swapped.insert(index);
+                swapped.insert(target);
+                changes.push((index, old(target)));
+                changes.push((target, old(index)));
+            }
+            _ => {}
+        }
+    }
+    changes
+}
+
+/// Whether a `default` cell surrounded only by default cells stays as it is in `generation`.
+/// Only then can the cells far from any populated cell be skipped.
+fn is_quiescent(ruleset: &Ruleset, default: Cell, generation: usize) -> bool {
+    let side = 2 * Condition::MAX_RADIUS + 1;
+    let probe = SparseGrid {
+        ruleset: ruleset.clone(),
+        size: side,
+        default,
+        cells: HashMap::new(),
+        generation,
+        boundary: Boundary::Wrap,
+        seed: 0,
+        rule_counts: Vec::new(),
+    };
+    let center = Condition::MAX_RADIUS * side + Condition::MAX_RADIUS;
+    let matches = matching_rules(&probe, center, default, None);
+    let idle = Transformation::Replace(default);
+    if matches.is_empty() {
+        return ruleset.fallback.is_none_or(|id| id == default.material_id);
+    }
+    // A rule with several outputs could have drawn a different one.
+    matches.iter().all(|&(rule_index, transformation)| {
+        transformation == idle && ruleset.rules[rule_index].outputs.len() == 1
+    })
+}
+
+/// The indices of the cells that might change this generation, in ascending order, given the
+/// indices of every cell that isn't the default material: those cells, every cell close enough
+/// for a rule to see one, and with [`Boundary::Dead`] every cell close enough to see past an edge.
+fn fringe(grid: &impl GridBackend, populated: impl IntoIterator<Item = usize>) -> BTreeSet<usize> {
+    let size = grid.size();
+    let radius = grid
+        .ruleset()
+        .rules
+        .iter()
+        .flat_map(|rule| &rule.conditions)
+        .map(Condition::neighbor_radius)
+        .max()
+        .unwrap_or(1)
+        .clamp(1, Condition::MAX_RADIUS);
+    let reach = i8::try_from(radius).unwrap_or(1);
+    let mut fringe = BTreeSet::new();
+    for index in populated {
+        for y_offset in -reach..=reach {
+            for x_offset in -reach..=reach {
+                fringe.extend(grid.neighbor_index(index, x_offset, y_offset));
+            }
+        }
+    }
+    if grid.boundary() == Boundary::Dead {
+        for y in 0..size {
+            if y < radius || y + radius >= size {
+                fringe.extend((0..size).map(|x| y * size + x));
+            } else {
+                fringe.extend((0..radius).chain(size - radius..size).map(|x| y * size + x));
+            }
+        }
+    }
+    fringe
+}
+
+/// Every rule that matches `cell` at `index`, by index, with the transformation it produces.
+/// Rules with several outputs draw one from `random`, or take their heaviest without it.
+fn matching_rules(
+    grid: &impl GridBackend,
+    index: usize,
+    cell: Cell,
+    mut random: Option<&mut StdRng>,
+) -> Vec<(usize, Transformation)> {
+    grid.ruleset()
+        .rules
+        .iter()
+        .enumerate()
+        .filter_map(|(rule_index, rule)| {
+            rule.transformed(grid, cell, index, random.as_deref_mut())
+                .map(|transformation| (rule_index, transformation))
+        })
+        .collect()
+}
+
+/// What happens to `cell` at `index` this generation: the transformation of one of the rules
+/// that match it, chosen by the ruleset's [`ConflictPolicy`], or the fallback material if none do.
+/// Immovable cells never change, and never take part in swaps.
+/// The count in `rule_counts` of the rule that produced the transformation goes up by one.
+fn transformation(
+    grid: &impl GridBackend,
+    index: usize,
+    cell: Cell,
+    random: &mut StdRng,
+    rule_counts: &mut [usize],
+) -> Option<Transformation> {
+    let ruleset = grid.ruleset();
+    if ruleset.is_immovable(cell.material_id) {
+        return None;
+    }
+    let policy = ruleset.conflict_policy;
+    let fallback = ruleset
+        .fallback
+        .map(|id| (None, Transformation::Replace(Cell::new(id))));
+    let chosen = if policy == ConflictPolicy::FirstMatch {
+        ruleset
+            .rules
+            .iter()
+            .enumerate()
+            .find_map(|(rule_index, rule)| {
+                rule.transformed(grid, cell, index, Some(&mut *random))
+                    .map(|transformation| (Some(rule_index), transformation))
+            })
+            .or(fallback)
+    } else {
+        let matches = matching_rules(grid, index, cell, Some(&mut *random));
+        policy
+            .choose(&ruleset.rules, &matches, Some(random))
+            .map(|position| {
+                let (rule_index, transformation) = matches[position];
+                (Some(rule_index), transformation)
+            })
+            .or(fallback)
+    };
+    let (rule_index, transformation) = chosen?;
+    // Swapping with an immovable cell would move it, so such swaps are rejected.
+    if let Transformation::Swap(target) = transformation {
+        let size = grid.size();
+        let target_cell = grid.cell_at(target % size, target / size)?;
+        if ruleset.is_immovable(target_cell.material_id) {
+            return None;
+        }
+    }
+    if let Some(count) = rule_index.and_then(|rule_index| rule_counts.get_mut(rule_index)) {
+        *count += 1;
+    }
+    Some(transformation)
+}
+
+/// A grid that only stores the cells that aren't its ruleset's default material,
+/// for large boards that are mostly empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid {
+    ruleset: Ruleset,
+    size: usize,
+    /// The cell every position missing from `cells` holds.
+    default: Cell,
+    cells: HashMap<(usize, usize), Cell>,
+    generation: usize,
+    boundary: Boundary,
+    seed: u64,
+    /// How many of the evaluated cells each rule fired on in the last generation.
+    rule_counts: Vec<usize>,
+}
+impl SparseGrid {
+    /// Smaller grids are cheap enough to evaluate whole.
+    pub const MIN_SIZE: usize = 128;
+    /// The largest percentage of non-default cells at which sparse evaluation still pays off.
+    pub const MAX_FILL_PERCENT: usize = 10;
+
+    /// Whether a grid `size` cells wide with `populated` non-default cells is better advanced
+    /// sparsely.
+    pub const fn suits(size: usize, populated: usize) -> bool {
+        size >= Self::MIN_SIZE
+            && populated.saturating_mul(100) <= size * size * Self::MAX_FILL_PERCENT
+    }
+
+    pub fn from_grid(grid: &Grid) -> Self {
+        let default = Cell::new(grid.ruleset.materials.default().id());
+        let cells = grid
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|&(_, &cell)| cell != default)
+            .map(|(index, &cell)| (grid.cell_coordinates(index), cell))
+            .collect();
+        Self {
+            ruleset: grid.ruleset.clone(),
+            size: grid.size,
+            default,
+            cells,
+            generation: grid.generation,
+            boundary: grid.boundary,
+            seed: grid.seed,
+            rule_counts: Vec::new(),
+        }
+    }
+
+    /// Every cell in row-major order, the way [`Grid`] stores them.
+    pub fn to_cells(&self) -> Vec<Cell> {
+        let mut cells = vec![self.default; self.size * self.size];
+        for (&(x, y), &cell) in &self.cells {
+            cells[y * self.size + x] = cell;
+        }
+        cells
+    }
+}
+impl GridBackend for SparseGrid {
+    fn ruleset(&self) -> &Ruleset {
+        &self.ruleset
+    }
+    fn size(&self) -> usize {
+        self.size
+    }
+    fn generation(&self) -> usize {
+        self.generation
+    }
+    fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    fn cell_at(&self, x: usize, y: usize) -> Option<Cell> {
+        if x >= self.size || y >= self.size {
+            return None;
+        }
+        Some(self.cells.get(&(x, y)).copied().unwrap_or(self.default))
+    }
+    fn set_cell(&mut self, x: usize, y: usize, new: Cell) {
+        if x >= self.size || y >= self.size {
+            return;
+        }
+        if new == self.default {
+            self.cells.remove(&(x, y));
+        } else {
+            self.cells.insert((x, y), new);
+        }
+    }
+
+    /// Advances the grid by one generation, committing transformations and swaps the same way
+    /// [`Grid`] does.
+    ///
+    /// Rules can turn default cells into something else based on their neighbors, so besides the
+    /// populated cells this evaluates the fringe around them, and the grid's edges if neighbors
+    /// past them are missing. If the rules would change even a default cell with no populated
+    /// neighbors, every cell is evaluated instead.
+    fn next_generation(&mut self) {
+        let size = self.size;
+        let (transformations, rule_counts) =
+            if is_quiescent(&self.ruleset, self.default, self.generation) {
+                let populated = self.cells.keys().map(|&(x, y)| y * size + x);
+                transformations(self, fringe(self, populated), self.seed)
+            } else {
+                transformations(self, 0..size * size, self.seed)
+            };
+        self.rule_counts = rule_counts;
+        let default = self.default;
+        let old = |index: usize| {
+            self.cells
+                .get(&(index % size, index / size))
+                .copied()
+                .unwrap_or(default)
+        };
+        // Only the cells that change are touched, rather than rebuilding the map.
+        for (index, cell) in committed(&transformations, old) {
+            self.set_cell(index % size, index / size, cell);
+        }
+        self.generation += 1;
+    }
+}
+
+/// What a cell near the edge of the grid sees past it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Boundary {
+    /// There's nothing past the edge, so those neighbors never match anything.
+    #[default]
+    Dead,
+    /// Opposite edges are joined, so cells on one edge neighbor those on the other.
+    Wrap,
+}
+impl Boundary {
+    pub const ALL: [Self; 2] = [Self::Dead, Self::Wrap];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Dead => "Dead",
+            Self::Wrap => "Wrap",
+        }
+    }
+    pub fn names() -> Vec<String> {
+        Self::ALL
+            .iter()
+            .map(|boundary| boundary.name().to_owned())
+            .collect()
+    }
+    pub fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|&boundary| boundary == self)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionalGridState {
+    size: usize,
+    cells: Vec<Cell>,
+    #[serde(default)]
+    generation: usize,
+    /// The seed of the run the state came from, if it was recorded.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_seed",
+        deserialize_with = "deserialize_seed"
+    )]
+    seed: Option<u64>,
+}
+/// TOML integers are signed, so seeds are written as strings to fit every `u64`.
+#[allow(clippy::ref_option)]
+fn serialize_seed<S>(seed: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match seed {
+        Some(seed) => serializer.serialize_str(&seed.to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+fn deserialize_seed<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|seed| seed.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+impl FunctionalGridState {
+    pub const PATH: &str = "./states/";
+    pub const EXTENSION: &str = "state";
+    pub const CSV_EXTENSION: &str = "csv";
+
+    /// The path of the file named `name` with `extension` in the states directory. The extension
+    /// is added to the name rather than replacing any part of it that looks like one.
+    pub fn path(name: &str, extension: &str) -> Result<PathBuf, String> {
+        check_file_name(name).map_err(|err| format!("Could not use state file '{name}'; {err}"))?;
+        Ok(Path::new(Self::PATH).join(format!("{name}.{extension}")))
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        let string = toml::to_string(self)
+            .map_err(|err| format!("Could not save state; serialization failed: {err}"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| {
+                format!("Could not save state; could not create directory '{parent:?}': {err}")
+            })?;
+        }
+        fs::write(path, string)
+            .map_err(|err| format!("Could not save state to '{path:?}'; file IO failed: {err}"))
+    }
+    pub const fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+    pub const fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| {
+            format!("Could not load state; could not read file '{path:?}': {err}")
+        })?;
+        let state: Self = toml::from_str(&text).map_err(|err| {
+            format!("Could not load state; deserialization failed for file '{path:?}': {err}")
+        })?;
+        let expected = state
+            .size
+            .checked_mul(state.size)
+            .filter(|_| (Grid::MIN_SIZE..=Grid::MAX_SIZE).contains(&state.size))
+            .ok_or_else(|| {
+                format!(
+                    "Could not load state from '{path:?}'; size {} is outside {}..={}.",
+                    state.size,
+                    Grid::MIN_SIZE,
+                    Grid::MAX_SIZE
+                )
+            })?;
+        if state.cells.len() != expected {
+            return Err(format!(
+                "Could not load state from '{path:?}'; expected {expected} cells, got {}.",
+                state.cells.len()
+            ));
+        }
+        Ok(state)
+    }
+}
+
+/// A rectangle of cells, by the grid coordinates of its top left cell and its size in cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GridRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+impl GridRect {
+    /// The smallest rectangle containing both corner cells.
+    pub fn spanning(a: (usize, usize), b: (usize, usize)) -> Self {
+        Self {
+            x: a.0.min(b.0),
+            y: a.1.min(b.1),
+            width: a.0.abs_diff(b.0) + 1,
+            height: a.1.abs_diff(b.1) + 1,
+        }
+    }
+    pub const fn is_empty(self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+    /// The part of the rectangle that lies within a grid of `size` by `size` cells.
+    pub fn clipped(self, size: usize) -> Self {
+        Self {
+            width: (self.x + self.width).min(size).saturating_sub(self.x),
+            height: (self.y + self.height).min(size).saturating_sub(self.y),
+            ..self
+        }
+    }
+    /// The coordinates of every cell in the rectangle, in row-major order.
+    pub fn cells(self) -> impl Iterator<Item = (usize, usize)> {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+/// A rectangle of cells copied out of a grid, in row-major order. Cells keep their material ids,
+/// so they only paste faithfully into grids using the same ruleset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridRegion {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+impl GridRegion {
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+}
+
+/// How many cells differ between two runs from the same start, as found by [`Grid::divergence`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The generation both runs started from.
+    start: usize,
+    /// Differing cells at the start, then after each generation.
+    counts: Vec<usize>,
+}
+impl Divergence {
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+    /// The first generation at which any cells differ, if any ever do.
+    pub fn first_difference(&self) -> Option<usize> {
+        self.counts
+            .iter()
+            .position(|&count| count > 0)
+            .map(|offset| self.start + offset)
+    }
+    /// Writes the divergence curve as CSV, with a header row and one row per generation.
+    pub fn to_csv(&self) -> String {
+        let rows: Vec<String> = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(offset, count)| format!("{},{count}\r\n", self.start + offset))
+            .collect();
+        String::from("generation,differing_cells\r\n") + &rows.concat()
+    }
+}
+
+/// What the grid looks like: each cell's color and activity, without the rules behind them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VisualGridState {
+    size: usize,
+    cells: Vec<MaterialColor>,
+    activity: Vec<u16>,
+}
+impl VisualGridState {
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+    pub fn cells(&self) -> &[MaterialColor] {
+        &self.cells
+    }
+    pub fn activity(&self) -> &[u16] {
+        &self.activity
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cell {
+    pub material_id: MaterialId,
+}
+impl Serialize for Cell {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.material_id.serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Cell {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw_id = u32::deserialize(deserializer)?;
+        Ok(Self::new(UniqueId::new_unchecked(raw_id)))
+    }
+}
+impl Cell {
+    pub const fn new(material_id: MaterialId) -> Self {
+        Self { material_id }
+    }
+
+    pub fn color(self, ruleset: &Ruleset) -> MaterialColor {
+        ruleset
+            .materials
+            .get(self.material_id)
+            .expect("cell should point to a valid material id for this ruleset.")
+            .color
+    }
+}
+
+/// Why a cell does or doesn't change, as reported by [`Grid::explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellExplanation {
+    pub cell: Cell,
+    /// The eight surrounding cells in row-major order, `None` past the edge of the grid.
+    pub neighbors: Vec<Option<Cell>>,
+    /// The index of every matching rule with the transformation it produces. Rules with several
+    /// outputs show their heaviest one.
+    pub matches: Vec<(usize, Transformation)>,
+    /// The position in `matches` of the one [`Grid::next_generation`] applies,
+    /// or `None` if nothing matches or the ruleset chooses randomly.
+    pub applied: Option<usize>,
+}
+
+/// The cells within a square of side `2 * radius + 1` around a center cell, excluding the center,
+/// stored in row-major order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellNeighbors {
+    radius: usize,
+    cells: Vec<Option<Cell>>,
+    /// The grid index of each of `cells`, if the grid recorded them.
+    indices: Option<Vec<Option<usize>>>,
+}
+impl CellNeighbors {
+    pub fn new(radius: usize, cells: Vec<Option<Cell>>) -> Self {
+        debug_assert_eq!(
+            cells.len(),
+            (2 * radius + 1).pow(2) - 1,
+            "CellNeighbors should contain every cell within the radius except the center."
+        );
+        Self {
+            radius,
+            cells,
+            indices: None,
+        }
+    }
+    /// Records the grid index of each neighbor, in the same order as the cells.
+    pub fn with_indices(self, indices: Vec<Option<usize>>) -> Self {
+        debug_assert_eq!(
+            indices.len(),
+            self.cells.len(),
+            "CellNeighbors should have an index for every cell."
+        );
+        Self {
+            indices: Some(indices),
+            ..self
+        }
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn offsets(&self) -> impl Iterator<Item = (isize, isize)> {
+        let radius = self.radius as isize;
+        (-radius..=radius)
+            .flat_map(move |y| (-radius..=radius).map(move |x| (x, y)))
+            .filter(|&offsets| offsets != (0, 0))
+    }
+
+    pub fn count_matching(
+        &self,
+        ruleset: &Ruleset,
+        pattern: Pattern,
+        neighborhood: Neighborhood,
+        center: Cell,
+    ) -> u8 {
+        self.count_where(neighborhood, |cell| pattern.matches(ruleset, cell, center))
+    }
+    /// Counts the neighbors within `neighborhood` that satisfy `predicate`.
+    pub fn count_where(&self, neighborhood: Neighborhood, predicate: impl Fn(Cell) -> bool) -> u8 {
+        // println!("Matching: ---");
+        self.offsets()
+            .zip(&self.cells)
+            .filter(|&(offset, _)| neighborhood.contains(offset, self.radius))
+            .filter(|(_, cell)| cell.is_some_and(&predicate))
+            .count()
+            .try_into()
+            .expect("CellNeighbors count should not exceed the maximum neighborhood size.")
+    }
+    /// Counts the neighbors within `neighborhood` that lie inside the grid.
+    pub fn count_present(&self, neighborhood: Neighborhood) -> u8 {
+        self.count_where(neighborhood, |_| true)
+    }
+    pub fn in_direction(&self, direction: Direction) -> Option<Cell> {
+        let (x, y) = direction.offset();
+        self.at_offset(x, y)
+    }
+    /// The neighbor `x` cells to the right and `y` cells down from the center, or `None` if it
+    /// lies outside the grid or beyond the radius these neighbors were gathered with.
+    pub fn at_offset(&self, x: i8, y: i8) -> Option<Cell> {
+        self.cells.get(self.position(x, y)?).copied().flatten()
+    }
+    /// The grid index of the neighbor in `direction`, or `None` if it lies outside the grid or
+    /// the indices weren't recorded.
+    pub fn index_in_direction(&self, direction: Direction) -> Option<usize> {
+        let (x, y) = direction.offset();
+        self.index_at_offset(x, y)
+    }
+    /// The grid index of the neighbor `x` cells to the right and `y` cells down from the center,
+    /// or `None` if it lies outside the grid or the radius, or the indices weren't recorded.
+    pub fn index_at_offset(&self, x: i8, y: i8) -> Option<usize> {
+        let indices = self.indices.as_ref()?;
+        indices.get(self.position(x, y)?).copied().flatten()
+    }
+    /// Where the neighbor at this offset is stored, or `None` for the center and offsets beyond
+    /// the radius.
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn position(&self, x: i8, y: i8) -> Option<usize> {
+        let radius = self.radius as isize;
+        if (x, y) == (0, 0) || isize::from(x).abs() > radius || isize::from(y).abs() > radius {
+            return None;
+        }
+        let side = 2 * radius + 1;
+        let position = (isize::from(y) + radius) * side + (isize::from(x) + radius);
+        let center = radius * side + radius;
+        let index = if position > center {
+            position - 1
+        } else {
+            position
+        };
+        Some(index as usize)
+    }
+}
+
+mod csv {
+    pub fn escape(field: &str) -> String {
+        if field.contains([',', '"', '\r', '\n']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    /// Splits RFC 4180 CSV into rows of fields.
+    pub fn parse(text: &str) -> Result<Vec<Vec<String>>, String> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = text.chars().peekable();
+        while let Some(char) = chars.next() {
+            match (quoted, char) {
+                (true, '"') if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                (true, '"') => quoted = false,
+                (false, '"') if field.is_empty() => quoted = true,
+                (false, ',') => row.push(std::mem::take(&mut field)),
+                (false, '\r') if chars.peek() == Some(&'\n') => {}
+                (false, '\n' | '\r') => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                _ => field.push(char),
+            }
+        }
+        if quoted {
+            return Err(String::from(
+                "Could not import CSV; a quoted field is never closed.",
+            ));
+        }
+        if !field.is_empty() || !row.is_empty() {
+            row.push(field);
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{
+        condition::{Condition, ConditionVariant, DirectionalMode, Operator},
+        material::{Material, MaterialGroup},
+        preset::{Orientation, Preset},
+        ruleset::{Rule, RuleOutput},
+    };
+
+    use super::*;
+
+    fn swap_rule(input: MaterialId, target: MaterialId, direction: Direction) -> Rule {
+        Rule {
+            label: String::new(),
+            input: Pattern::Material(input),
+            outputs: vec![(RuleOutput::SwapDirection(direction), 1)],
+            conditions: vec![Condition {
+                variant: ConditionVariant::Directional(vec![direction]),
+                pattern: Pattern::Material(target),
+                inverted: false,
+                neighborhood: Neighborhood::Moore,
+                radius: 1,
+                directional_mode: DirectionalMode::Any,
+            }],
+        }
+    }
+
+    fn replace_rule(input: Pattern, output: MaterialId) -> Rule {
+        Rule {
+            label: String::new(),
+            input,
+            outputs: vec![(RuleOutput::Material(output), 1)],
+            conditions: Vec::new(),
+        }
+    }
+
+    fn sand_ruleset() -> (Ruleset, MaterialId, MaterialId) {
+        let mut ruleset = Ruleset::blank();
+        let air = ruleset.materials.default().id();
+        let sand = Material::new(&ruleset);
+        let sand_id = sand.id();
+        ruleset.materials.push(sand);
+        (ruleset, air, sand_id)
+    }
+
+    fn count(grid: &Grid, material: MaterialId) -> usize {
+        grid.cells
+            .iter()
+            .filter(|cell| cell.material_id == material)
+            .count()
+    }
+
+    #[test]
+    fn fallback() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        let ash = Material::new(&ruleset);
+        let ash_id = ash.id();
+        ruleset.materials.push(ash);
+        // Sand matches a rule that leaves it unchanged, so only air falls back to ash.
+        ruleset.rules.push(Rule {
+            label: String::new(),
+            input: Pattern::Material(sand),
+            outputs: vec![(RuleOutput::Material(sand), 1)],
+            conditions: vec![],
+        });
+        ruleset.fallback = Some(ash_id);
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_cell(1, 1, Cell::new(sand));
+
+        grid.next_generation();
+        assert_eq!(grid.cell_at(1, 1), Some(Cell::new(sand)));
+        assert_eq!(count(&grid, ash_id), 8);
+        assert_eq!(count(&grid, air), 0);
+    }
+
+    #[test]
+    fn swap_falls() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        ruleset.rules.push(swap_rule(sand, air, Direction::South));
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_cell(1, 0, Cell::new(sand));
+
+        grid.next_generation();
+        assert_eq!(grid.cell_at(1, 0), Some(Cell::new(air)));
+        assert_eq!(grid.cell_at(1, 1), Some(Cell::new(sand)));
+        grid.next_generation();
+        grid.next_generation();
+        assert_eq!(grid.cell_at(1, 2), Some(Cell::new(sand)));
+        assert_eq!(count(&grid, sand), 1);
+    }
+
+    #[test]
+    fn rule_matches() {
+        let (ruleset, air, sand) = sand_ruleset();
+        let falls = swap_rule(sand, air, Direction::South);
+        let sinks = Rule {
+            outputs: vec![(RuleOutput::CopyDirection(Direction::South), 1)],
+            conditions: vec![],
+            ..falls.clone()
+        };
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_cell(1, 0, Cell::new(sand));
+        grid.set_cell(1, 2, Cell::new(sand));
+
+        assert!(falls.matches(&grid, Cell::new(sand), 1));
+        assert!(!falls.matches(&grid, Cell::new(sand), 7));
+        assert!(!falls.matches(&grid, Cell::new(air), 4));
+        // Nothing lies south of the bottom row, so the rule applies but leaves the cell alone.
+        assert!(sinks.matches(&grid, Cell::new(sand), 7));
+        assert_eq!(
+            sinks.transformed::<StdRng>(&grid, Cell::new(sand), 7, None),
+            None
+        );
+    }
+
+    #[test]
+    fn swap_conflict() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        ruleset.rules.push(swap_rule(sand, air, Direction::East));
+        ruleset.rules.push(swap_rule(sand, air, Direction::West));
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_cell(0, 0, Cell::new(sand));
+        grid.set_cell(2, 0, Cell::new(sand));
+
+        // Both sand cells want to move into (1, 0); the lower index wins.
+        grid.next_generation();
+        assert_eq!(grid.cell_at(0, 0), Some(Cell::new(air)));
+        assert_eq!(grid.cell_at(1, 0), Some(Cell::new(sand)));
+        assert_eq!(grid.cell_at(2, 0), Some(Cell::new(sand)));
+        assert_eq!(count(&grid, sand), 2);
+    }
+
+    #[test]
+    fn explain() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        ruleset.rules.push(swap_rule(sand, air, Direction::South));
+        ruleset.rules.push(swap_rule(air, sand, Direction::North));
+        ruleset.rules.push(swap_rule(sand, air, Direction::East));
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_cell(1, 0, Cell::new(sand));
+
+        let explanation = grid.explain(1).expect("index should be inside the grid");
+        assert_eq!(explanation.cell, Cell::new(sand));
+        assert_eq!(
+            explanation.neighbors,
+            [
+                vec![None; 3],
+                vec![Some(Cell::new(air)); 2],
+                vec![Some(Cell::new(air)); 3],
+            ]
+            .concat()
+        );
+        assert_eq!(
+            explanation.matches,
+            vec![(0, Transformation::Swap(4)), (2, Transformation::Swap(2))]
+        );
+        assert_eq!(explanation.applied, Some(0));
+        assert_eq!(grid.explain(9), None);
+    }
+
+    #[test]
+    fn conflict_policies() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        let stone = Material::new(&ruleset);
+        let stone_id = stone.id();
+        ruleset.materials.push(stone);
+        ruleset
+            .rules
+            .push(replace_rule(Pattern::Material(air), sand));
+        ruleset
+            .rules
+            .push(replace_rule(Pattern::Material(air), stone_id));
+        let with_policy = |policy| {
+            let mut ruleset = ruleset.clone();
+            ruleset.conflict_policy = policy;
+            let mut grid = Grid::new(ruleset, 10);
+            grid.next_generation();
+            grid
+        };
+
+        // With equally specific rules, both deterministic policies fall back to rule order.
+        assert_eq!(count(&with_policy(ConflictPolicy::FirstMatch), sand), 100);
+        assert_eq!(count(&with_policy(ConflictPolicy::MostSpecific), sand), 100);
+
+        // Random choices mix both rules, and replay exactly for the same seed.
+        let random = with_policy(ConflictPolicy::Random(7));
+        assert!(count(&random, sand) > 0);
+        assert!(count(&random, stone_id) > 0);
+        assert_eq!(count(&random, sand) + count(&random, stone_id), 100);
+        assert_eq!(random.cells, with_policy(ConflictPolicy::Random(7)).cells);
+        assert_ne!(random.cells, with_policy(ConflictPolicy::Random(8)).cells);
+
+        // So does the grid's own seed.
+        let reseeded = |seed| {
+            let mut grid = Grid::new(random.ruleset.clone(), 10);
+            grid.set_seed(seed);
+            grid.next_generation();
+            grid
+        };
+        assert_eq!(reseeded(0).cells, random.cells);
+        assert_eq!(reseeded(3).cells, reseeded(3).cells);
+        assert_ne!(reseeded(3).cells, random.cells);
+    }
+
+    #[test]
+    fn most_specific() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        let mut group = MaterialGroup::new(&ruleset);
+        group.push(air);
+        let group_id = group.id();
+        ruleset.groups.push(group);
+        ruleset
+            .rules
+            .push(replace_rule(Pattern::Group(group_id), sand));
+        ruleset
+            .rules
+            .push(replace_rule(Pattern::Material(air), air));
+
+        ruleset.conflict_policy = ConflictPolicy::FirstMatch;
+        let mut grid = Grid::new(ruleset.clone(), 3);
+        grid.next_generation();
+        assert_eq!(count(&grid, sand), 9);
+
+        // A single material is more specific than a group, so the later rule wins.
+        ruleset.conflict_policy = ConflictPolicy::MostSpecific;
+        let mut grid = Grid::new(ruleset, 3);
+        grid.next_generation();
+        assert_eq!(count(&grid, sand), 0);
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn serde_state() {
+        let (ruleset, air, sand) = sand_ruleset();
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_cell(1, 1, Cell::new(sand));
+        grid.set_cell(
+            2,
+            1,
+            Cell::new(UniqueId::new_unchecked(sand.get().wrapping_add(1))),
+        );
+
+        let state_string = toml::to_string(&grid.functional_state()).unwrap();
+        println!("{state_string:?}");
+        assert!(!state_string.contains("seed"));
+        let state: FunctionalGridState = toml::from_str(&state_string).unwrap();
+        assert_eq!(state.seed(), None);
+
+        let mut seeded = state.clone();
+        seeded.set_seed(Some(u64::MAX));
+        let seeded_string = toml::to_string(&seeded).unwrap();
+        let seeded: FunctionalGridState = toml::from_str(&seeded_string).unwrap();
+        assert_eq!(seeded.seed(), Some(u64::MAX));
+
+        // Materials missing from the ruleset are replaced with the default.
+        assert_eq!(grid.load_state(state), 1);
+        assert_eq!(grid.cell_at(1, 1), Some(Cell::new(sand)));
+        assert_eq!(grid.cell_at(2, 1), Some(Cell::new(air)));
+    }
+
+    #[test]
+    fn parity() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        let on_step = |remainder| Condition {
+            variant: ConditionVariant::Generation {
+                modulus: 3,
+                remainder,
+            },
+            pattern: Pattern::Material(air),
+            inverted: false,
+            neighborhood: Neighborhood::Moore,
+            radius: 1,
+            directional_mode: DirectionalMode::Any,
+        };
+        let mut appear = replace_rule(Pattern::Material(air), sand);
+        appear.conditions.push(on_step(1));
+        let mut vanish = replace_rule(Pattern::Material(sand), air);
+        vanish.conditions.push(on_step(2));
+        ruleset.rules.extend([appear, vanish]);
+        let mut grid = Grid::new(ruleset, 2);
+
+        let mut sand_counts = Vec::new();
+        for _ in 0..7 {
+            grid.next_generation();
+            sand_counts.push(count(&grid, sand));
+        }
+        // Sand appears when generation 1 (mod 3) runs and vanishes when generation 2 does.
+        assert_eq!(sand_counts, [0, 4, 0, 0, 4, 0, 0]);
+        assert_eq!(grid.generation(), 7);
+    }
+
+    #[test]
+    fn self_pattern_clusters() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        // Any cell without a single neighbor of its own material turns to air.
+        let mut isolated = replace_rule(Pattern::SelfSame, air);
+        isolated.conditions.push(Condition {
+            variant: ConditionVariant::Count(Operator::List(vec![0])),
+            pattern: Pattern::SelfSame,
+            inverted: false,
+            neighborhood: Neighborhood::Moore,
+            radius: 1,
+            directional_mode: DirectionalMode::Any,
+        });
+        ruleset.rules.push(isolated);
+        let mut grid = Grid::new(ruleset, 5);
+        grid.set_cell(0, 0, Cell::new(sand));
+        for (x, y) in [(3, 3), (4, 3), (3, 4), (4, 4)] {
+            grid.set_cell(x, y, Cell::new(sand));
+        }
+
+        grid.next_generation();
+        assert_eq!(grid.cell_at(0, 0), Some(Cell::new(air)));
+        assert_eq!(grid.cell_at(3, 3), Some(Cell::new(sand)));
+        assert_eq!(count(&grid, sand), 4);
+        assert_eq!(count(&grid, air), 21);
+    }
+
+    #[test]
+    fn activity() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        ruleset
+            .rules
+            .push(replace_rule(Pattern::Material(air), sand));
+        ruleset
+            .rules
+            .push(replace_rule(Pattern::Material(sand), air));
+        let mut grid = Grid::new(ruleset, 2);
+
+        // Every cell flips each generation, so activity builds toward the maximum.
+        let mut last = 0;
+        for _ in 0..100 {
+            grid.next_generation();
+            assert!(grid.activity[0] > last || grid.activity[0] > 65_000);
+            last = grid.activity[0];
+        }
+        assert!(last > 65_000);
+
+        // Once the grid stops changing, activity fades.
+        grid.ruleset.rules.clear();
+        grid.set_activity_decay(50);
+        grid.next_generation();
+        assert_eq!(grid.activity[0], last / 2);
+
+        grid.set_activity_decay(100);
+        assert_eq!(grid.activity_decay(), Grid::MAX_ACTIVITY_DECAY);
+        grid.reset_activity();
+        assert!(grid.activity.iter().all(|&activity| activity == 0));
+    }
+
+    #[test]
+    fn edge_bounds() {
+        let (ruleset, air, sand) = sand_ruleset();
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_cell(0, 1, Cell::new(sand));
+
+        // (3, 0) would wrap onto (0, 1) if only the index were checked.
+        assert_eq!(grid.cell_at(3, 0), None);
+        assert_eq!(grid.cell_at(0, 3), None);
+        grid.set_cell(3, 0, Cell::new(air));
+        assert_eq!(grid.cell_at(0, 1), Some(Cell::new(sand)));
+
+        // Neighbors past the east and south edges don't exist, including diagonals.
+        let east_edge = grid.cell_index(2, 0);
+        assert_eq!(grid.get_neighbor(east_edge, 1, 0), None);
+        assert_eq!(grid.get_neighbor(east_edge, 1, 1), None);
+        assert_eq!(grid.get_neighbor(east_edge, 0, 1), Some(Cell::new(air)));
+        let south_edge = grid.cell_index(0, 2);
+        assert_eq!(grid.get_neighbor(south_edge, 0, 1), None);
+        assert_eq!(grid.get_neighbor(south_edge, 1, 1), None);
+        assert_eq!(grid.get_neighbor(south_edge, 0, -1), Some(Cell::new(sand)));
+    }
+
+    #[test]
+    fn wrap_boundary() {
+        let (ruleset, air, sand) = sand_ruleset();
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_cell(0, 1, Cell::new(sand));
+        grid.set_boundary(Boundary::Wrap);
+
+        // Past the east edge is the west edge, and past the corner is the opposite corner.
+        let east_edge = grid.cell_index(2, 1);
+        assert_eq!(grid.get_neighbor(east_edge, 1, 0), Some(Cell::new(sand)));
+        assert_eq!(
+            grid.neighbor_index(east_edge, 1, 1),
+            Some(grid.cell_index(0, 2))
+        );
+        let corner = grid.cell_index(0, 0);
+        assert_eq!(
+            grid.neighbor_index(corner, -1, -1),
+            Some(grid.cell_index(2, 2))
+        );
+        assert_eq!(grid.get_neighbor(corner, 0, -1), Some(Cell::new(air)));
+
+        grid.set_boundary(Boundary::Dead);
+        assert_eq!(grid.get_neighbor(east_edge, 1, 0), None);
+    }
+
+    #[test]
+    fn randomize_region() {
+        let (ruleset, air, sand) = sand_ruleset();
+        let mut grid = Grid::new(ruleset, 4);
+        let weights = [(air, 0), (sand, 1)];
+
+        // Only the part of the rectangle inside the grid is filled.
+        let rect = GridRect {
+            x: 2,
+            y: 1,
+            width: 5,
+            height: 2,
+        };
+        grid.randomize_region(rect, &weights, 3);
+        assert_eq!(count(&grid, sand), 4);
+        for (x, y) in rect.clipped(4).cells() {
+            assert_eq!(grid.cell_at(x, y), Some(Cell::new(sand)));
+        }
+
+        let before = grid.clone();
+        grid.randomize_region(GridRect::default(), &weights, 3);
+        grid.randomize_region(GridRect::spanning((0, 0), (3, 3)), &[(sand, 0)], 3);
+        assert_eq!(grid.cells, before.cells);
+
+        let mixed = [(air, 1), (sand, 1)];
+        let whole = GridRect::spanning((3, 3), (0, 0));
+        let mut first = Grid::new(grid.ruleset.clone(), 4);
+        let mut second = first.clone();
+        first.randomize_region(whole, &mixed, 9);
+        second.randomize_region(whole, &mixed, 9);
+        assert_eq!(first.cells, second.cells);
+    }
+
+    #[test]
+    fn copy_paste_region() {
+        let (ruleset, air, sand) = sand_ruleset();
+        let mut grid = Grid::new(ruleset, 4);
+        grid.set_cell(0, 0, Cell::new(sand));
+        grid.set_cell(1, 1, Cell::new(sand));
+
+        let region = grid
+            .copy_region(GridRect::spanning((0, 0), (1, 1)))
+            .expect("The region is inside the grid.");
+        assert_eq!((region.width(), region.height()), (2, 2));
+        assert_eq!(grid.copy_region(GridRect::spanning((4, 4), (5, 5))), None);
+
+        // Pasting near the corner clips the parts that fall outside.
+        assert_eq!(grid.paste_region((3, 2), &region), 0);
+        assert_eq!(grid.cell_at(3, 2), Some(Cell::new(sand)));
+        assert_eq!(grid.cell_at(3, 3), Some(Cell::new(air)));
+        assert_eq!(count(&grid, sand), 3);
+
+        // Materials missing from another ruleset fall back to its default.
+        let mut other = Grid::new(Ruleset::blank(), 4);
+        assert_eq!(other.paste_region((0, 0), &region), 2);
+        assert_eq!(count(&other, other.ruleset.materials.default().id()), 16);
+    }
+
+    #[test]
+    fn flip_and_rotate() {
+        let (ruleset, _, sand) = sand_ruleset();
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_cell(1, 0, Cell::new(sand));
+        grid.set_cell(2, 0, Cell::new(sand));
+        let original = grid.cells.clone();
+        let sand_at = |grid: &Grid| {
+            (0..grid.cells.len())
+                .filter(|&index| grid.cells[index].material_id == sand)
+                .map(|index| grid.cell_coordinates(index))
+                .collect::<Vec<_>>()
+        };
+
+        grid.flip_horizontal();
+        assert_eq!(sand_at(&grid), [(0, 0), (1, 0)]);
+        grid.flip_horizontal();
+        grid.flip_vertical();
+        assert_eq!(sand_at(&grid), [(1, 2), (2, 2)]);
+        grid.flip_vertical();
+
+        // The top row becomes the right column.
+        grid.rotate_cw();
+        assert_eq!(sand_at(&grid), [(2, 1), (2, 2)]);
+        grid.rotate_ccw();
+        assert_eq!(grid.cells, original);
+        grid.rotate_ccw();
+        assert_eq!(sand_at(&grid), [(0, 0), (0, 1)]);
+        for _ in 0..4 {
+            grid.rotate_ccw();
+        }
+        assert_eq!(sand_at(&grid), [(0, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn blinker_period() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        ruleset.rules.push(swap_rule(sand, air, Direction::South));
+        ruleset.rules.push(swap_rule(sand, air, Direction::North));
+        let mut grid = Grid::new(ruleset, 2);
+        grid.set_cell(0, 0, Cell::new(sand));
+
+        grid.next_generation();
+        assert_eq!(grid.period(), None);
+        grid.next_generation();
+        assert_eq!(grid.period(), Some(2));
+
+        grid.set_cell(1, 0, Cell::new(sand));
+        assert_eq!(grid.period(), None);
+    }
+
+    #[test]
+    fn game_of_life_blinker() {
+        let ruleset = Ruleset::game_of_life();
+        let alive = ruleset.materials.get_at(1).map(Identifiable::id);
+        let alive = Cell::new(alive.expect("life should have an alive material"));
+        let mut grid = Grid::new(ruleset, 5);
+        for x in 1..4 {
+            grid.set_cell(x, 2, alive);
+        }
+
+        grid.next_generation();
+        for y in 1..4 {
+            assert_eq!(grid.cell_at(2, y), Some(alive));
+        }
+        assert_eq!(grid.cell_at(1, 2), grid.cell_at(0, 0));
+        assert_eq!(grid.period(), None);
+        grid.next_generation();
+        assert_eq!(grid.cell_at(1, 2), Some(alive));
+        assert_eq!(grid.period(), Some(2));
+    }
+
+    #[test]
+    fn step_region() {
+        let ruleset = Ruleset::game_of_life();
+        let alive = ruleset.materials.get_at(1).map(Identifiable::id);
+        let alive = Cell::new(alive.expect("life should have an alive material"));
+        let mut grid = Grid::new(ruleset, 5);
+        for x in 1..4 {
+            grid.set_cell(x, 2, alive);
+        }
+
+        // Only the middle column turns, so the blinker's ends stay put and it becomes a plus.
+        let column = GridRect {
+            x: 2,
+            y: 0,
+            width: 1,
+            height: 5,
+        };
+        grid.step_region(column);
+        for y in 1..4 {
+            assert_eq!(grid.cell_at(2, y), Some(alive));
+        }
+        assert_eq!(grid.cell_at(1, 2), Some(alive));
+        assert_eq!(grid.cell_at(3, 2), Some(alive));
+        assert_eq!(grid.generation(), 1);
+
+        // A region entirely off the grid changes nothing.
+        let cells = grid.cells.clone();
+        grid.step_region(GridRect {
+            x: 5,
+            y: 5,
+            width: 2,
+            height: 2,
+        });
+        assert_eq!(grid.cells, cells);
+    }
+
+    #[test]
+    fn immovable() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        let mut wall = Material::new(&ruleset);
+        wall.immovable = true;
+        let wall_id = wall.id();
+        ruleset.materials.push(wall);
+        // Sand tries to sink through walls, and walls try to crumble to air.
+        ruleset
+            .rules
+            .push(swap_rule(sand, wall_id, Direction::South));
+        ruleset
+            .rules
+            .push(replace_rule(Pattern::Material(wall_id), air));
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_cell(1, 0, Cell::new(sand));
+        grid.set_cell(1, 1, Cell::new(wall_id));
+
+        for _ in 0..5 {
+            grid.next_generation();
+            assert_eq!(grid.cell_at(1, 0), Some(Cell::new(sand)));
+            assert_eq!(grid.cell_at(1, 1), Some(Cell::new(wall_id)));
+        }
+    }
+
+    #[test]
+    fn weighted_outputs() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        let ash = Material::new(&ruleset);
+        let ash_id = ash.id();
+        ruleset.materials.push(ash);
+        let mut rule = replace_rule(Pattern::Material(air), sand);
+        rule.outputs.push((RuleOutput::Material(ash_id), 3));
+        ruleset.rules.push(rule);
+        let mut grid = Grid::new(ruleset, 20);
+        // Without randomness the heaviest output is the one reported.
+        let explanation = grid.explain(0).expect("The first cell should exist.");
+        assert_eq!(
+            explanation.matches,
+            vec![(0, Transformation::Replace(Cell::new(ash_id)))]
+        );
+
+        let mut again = grid.clone();
+        grid.next_generation();
+        again.next_generation();
+        assert_eq!(grid.cells, again.cells);
+        let (sand_count, ash_count) = (count(&grid, sand), count(&grid, ash_id));
+        assert_eq!(sand_count + ash_count, 400);
+        assert!(sand_count > 0 && ash_count > 2 * sand_count);
+    }
+
+    #[test]
+    fn rule_counts() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        ruleset
+            .rules
+            .push(replace_rule(Pattern::Material(sand), sand));
+        // Sand is never next to sand, so this rule never fires.
+        let mut stacked = swap_rule(sand, sand, Direction::South);
+        stacked.outputs = vec![(RuleOutput::Material(air), 1)];
+        ruleset.rules.push(stacked);
+        for size in [3, SparseGrid::MIN_SIZE] {
+            let mut grid = Grid::new(ruleset.clone(), size);
+            assert!(grid.rule_counts().is_empty());
+            grid.set_cell(0, 0, Cell::new(sand));
+            grid.set_cell(2, 2, Cell::new(sand));
+            grid.next_generation();
+            assert_eq!(grid.rule_counts(), [2, 0]);
+
+            grid.load_state(grid.functional_state());
+            assert!(grid.rule_counts().is_empty());
+        }
+    }
+
+    #[test]
+    fn sparse_matches_dense() {
+        fn assert_same_steps(mut grid: Grid, generations: usize) {
+            let mut sparse = SparseGrid::from_grid(&grid);
+            for generation in 0..generations {
+                grid.next_generation();
+                sparse.next_generation();
+                assert_eq!(sparse.to_cells(), grid.cells, "generation {generation}");
+            }
+            assert_eq!(sparse.generation(), grid.generation());
+        }
+
+        let life = Ruleset::game_of_life();
+        let alive = life.materials.get_at(1).map(Identifiable::id);
+        let alive = Cell::new(alive.expect("life should have an alive material"));
+        for boundary in Boundary::ALL {
+            let mut grid = Grid::new(life.clone(), 8);
+            grid.set_boundary(boundary);
+            for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+                grid.set_cell(x, y, alive);
+            }
+            assert_same_steps(grid, 12);
+        }
+
+        let (mut ruleset, air, sand) = sand_ruleset();
+        ruleset.rules.push(swap_rule(sand, air, Direction::South));
+        let mut grid = Grid::new(ruleset.clone(), 6);
+        grid.set_cell(2, 0, Cell::new(sand));
+        grid.set_cell(3, 1, Cell::new(sand));
+        assert_same_steps(grid, 6);
+
+        // Air turns to sand even far away from any sand, so every cell has to be evaluated.
+        ruleset.rules = vec![replace_rule(Pattern::Material(air), sand)];
+        let grid = Grid::new(ruleset, 6);
+        let mut sparse = SparseGrid::from_grid(&grid);
+        sparse.next_generation();
+        assert_eq!(sparse.to_cells(), vec![Cell::new(sand); 36]);
+        assert_same_steps(grid, 1);
+
+        // Sand falls half the time. Each cell draws its own random choices, so a large grid that
+        // only evaluates the cells near sand plays out like one evaluating every cell.
+        let (mut ruleset, air, sand) = sand_ruleset();
+        let mut falling = swap_rule(sand, air, Direction::South);
+        falling.outputs.push((RuleOutput::Material(sand), 1));
+        ruleset.rules = vec![falling];
+        let mut grid = Grid::new(ruleset, SparseGrid::MIN_SIZE);
+        for x in 0..20 {
+            grid.set_cell(x * 3, x, Cell::new(sand));
+        }
+        let mut whole = grid.clone();
+        for generation in 0..10 {
+            grid.next_generation();
+            (whole.cells, _) = whole.stepped_cells(0..whole.cells.len());
+            whole.generation += 1;
+            assert_eq!(grid.cells, whole.cells, "generation {generation}");
+        }
+        assert_same_steps(grid, 10);
+    }
+
+    #[test]
+    fn large_grid_steps_sparsely() {
+        let ruleset = Ruleset::game_of_life();
+        let alive = ruleset.materials.get_at(1).map(Identifiable::id);
+        let alive = Cell::new(alive.expect("life should have an alive material"));
+        let mut grid = Grid::new(ruleset, SparseGrid::MIN_SIZE);
+        assert!(SparseGrid::suits(grid.size, 3));
+        for x in 60..63 {
+            grid.set_cell(x, 64, alive);
+        }
+
+        grid.next_generation();
+        assert_eq!(grid.cell_at(61, 63), Some(alive));
+        assert_eq!(grid.cell_at(61, 65), Some(alive));
+        assert_eq!(grid.cell_at(60, 64), grid.cell_at(0, 0));
+        grid.next_generation();
+        assert_eq!(grid.cell_at(60, 64), Some(alive));
+        assert_eq!(grid.cell_at(61, 63), grid.cell_at(0, 0));
+        assert_eq!(grid.period(), Some(2));
+    }
+
+    #[test]
+    fn stamp() {
+        let (ruleset, _, sand) = sand_ruleset();
+        let mut grid = Grid::new(ruleset, 3);
+        let glider = Preset::Glider.oriented(Orientation::default());
+
+        grid.stamp(&glider, (1, 1), Cell::new(sand));
+        assert_eq!(count(&grid, sand), glider.len());
+
+        let mut clipped = Grid::new(grid.ruleset.clone(), 3);
+        clipped.stamp(&glider, (0, 0), Cell::new(sand));
+        // Only the bottom-right 2x2 of the glider lands on the grid.
+        assert_eq!(count(&clipped, sand), 3);
+        assert_eq!(clipped.cell_at(1, 1), Some(Cell::new(sand)));
+    }
+
+    #[test]
+    fn line() {
+        let (ruleset, _, _) = sand_ruleset();
+        let grid = Grid::new(ruleset, 5);
+        let index = |x, y| grid.cell_index(x, y);
+
+        assert_eq!(grid.line(index(1, 1), index(1, 1)), vec![index(1, 1)]);
+        assert_eq!(
+            grid.line(index(0, 0), index(4, 2)),
+            vec![
+                index(0, 0),
+                index(1, 1),
+                index(2, 1),
+                index(3, 2),
+                index(4, 2)
+            ]
+        );
+        assert_eq!(
+            grid.line(index(2, 4), index(2, 1)),
+            vec![index(2, 4), index(2, 3), index(2, 2), index(2, 1)]
+        );
+    }
+
+    #[test]
+    fn population_history() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        ruleset.rules.push(Rule {
+            label: String::new(),
+            input: Pattern::Material(sand),
+            outputs: vec![(RuleOutput::Material(air), 1)],
+            conditions: vec![],
+        });
+        let mut grid = Grid::new(ruleset, 2);
+        grid.set_cell(0, 0, Cell::new(sand));
+        grid.set_cell(1, 1, Cell::new(sand));
+
+        grid.next_generation();
+        assert_eq!(grid.population(), &[vec![2, 2], vec![4, 0]]);
+
+        for _ in 0..Grid::POPULATION_HISTORY {
+            grid.next_generation();
+        }
+        assert_eq!(grid.population().len(), Grid::POPULATION_HISTORY);
+        grid.clear_population();
+        assert!(grid.population().is_empty());
+    }
+
+    #[test]
+    fn divergence() {
+        let (ruleset, air, sand) = sand_ruleset();
+        let mut falling = ruleset.clone();
+        falling.rules.push(swap_rule(sand, air, Direction::South));
+        let mut grid = Grid::new(ruleset.clone(), 3);
+        grid.set_cell(1, 0, Cell::new(sand));
+
+        let same = grid.divergence(ruleset, 2);
+        assert_eq!(same.counts(), [0, 0, 0]);
+        assert_eq!(same.first_difference(), None);
+
+        // Sand falls under the second ruleset and stays put under the first.
+        let divergence = grid.divergence(falling, 2);
+        assert_eq!(divergence.counts(), [0, 2, 2]);
+        assert_eq!(divergence.first_difference(), Some(1));
+        assert_eq!(
+            divergence.to_csv(),
+            "generation,differing_cells\r\n0,0\r\n1,2\r\n2,2\r\n"
+        );
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn resize() {
+        let (ruleset, air, sand) = sand_ruleset();
+        let mut grid = Grid::new(ruleset, 3);
+        for (x, y) in [(0, 0), (1, 1), (2, 2), (2, 0)] {
+            grid.set_cell(x, y, Cell::new(sand));
+        }
+        let original = grid.clone();
+
+        grid.resize(5).unwrap();
+        assert_eq!(grid.size, 5);
+        for y in 0..5 {
+            for x in 0..5 {
+                let expected = if x < 3 && y < 3 {
+                    original.cell_at(x, y)
+                } else {
+                    Some(Cell::new(air))
+                };
+                assert_eq!(grid.cell_at(x, y), expected, "({x}, {y})");
+            }
+        }
+
+        grid.resize(2).unwrap();
+        assert_eq!(grid.size, 2);
+        assert_eq!(grid.cells.len(), 4);
+        assert_eq!(grid.cell_at(0, 0), Some(Cell::new(sand)));
+        assert_eq!(grid.cell_at(1, 0), Some(Cell::new(air)));
+        assert_eq!(grid.cell_at(0, 1), Some(Cell::new(air)));
+        assert_eq!(grid.cell_at(1, 1), Some(Cell::new(sand)));
+
+        // Out-of-range sizes are rejected without touching the grid.
+        let before = grid.clone();
+        assert!(grid.resize(0).is_err());
+        assert!(grid.resize(Grid::MAX_SIZE + 1).is_err());
+        assert_eq!(grid, before);
+        grid.resize(1).unwrap();
+        assert_eq!(grid.cells, vec![Cell::new(sand)]);
+        assert_eq!(Grid::new(grid.ruleset.clone(), 0).size, 1);
+    }
+
+    #[test]
+    fn load_mismatched_state() {
+        let (ruleset, air, sand) = sand_ruleset();
+        let mut large = Grid::new(ruleset.clone(), 4);
+        large.set_cell(0, 0, Cell::new(sand));
+        large.set_cell(3, 3, Cell::new(sand));
+        let mut small = Grid::new(ruleset, 2);
+        small.set_cell(1, 1, Cell::new(sand));
+
+        // Loading normally adopts the state's size.
+        let mut grid = small.clone();
+        grid.load_state(large.functional_state());
+        assert_eq!(grid.size, 4);
+        assert_eq!(grid.cells, large.cells);
+
+        // Clipping keeps the grid's size, cutting off or padding the state.
+        let mut grid = small.clone();
+        grid.load_state_clipped(large.functional_state());
+        assert_eq!(grid.size, 2);
+        assert_eq!(grid.cell_at(0, 0), Some(Cell::new(sand)));
+        assert_eq!(grid.cell_at(1, 1), Some(Cell::new(air)));
+
+        let mut grid = large.clone();
+        grid.load_state_clipped(small.functional_state());
+        assert_eq!(grid.size, 4);
+        assert_eq!(grid.cell_at(1, 1), Some(Cell::new(sand)));
+        assert_eq!(grid.cell_at(0, 0), Some(Cell::new(air)));
+        assert_eq!(grid.cell_at(3, 3), Some(Cell::new(air)));
+    }
+
+    #[test]
+    fn neighbor_indices() {
+        let (ruleset, _, sand) = sand_ruleset();
+        let mut grid = Grid::new(ruleset, 3);
+        grid.set_boundary(Boundary::Dead);
+        assert_eq!(
+            grid.neighbor_indices(4),
+            [0, 1, 2, 3, 5, 6, 7, 8].map(Some),
+            "The center cell's neighbors follow Direction::ALL"
+        );
+        assert_eq!(
+            grid.neighbor_indices(0),
+            [None, None, None, None, Some(1), None, Some(3), Some(4)]
+        );
+        grid.set_boundary(Boundary::Wrap);
+        assert_eq!(grid.neighbor_indices(0), [8, 6, 7, 2, 1, 5, 3, 4].map(Some));
+
+        // Each direction's index holds the cell seen in that direction.
+        grid.set_cell(2, 1, Cell::new(sand));
+        let neighbors = grid.neighbors_with_indices(4, 2);
+        for direction in Direction::ALL {
+            let index = neighbors.index_in_direction(direction);
+            assert_eq!(index, grid.neighbor_indices(4)[direction as usize]);
+            assert_eq!(
+                index.map(|index| grid.cells[index]),
+                neighbors.in_direction(direction)
+            );
+        }
+        assert_eq!(neighbors.index_at_offset(-2, 0), Some(5));
+        assert_eq!(
+            grid.neighbors(4, 1).index_in_direction(Direction::East),
+            None
+        );
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn from_image() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        ruleset.materials.get_mut_at(0).unwrap().color = MaterialColor::new(255, 255, 255);
+        ruleset.materials.get_mut_at(1).unwrap().color = MaterialColor::new(200, 150, 0);
+        let image = image::RgbImage::from_fn(2, 2, |x, y| {
+            if x == y {
+                image::Rgb([250, 250, 240])
+            } else {
+                image::Rgb([180, 120, 30])
+            }
+        });
+
+        let grid = Grid::from_image(&DynamicImage::ImageRgb8(image), ruleset, 2);
+        assert_eq!(grid.cell_at(0, 0), Some(Cell::new(air)));
+        assert_eq!(grid.cell_at(1, 0), Some(Cell::new(sand)));
+        assert_eq!(grid.cell_at(0, 1), Some(Cell::new(sand)));
+        assert_eq!(grid.cell_at(1, 1), Some(Cell::new(air)));
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn csv() {
+        let (mut ruleset, air, sand) = sand_ruleset();
+        ruleset.materials.get_mut_at(1).unwrap().name = String::from("Sand, \"wet\"");
+        let mut grid = Grid::new(ruleset.clone(), 2);
+        grid.set_cell(1, 0, Cell::new(sand));
+
+        let csv = grid.to_csv();
+        assert_eq!(csv, "Blank,\"Sand, \"\"wet\"\"\"\r\nBlank,Blank\r\n");
+
+        let new_grid = Grid::from_csv(&csv, ruleset.clone()).unwrap();
+        assert_eq!(new_grid.cell_at(0, 0), Some(Cell::new(air)));
+        assert_eq!(new_grid.cell_at(1, 0), Some(Cell::new(sand)));
+        assert_eq!(new_grid.cells, grid.cells);
+
+        assert!(Grid::from_csv("Blank,Water\nBlank,Blank", ruleset.clone()).is_err());
+        assert!(Grid::from_csv("Blank,Blank\nBlank", ruleset.clone()).is_err());
+        let oversized = "Blank\n".repeat(Grid::MAX_SIZE + 1);
+        assert!(Grid::from_csv(&oversized, ruleset).is_err());
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn state_path() {
+        let path = FunctionalGridState::path("run.v2", FunctionalGridState::EXTENSION).unwrap();
+        assert_eq!(path.file_name().unwrap(), "run.v2.state");
+        assert!(FunctionalGridState::path("../run", FunctionalGridState::EXTENSION).is_err());
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn state_size_limits() {
+        let path = std::env::temp_dir().join(format!("state-{}.toml", std::process::id()));
+        // Sizes past the largest grid are rejected, even ones whose square would overflow.
+        for size in [0, Grid::MAX_SIZE + 1, 1 << 32] {
+            fs::write(&path, format!("size = {size}\ncells = []")).unwrap();
+            assert!(
+                FunctionalGridState::load_from(&path).is_err(),
+                "{size} was loaded"
+            );
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
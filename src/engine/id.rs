@@ -1,8 +1,7 @@
-use std::{fmt::Debug, fmt::Display, marker::PhantomData};
+use std::{fmt::Debug, fmt::Display, hash::Hash, marker::PhantomData};
 
 use rand::Rng;
-use serde::Serialize;
-use vizia::binding::Data;
+use serde::{Deserialize, Serialize};
 
 pub trait Identifiable: Sized {
     fn id(&self) -> UniqueId<Self>;
@@ -10,6 +9,9 @@ pub trait Identifiable: Sized {
 
 pub struct UniqueId<T: Identifiable>(u32, PhantomData<T>);
 impl<T: Identifiable> UniqueId<T> {
+    /// Picks a random id not used by `current`.
+    /// Unlike [`UniqueId::next`], the result is unlikely to collide with ids from other lists,
+    /// which makes it suited to entities that will be merged elsewhere.
     pub fn new(current: &[T]) -> Self {
         assert!(
             current.len() < u32::MAX as usize,
@@ -25,6 +27,18 @@ impl<T: Identifiable> UniqueId<T> {
             }
         }
     }
+    /// Picks the id after the largest one in `current`, so ids are assigned deterministically.
+    /// Falls back to [`UniqueId::new`] once `u32::MAX` is taken.
+    pub fn next(current: &[T]) -> Self {
+        current
+            .iter()
+            .map(|item| item.id().0)
+            .max()
+            .map_or(Self(0, PhantomData), |max| {
+                max.checked_add(1)
+                    .map_or_else(|| Self::new(current), |id| Self(id, PhantomData))
+            })
+    }
     pub const fn new_unchecked(id: u32) -> Self {
         Self(id, PhantomData)
     }
@@ -57,6 +71,11 @@ impl<T: Identifiable> PartialEq for UniqueId<T> {
     }
 }
 impl<T: Identifiable> Eq for UniqueId<T> {}
+impl<T: Identifiable> Hash for UniqueId<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
 impl<T: Identifiable> Clone for UniqueId<T> {
     fn clone(&self) -> Self {
         *self
@@ -71,8 +90,37 @@ impl<T: Identifiable> Serialize for UniqueId<T> {
         serializer.serialize_u32(self.0)
     }
 }
-impl<T: Identifiable + 'static> Data for UniqueId<T> {
-    fn same(&self, other: &Self) -> bool {
-        self.0 == other.0
+impl<'de, T: Identifiable> Deserialize<'de> for UniqueId<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        u32::deserialize(deserializer).map(Self::new_unchecked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Item(UniqueId<Self>);
+    impl Identifiable for Item {
+        fn id(&self) -> UniqueId<Self> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn next() {
+        assert_eq!(UniqueId::<Item>::next(&[]).get(), 0);
+
+        let items = [
+            Item(UniqueId::new_unchecked(4)),
+            Item(UniqueId::new_unchecked(2)),
+        ];
+        assert_eq!(UniqueId::next(&items).get(), 5);
+
+        let full = [Item(UniqueId::new_unchecked(u32::MAX))];
+        assert_ne!(UniqueId::next(&full).get(), u32::MAX);
     }
 }
@@ -0,0 +1,820 @@
+use std::{fmt::Display, str::FromStr, vec};
+
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Serialize,
+};
+
+use super::{
+    id::{Identifiable, UniqueId},
+    ruleset::Ruleset,
+};
+
+pub type MaterialId = UniqueId<Material>;
+pub type GroupId = UniqueId<MaterialGroup>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Material {
+    id: UniqueId<Self>,
+    pub name: String,
+    pub color: MaterialColor,
+    /// Whether generating a palette leaves this material's color alone.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    /// Whether cells of this material are left alone by the rules and by painting over them.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub immovable: bool,
+}
+impl Material {
+    pub fn new(ruleset: &Ruleset) -> Self {
+        Self {
+            id: UniqueId::next(&ruleset.materials.0),
+            name: String::from("Empty"),
+            color: MaterialColor::DEFAULT,
+            locked: false,
+            immovable: false,
+        }
+    }
+    pub fn new_unchecked(id: MaterialId) -> Self {
+        Self {
+            id,
+            name: String::from("Empty"),
+            color: MaterialColor::DEFAULT,
+            locked: false,
+            immovable: false,
+        }
+    }
+
+    /// Returns this material with its id replaced. References to the old id are not updated.
+    pub fn with_id(self, id: MaterialId) -> Self {
+        Self { id, ..self }
+    }
+
+    pub fn blank() -> Self {
+        Self {
+            id: UniqueId::next(&[]),
+            name: String::from("Blank"),
+            color: MaterialColor::BLANK,
+            locked: false,
+            immovable: false,
+        }
+    }
+}
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            id: UniqueId::next(&[]),
+            name: String::from("Empty"),
+            color: MaterialColor::DEFAULT,
+            locked: false,
+            immovable: false,
+        }
+    }
+}
+impl Identifiable for Material {
+    fn id(&self) -> UniqueId<Self> {
+        self.id
+    }
+}
+struct MaterialVisitor;
+impl<'de> Visitor<'de> for MaterialVisitor {
+    type Value = Material;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "struct Material")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut id = None;
+        let mut name = None;
+        let mut color = None;
+        let mut locked = None;
+        let mut immovable = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "id" => {
+                    if id.is_some() {
+                        return Err(de::Error::duplicate_field("id"));
+                    }
+                    let raw_id: u32 = map.next_value()?;
+                    id = Some(UniqueId::new_unchecked(raw_id));
+                }
+                "name" => {
+                    if name.is_some() {
+                        return Err(de::Error::duplicate_field("name"));
+                    }
+                    name = map.next_value()?;
+                }
+                "color" => {
+                    if color.is_some() {
+                        return Err(de::Error::duplicate_field("color"));
+                    }
+                    color = map.next_value()?;
+                }
+                "locked" => {
+                    if locked.is_some() {
+                        return Err(de::Error::duplicate_field("locked"));
+                    }
+                    locked = Some(map.next_value()?);
+                }
+                "immovable" => {
+                    if immovable.is_some() {
+                        return Err(de::Error::duplicate_field("immovable"));
+                    }
+                    immovable = Some(map.next_value()?);
+                }
+                _ => {
+                    return Err(de::Error::unknown_field(
+                        &key,
+                        &["id", "name", "color", "locked", "immovable"],
+                    ))
+                }
+            }
+        }
+
+        let id = id.ok_or_else(|| de::Error::missing_field("id"))?;
+        let name = name.ok_or_else(|| de::Error::missing_field("name"))?;
+        let color = color.ok_or_else(|| de::Error::missing_field("color"))?;
+        let locked = locked.unwrap_or_default();
+        let immovable = immovable.unwrap_or_default();
+
+        Ok(Material {
+            id,
+            name,
+            color,
+            locked,
+            immovable,
+        })
+    }
+}
+impl<'de> Deserialize<'de> for Material {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            "Material",
+            &["id", "name", "color", "locked", "immovable"],
+            MaterialVisitor,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChannel {
+    Red,
+    Green,
+    Blue,
+}
+impl ColorChannel {
+    pub const ALL: [Self; 3] = [Self::Red, Self::Green, Self::Blue];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Red => "R",
+            Self::Green => "G",
+            Self::Blue => "B",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd)]
+pub struct MaterialColor {
+    r: u8,
+    g: u8,
+    b: u8,
+}
+impl MaterialColor {
+    pub const DEFAULT: Self = Self::new(0, 0, 0);
+    const BLANK: Self = Self::new(255, 255, 255);
+    /// Stands in for patterns with no colors of their own, like empty groups.
+    pub const NEUTRAL: Self = Self::new(128, 128, 128);
+    /// Okabe and Ito's palette, which stays distinguishable under the common kinds of color
+    /// blindness.
+    const OKABE_ITO: [Self; 8] = [
+        Self::new(230, 159, 0),
+        Self::new(86, 180, 233),
+        Self::new(0, 158, 115),
+        Self::new(240, 228, 66),
+        Self::new(0, 114, 178),
+        Self::new(213, 94, 0),
+        Self::new(204, 121, 167),
+        Self::new(0, 0, 0),
+    ];
+    /// The angle between successive generated hues, in degrees, which spreads them evenly
+    /// around the color wheel without ever repeating one.
+    const GOLDEN_ANGLE: f64 = 137.507_764;
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+    pub const fn channel(self, channel: ColorChannel) -> u8 {
+        match channel {
+            ColorChannel::Red => self.r,
+            ColorChannel::Green => self.g,
+            ColorChannel::Blue => self.b,
+        }
+    }
+    pub const fn with_channel(self, channel: ColorChannel, value: u8) -> Self {
+        match channel {
+            ColorChannel::Red => Self { r: value, ..self },
+            ColorChannel::Green => Self { g: value, ..self },
+            ColorChannel::Blue => Self { b: value, ..self },
+        }
+    }
+    pub const fn rgb(self) -> (u8, u8, u8) {
+        (self.r, self.g, self.b)
+    }
+    /// Returns `count` colors, distinct from each other and from every color in `taken`.
+    ///
+    /// The colors start with [`Self::OKABE_ITO`], then continue with hues spaced by the golden
+    /// angle in OKLCH, cycling through three lightness levels so consecutive colors also differ
+    /// in brightness.
+    pub fn distinct_palette(count: usize, taken: &[Self]) -> Vec<Self> {
+        let generated = (0_u32..).map(|step| {
+            let lightness = match step % 3 {
+                0 => 0.75,
+                1 => 0.55,
+                _ => 0.4,
+            };
+            Self::from_oklch(lightness, 0.13, f64::from(step) * Self::GOLDEN_ANGLE)
+        });
+        let mut palette = Vec::with_capacity(count);
+        for color in Self::OKABE_ITO.into_iter().chain(generated) {
+            if palette.len() == count {
+                break;
+            }
+            if !taken.contains(&color) && !palette.contains(&color) {
+                palette.push(color);
+            }
+        }
+        palette
+    }
+    /// Converts an OKLCH color, with `hue` in degrees, to the nearest sRGB color.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::many_single_char_names
+    )]
+    fn from_oklch(lightness: f64, chroma: f64, hue: f64) -> Self {
+        let (sin, cos) = hue.to_radians().sin_cos();
+        let (a, b) = (chroma * cos, chroma * sin);
+        let l = 0.215_803_757_3_f64
+            .mul_add(b, 0.396_337_777_4_f64.mul_add(a, lightness))
+            .powi(3);
+        let m = (-0.063_854_172_8_f64)
+            .mul_add(b, (-0.105_561_345_8_f64).mul_add(a, lightness))
+            .powi(3);
+        let s = (-1.291_485_548_f64)
+            .mul_add(b, (-0.089_484_177_5_f64).mul_add(a, lightness))
+            .powi(3);
+        let linear = [
+            0.230_969_929_2_f64.mul_add(s, 4.076_741_662_1_f64.mul_add(l, -3.307_711_591_3 * m)),
+            (-0.341_319_396_5_f64)
+                .mul_add(s, (-1.268_438_004_6_f64).mul_add(l, 2.609_757_401_1 * m)),
+            1.707_614_701_f64.mul_add(s, (-0.004_196_086_3_f64).mul_add(l, -0.703_418_614_7 * m)),
+        ];
+        let [r, g, b] = linear.map(|channel| {
+            let channel = channel.clamp(0.0, 1.0);
+            let encoded = if channel <= 0.003_130_8 {
+                12.92 * channel
+            } else {
+                1.055_f64.mul_add(channel.powf(2.4_f64.recip()), -0.055)
+            };
+            (encoded * 255.0).round() as u8
+        });
+        Self::new(r, g, b)
+    }
+    /// The squared euclidean distance between two colors in RGB space.
+    pub fn distance_squared(self, other: Self) -> u32 {
+        [(self.r, other.r), (self.g, other.g), (self.b, other.b)]
+            .into_iter()
+            .map(|(a, b)| u32::from(a.abs_diff(b)).pow(2))
+            .sum()
+    }
+    /// Blends from this color toward `other`, reaching it when `t` is 1.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |from: u8, to: u8| {
+            (f32::from(to) - f32::from(from))
+                .mul_add(t, f32::from(from))
+                .round() as u8
+        };
+        Self::new(
+            channel(self.r, other.r),
+            channel(self.g, other.g),
+            channel(self.b, other.b),
+        )
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn invert_grayscale(self) -> Self {
+        let avg =
+            (((255 - self.r) as u32 + (255 - self.g) as u32 + (255 - self.b) as u32) / 3) as u8;
+        Self {
+            r: avg,
+            g: avg,
+            b: avg,
+        }
+    }
+}
+impl Display for MaterialColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+}
+impl FromStr for MaterialColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let numbers = s
+            .strip_prefix('#')
+            .ok_or_else(|| String::from("str was not prefixed with '#'"))?;
+        let mut numbers = numbers
+            .as_bytes()
+            .chunks(2)
+            .map(|bytes| u8::from_str_radix(&String::from_utf8_lossy(bytes), 16));
+        let r = numbers
+            .next()
+            .ok_or_else(|| String::from("Too few numbers. Got '0', expected '3'."))
+            .and_then(|result| {
+                result.map_err(|err| format!("value for 'r' is invalid hexadecimal. {err}"))
+            })?;
+        let g = numbers
+            .next()
+            .ok_or_else(|| String::from("Too few numbers. Got '1', expected '3'."))
+            .and_then(|result| {
+                result.map_err(|err| format!("value for 'g' is invalid hexadecimal. {err}"))
+            })?;
+        let b = numbers
+            .next()
+            .ok_or_else(|| String::from("Too few numbers. Got '2', expected '3'."))
+            .and_then(|result| {
+                result.map_err(|err| format!("value for 'b' is invalid hexadecimal. {err}"))
+            })?;
+        if numbers.next().is_some() {
+            return Err(String::from("Too many numbers. Expected '3'."));
+        }
+        Ok(Self::new(r, g, b))
+    }
+}
+impl Serialize for MaterialColor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+struct MaterialColorVisitor;
+impl<'de> Visitor<'de> for MaterialColorVisitor {
+    type Value = MaterialColor;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "struct MaterialColor")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        v.parse().map_err(|err| de::Error::custom(&err))
+    }
+}
+impl<'de> Deserialize<'de> for MaterialColor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(MaterialColorVisitor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaterialMap(Vec<Material>);
+impl MaterialMap {
+    pub fn new(default: Material) -> Self {
+        let materials = vec![default];
+        Self(materials)
+    }
+    pub const fn new_unchecked(v: Vec<Material>) -> Self {
+        Self(v)
+    }
+    pub fn default(&self) -> &Material {
+        &self.0[0]
+    }
+
+    pub fn get(&self, key: MaterialId) -> Option<&Material> {
+        self.0.iter().find(|material| material.id == key)
+    }
+    /// The material with this id, or the default material if there isn't one.
+    pub fn get_or_default(&self, key: MaterialId) -> &Material {
+        self.get(key).unwrap_or_else(|| self.default())
+    }
+
+    pub fn remove(&mut self, id: MaterialId) {
+        if let Some(index) = self.0.iter().position(|m| m.id == id) {
+            self.0.remove(index);
+        };
+    }
+
+    /// The material whose color is closest to `color`.
+    pub fn nearest(&self, color: MaterialColor) -> &Material {
+        self.iter()
+            .min_by_key(|material| material.color.distance_squared(color))
+            .unwrap_or_else(|| self.default())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.iter().map(|m| m.name.clone()).collect()
+    }
+
+    pub fn index_of(&self, id: MaterialId) -> Option<usize> {
+        self.iter().position(|m| m.id == id)
+    }
+
+    pub fn get_at(&self, index: usize) -> Option<&Material> {
+        self.0.get(index)
+    }
+
+    pub fn get_mut_at(&mut self, index: usize) -> Option<&mut Material> {
+        self.0.get_mut(index)
+    }
+
+    pub fn next_id(&self) -> MaterialId {
+        UniqueId::next(&self.0)
+    }
+    pub fn push(&mut self, material: Material) {
+        self.0.push(material);
+    }
+    /// Moves the material at `from` to `to`, shifting the ones between them over. The default
+    /// material must stay first, so it can't be moved or displaced. Returns whether it moved.
+    pub fn reorder(&mut self, from: usize, to: usize) -> bool {
+        if from == 0 || to == 0 || from >= self.len() || to >= self.len() {
+            return false;
+        }
+        let material = self.0.remove(from);
+        self.0.insert(to, material);
+        true
+    }
+    /// Where the material at `index` ends up after [`Self::reorder`] moves `from` to `to`.
+    pub const fn reordered_index(index: usize, from: usize, to: usize) -> usize {
+        if index == from {
+            to
+        } else if from < index && index <= to {
+            index - 1
+        } else if to <= index && index < from {
+            index + 1
+        } else {
+            index
+        }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<Material> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaterialGroup {
+    id: UniqueId<Self>,
+    pub name: String,
+    materials: Vec<MaterialId>,
+    /// Groups whose members also belong to this group.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<GroupId>,
+}
+impl MaterialGroup {
+    pub fn new(ruleset: &Ruleset) -> Self {
+        Self {
+            id: UniqueId::next(&ruleset.groups),
+            name: String::from("New Group"),
+            materials: vec![],
+            groups: vec![],
+        }
+    }
+    pub fn new_unchecked(id: GroupId, materials: Vec<MaterialId>) -> Self {
+        Self {
+            id,
+            name: String::from("New Group"),
+            materials,
+            groups: vec![],
+        }
+    }
+    /// Returns this group with its id replaced. References to the old id are not updated.
+    pub fn with_id(self, id: GroupId) -> Self {
+        Self { id, ..self }
+    }
+    pub fn contains(&self, id: MaterialId) -> bool {
+        self.materials.contains(&id)
+    }
+    pub fn materials(&self) -> &[MaterialId] {
+        &self.materials
+    }
+    pub fn materials_mut(&mut self) -> &mut [MaterialId] {
+        &mut self.materials
+    }
+    pub fn push(&mut self, id: MaterialId) {
+        self.materials.push(id);
+    }
+    /// Adds the material to this group, or removes every entry of it if it's already a member.
+    pub fn toggle(&mut self, id: MaterialId) {
+        if self.contains(id) {
+            self.materials.retain(|&material| material != id);
+        } else {
+            self.materials.push(id);
+        }
+    }
+    pub fn subgroups(&self) -> &[GroupId] {
+        &self.groups
+    }
+    pub fn subgroups_mut(&mut self) -> &mut [GroupId] {
+        &mut self.groups
+    }
+    pub fn retain_subgroups(&mut self, keep: impl FnMut(&GroupId) -> bool) {
+        self.groups.retain(keep);
+    }
+    /// Adds the group as a member of this one, or removes it if it's already a member.
+    /// Doesn't check for cycles; see [`Ruleset::would_cycle`].
+    pub fn toggle_subgroup(&mut self, id: GroupId) {
+        if self.groups.contains(&id) {
+            self.groups.retain(|&group| group != id);
+        } else {
+            self.groups.push(id);
+        }
+    }
+    /// The average color of the group's members, including those of its subgroups,
+    /// or [`MaterialColor::NEUTRAL`] if none exist.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn representative_color(&self, ruleset: &Ruleset) -> MaterialColor {
+        let mut members = self.materials.clone();
+        for material in self
+            .groups
+            .iter()
+            .flat_map(|&group| ruleset.group_materials(group))
+        {
+            if !members.contains(&material) {
+                members.push(material);
+            }
+        }
+        let colors: Vec<MaterialColor> = members
+            .iter()
+            .filter_map(|&id| ruleset.materials.get(id))
+            .map(|material| material.color)
+            .collect();
+        if colors.is_empty() {
+            return MaterialColor::NEUTRAL;
+        }
+        let count = colors.len();
+        let [r, g, b] = ColorChannel::ALL.map(|channel| {
+            let total: usize = colors
+                .iter()
+                .map(|color| usize::from(color.channel(channel)))
+                .sum();
+            // An average of `u8`s always fits in a `u8`.
+            (total / count) as u8
+        });
+        MaterialColor::new(r, g, b)
+    }
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut MaterialId> {
+        self.materials.get_mut(index)
+    }
+    pub fn remove_at(&mut self, index: usize) {
+        self.materials.remove(index);
+    }
+}
+impl Identifiable for MaterialGroup {
+    fn id(&self) -> UniqueId<Self> {
+        self.id
+    }
+}
+struct MaterialGroupVisitor;
+impl<'de> Visitor<'de> for MaterialGroupVisitor {
+    type Value = MaterialGroup;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "struct MaterialGroup")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut id = None;
+        let mut name = None;
+        let mut materials = None;
+        let mut groups = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "id" => {
+                    if id.is_some() {
+                        return Err(de::Error::duplicate_field("id"));
+                    }
+                    let id_raw: u32 = map.next_value()?;
+                    id = Some(UniqueId::new_unchecked(id_raw));
+                }
+                "name" => {
+                    if name.is_some() {
+                        return Err(de::Error::duplicate_field("name"));
+                    }
+                    name = Some(map.next_value()?);
+                }
+                "materials" => {
+                    if materials.is_some() {
+                        return Err(de::Error::duplicate_field("materials"));
+                    }
+                    let materials_raw: Vec<u32> = map.next_value()?;
+                    materials = Some(
+                        materials_raw
+                            .into_iter()
+                            .map(UniqueId::new_unchecked)
+                            .collect(),
+                    );
+                }
+                "groups" => {
+                    if groups.is_some() {
+                        return Err(de::Error::duplicate_field("groups"));
+                    }
+                    let groups_raw: Vec<u32> = map.next_value()?;
+                    groups = Some(
+                        groups_raw
+                            .into_iter()
+                            .map(UniqueId::new_unchecked)
+                            .collect(),
+                    );
+                }
+                _ => {
+                    return Err(de::Error::unknown_field(
+                        &key,
+                        &["id", "name", "materials", "groups"],
+                    ))
+                }
+            }
+        }
+
+        let id = id.ok_or_else(|| de::Error::missing_field("id"))?;
+        let name = name.ok_or_else(|| de::Error::missing_field("name"))?;
+        let materials = materials.ok_or_else(|| de::Error::missing_field("materials"))?;
+
+        Ok(MaterialGroup {
+            id,
+            name,
+            materials,
+            groups: groups.unwrap_or_default(),
+        })
+    }
+}
+impl<'de> Deserialize<'de> for MaterialGroup {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            "MaterialGroup",
+            &["id", "name", "materials", "groups"],
+            MaterialGroupVisitor,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_or_default() {
+        let mut ruleset = Ruleset::blank();
+        let sand = Material::new(&ruleset);
+        let sand_id = sand.id();
+        ruleset.materials.push(sand);
+        assert_eq!(ruleset.materials.get_or_default(sand_id).id(), sand_id);
+
+        ruleset.materials.remove(sand_id);
+        let default = ruleset.materials.default().id();
+        assert_eq!(ruleset.materials.get_or_default(sand_id).id(), default);
+    }
+
+    #[test]
+    fn representative_color() {
+        let mut ruleset = Ruleset::blank();
+        let mut group = MaterialGroup::new(&ruleset);
+        assert_eq!(group.representative_color(&ruleset), MaterialColor::NEUTRAL);
+
+        let mut red = Material::new(&ruleset);
+        red.color = MaterialColor::new(255, 0, 0);
+        group.push(red.id());
+        ruleset.materials.push(red);
+        let mut blue = Material::new(&ruleset);
+        blue.color = MaterialColor::new(0, 0, 100);
+        group.push(blue.id());
+        ruleset.materials.push(blue);
+        assert_eq!(
+            group.representative_color(&ruleset),
+            MaterialColor::new(127, 0, 50)
+        );
+    }
+
+    #[test]
+    fn toggle_membership() {
+        let id = UniqueId::new_unchecked(3);
+        let mut group = MaterialGroup::new_unchecked(UniqueId::new_unchecked(0), vec![id, id]);
+        group.toggle(id);
+        assert!(!group.contains(id));
+        group.toggle(id);
+        assert_eq!(group.materials(), [id]);
+    }
+
+    #[test]
+    fn reorder() {
+        let mut map = MaterialMap::new_unchecked(
+            (0..4)
+                .map(|id| Material::new_unchecked(UniqueId::new_unchecked(id)))
+                .collect(),
+        );
+        let ids = |map: &MaterialMap| map.iter().map(|m| m.id().get()).collect::<Vec<_>>();
+
+        assert!(map.reorder(1, 3));
+        assert_eq!(ids(&map), [0, 2, 3, 1]);
+        assert!(map.reorder(3, 2));
+        assert_eq!(ids(&map), [0, 2, 1, 3]);
+        assert!(!map.reorder(0, 1));
+        assert!(!map.reorder(2, 0));
+        assert!(!map.reorder(1, 4));
+        assert_eq!(ids(&map), [0, 2, 1, 3]);
+
+        let moved = |index| MaterialMap::reordered_index(index, 1, 3);
+        assert_eq!([0, 1, 2, 3].map(moved), [0, 3, 1, 2]);
+        let moved = |index| MaterialMap::reordered_index(index, 3, 1);
+        assert_eq!([0, 1, 2, 3].map(moved), [0, 2, 3, 1]);
+    }
+
+    #[test]
+    fn distinct_palette() {
+        let taken = [MaterialColor::new(0, 0, 0), MaterialColor::new(230, 159, 0)];
+        let palette = MaterialColor::distinct_palette(40, &taken);
+        assert_eq!(palette.len(), 40);
+        assert_eq!(palette[0], MaterialColor::new(86, 180, 233));
+        for (index, color) in palette.iter().enumerate() {
+            assert!(!taken.contains(color));
+            assert!(!palette[..index].contains(color));
+        }
+        assert!(MaterialColor::distinct_palette(0, &taken).is_empty());
+    }
+
+    #[test]
+    fn lerp() {
+        let black = MaterialColor::new(0, 0, 0);
+        let white = MaterialColor::new(255, 255, 255);
+        assert_eq!(black.lerp(white, 0.0), black);
+        assert_eq!(black.lerp(white, 1.0), white);
+        assert_eq!(black.lerp(white, 0.5), MaterialColor::new(128, 128, 128));
+        assert_eq!(white.lerp(black, 2.0), black);
+    }
+
+    #[test]
+    fn oklch() {
+        assert_eq!(
+            MaterialColor::from_oklch(1.0, 0.0, 0.0),
+            MaterialColor::new(255, 255, 255)
+        );
+        assert_eq!(
+            MaterialColor::from_oklch(0.627_955, 0.257_683, 29.233_885),
+            MaterialColor::new(255, 0, 0)
+        );
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn serde_material() {
+        let material = Material::blank();
+        let serialized = toml::to_string(&material);
+        if let Err(err) = serialized {
+            println!("{err}");
+            panic!("'serialized' returned error")
+        }
+        let deserialized = toml::from_str(&serialized.unwrap());
+        if let Err(err) = deserialized {
+            println!("{err}");
+            panic!("'deserialized' returned error")
+        }
+        assert_eq!(material, deserialized.unwrap());
+
+        let wall = Material {
+            immovable: true,
+            ..Material::blank()
+        };
+        let serialized = toml::to_string(&wall).unwrap();
+        assert!(serialized.contains("immovable = true"));
+        assert_eq!(wall, toml::from_str(&serialized).unwrap());
+        assert!(!toml::to_string(&material).unwrap().contains("immovable"));
+    }
+}
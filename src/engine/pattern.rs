@@ -2,61 +2,59 @@ use serde::{
     de::{self, Visitor},
     Deserialize, Serialize,
 };
-use vizia::{
-    binding::LensExt,
-    context::{Context, EventContext},
-    layout::Units::Stretch,
-    modifiers::{LayoutModifiers, StyleModifiers},
-    views::ComboBox,
-};
 
-use crate::{
+use super::{
     grid::Cell,
     id::{Identifiable, UniqueId},
-    material::{GroupId, MaterialId},
+    material::{GroupId, MaterialColor, MaterialId},
     ruleset::Ruleset,
-    AppData,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Pattern {
     Material(MaterialId),
     Group(GroupId),
+    /// Whatever material the cell being evaluated has.
+    SelfSame,
 }
 impl Pattern {
-    pub fn display_editor<F>(self, cx: &mut Context, on_select: F)
-    where
-        F: Fn(&mut EventContext, usize) + 'static,
-    {
-        ComboBox::new(
-            cx,
-            AppData::screen.map(|screen| screen.ruleset().pattern_values()),
-            AppData::screen.map(move |screen| match self {
-                Self::Material(id) => screen
-                    .ruleset()
-                    .materials
-                    .index_of(id)
-                    .expect("Displayed pattern should match the current ruleset."),
-                Self::Group(id) => screen
-                    .ruleset()
-                    .index_of_group(id)
-                    .map(|index| screen.ruleset().materials.len() + index)
-                    .expect("Displayed pattern should match the current ruleset."),
+    /// A color to recognize the pattern by: a material's own color, or a group's
+    /// [`super::material::MaterialGroup::representative_color`]. Missing materials and groups, and
+    /// [`Pattern::SelfSame`], are neutral.
+    pub fn color(self, ruleset: &Ruleset) -> MaterialColor {
+        match self {
+            Self::Material(id) => ruleset
+                .materials
+                .get(id)
+                .map_or(MaterialColor::NEUTRAL, |material| material.color),
+            Self::Group(id) => ruleset.group(id).map_or(MaterialColor::NEUTRAL, |group| {
+                group.representative_color(ruleset)
             }),
-        )
-        .width(Stretch(1.0))
-        .top(Stretch(1.0))
-        .bottom(Stretch(1.0))
-        .on_select(on_select)
-        .class(crate::display::style::LIGHT_COMBOBOX);
+            Self::SelfSame => MaterialColor::NEUTRAL,
+        }
     }
 
-    pub fn matches(self, ruleset: &Ruleset, target: Cell) -> bool {
+    /// Names the pattern in prose: a material's name, a group's name after `#` as the editor
+    /// shows it, or `same-material` for [`Pattern::SelfSame`].
+    pub fn describe(self, ruleset: &Ruleset) -> String {
         match self {
-            Self::Material(id) => id == target.material_id,
+            Self::Material(id) => ruleset
+                .materials
+                .get(id)
+                .map_or_else(|| String::from("missing material"), |m| m.name.clone()),
             Self::Group(id) => ruleset
                 .group(id)
-                .is_some_and(|group| group.contains(target.material_id)),
+                .map_or_else(|| String::from("missing group"), |g| format!("#{}", g.name)),
+            Self::SelfSame => String::from("same-material"),
+        }
+    }
+
+    /// Whether `target` fits the pattern while evaluating the cell `center`.
+    pub fn matches(self, ruleset: &Ruleset, target: Cell, center: Cell) -> bool {
+        match self {
+            Self::Material(id) => id == target.material_id,
+            Self::Group(id) => ruleset.group_contains(id, target.material_id),
+            Self::SelfSame => target.material_id == center.material_id,
         }
     }
 
@@ -71,6 +69,9 @@ impl Pattern {
                     .get(index - ruleset.materials.len())
                     .map(|g| Self::Group(g.id()))
             })
+            .or_else(|| {
+                (index == ruleset.materials.len() + ruleset.groups.len()).then_some(Self::SelfSame)
+            })
     }
 }
 impl<'de> Deserialize<'de> for Pattern {
@@ -93,6 +94,9 @@ impl<'de> Visitor<'de> for PatternVisitor {
     where
         E: de::Error,
     {
+        if v == "self" {
+            return Ok(Pattern::SelfSame);
+        }
         let (id, suffix) = v.split_at(v.len() - 1);
         let id = id
             .parse()
@@ -102,7 +106,7 @@ impl<'de> Visitor<'de> for PatternVisitor {
             "g" => Ok(Pattern::Group(UniqueId::new_unchecked(id))),
             _ => Err(de::Error::invalid_value(
                 de::Unexpected::Str(suffix),
-                &"either 'm' or 'g'",
+                &"either 'm', 'g', or \"self\"",
             )),
         }
     }
@@ -115,6 +119,7 @@ impl Serialize for Pattern {
         let string = match self {
             Self::Material(id) => format!("{id}m"),
             Self::Group(id) => format!("{id}g"),
+            Self::SelfSame => String::from("self"),
         };
         serializer.serialize_str(&string)
     }
@@ -122,9 +127,10 @@ impl Serialize for Pattern {
 
 #[cfg(test)]
 mod tests {
-    use crate::{
+    use crate::engine::{
         id::UniqueId,
         material::{Material, MaterialGroup, MaterialMap},
+        ruleset::{ConflictPolicy, RulesetMetadata},
     };
 
     // Wrapper struct because for some reason toml doesn't want to directly deserialize patterns.
@@ -162,6 +168,11 @@ mod tests {
 
         assert_eq!(material_pattern, new_material_pattern);
         assert_eq!(group_pattern, new_group_pattern);
+
+        let self_string = toml::to_string(&W::new(Pattern::SelfSame)).unwrap();
+        assert_eq!(self_string.trim(), "v = \"self\"");
+        let new_self_pattern: W<Pattern> = toml::from_str(&self_string).unwrap();
+        assert_eq!(new_self_pattern.v, Pattern::SelfSame);
     }
 
     #[test]
@@ -181,6 +192,11 @@ mod tests {
         let groups: Vec<MaterialGroup> = vec![g(10, 1), g(20, 2), g(30, 3)];
         let ruleset = Ruleset {
             name: String::from("Test"),
+            conflict_policy: ConflictPolicy::default(),
+            default_size: None,
+            default_boundary: None,
+            fallback: None,
+            metadata: RulesetMetadata::default(),
             rules: vec![],
             materials: map,
             groups,
@@ -210,6 +226,7 @@ mod tests {
             Pattern::from_index(&ruleset, 5),
             Some(Pattern::Group(ida(30)))
         );
-        assert_eq!(Pattern::from_index(&ruleset, 6), None);
+        assert_eq!(Pattern::from_index(&ruleset, 6), Some(Pattern::SelfSame));
+        assert_eq!(Pattern::from_index(&ruleset, 7), None);
     }
 }
@@ -0,0 +1,139 @@
+/// Well-known Game of Life patterns that can be stamped onto the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Glider,
+    Blinker,
+    Pulsar,
+    GliderGun,
+}
+impl Preset {
+    pub const ALL: [Self; 4] = [Self::Glider, Self::Blinker, Self::Pulsar, Self::GliderGun];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Glider => "Glider",
+            Self::Blinker => "Blinker",
+            Self::Pulsar => "Pulsar",
+            Self::GliderGun => "Glider Gun",
+        }
+    }
+    pub fn names() -> Vec<String> {
+        Self::ALL
+            .iter()
+            .map(|preset| preset.name().to_owned())
+            .collect()
+    }
+    pub fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|&preset| preset == self)
+            .unwrap_or_default()
+    }
+
+    /// Live cell coordinates relative to the top-left corner of the pattern.
+    const fn cells(self) -> &'static [(usize, usize)] {
+        match self {
+            Self::Glider => &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)],
+            Self::Blinker => &[(0, 0), (1, 0), (2, 0)],
+            #[rustfmt::skip]
+            Self::Pulsar => &[
+                (2, 0), (3, 0), (4, 0), (8, 0), (9, 0), (10, 0),
+                (0, 2), (5, 2), (7, 2), (12, 2),
+                (0, 3), (5, 3), (7, 3), (12, 3),
+                (0, 4), (5, 4), (7, 4), (12, 4),
+                (2, 5), (3, 5), (4, 5), (8, 5), (9, 5), (10, 5),
+                (2, 7), (3, 7), (4, 7), (8, 7), (9, 7), (10, 7),
+                (0, 8), (5, 8), (7, 8), (12, 8),
+                (0, 9), (5, 9), (7, 9), (12, 9),
+                (0, 10), (5, 10), (7, 10), (12, 10),
+                (2, 12), (3, 12), (4, 12), (8, 12), (9, 12), (10, 12),
+            ],
+            // Gosper's glider gun.
+            #[rustfmt::skip]
+            Self::GliderGun => &[
+                (24, 0),
+                (22, 1), (24, 1),
+                (12, 2), (13, 2), (20, 2), (21, 2), (34, 2), (35, 2),
+                (11, 3), (15, 3), (20, 3), (21, 3), (34, 3), (35, 3),
+                (0, 4), (1, 4), (10, 4), (16, 4), (20, 4), (21, 4),
+                (0, 5), (1, 5), (10, 5), (14, 5), (16, 5), (17, 5), (22, 5), (24, 5),
+                (10, 6), (16, 6), (24, 6),
+                (11, 7), (15, 7),
+                (12, 8), (13, 8),
+            ],
+        }
+    }
+
+    /// The pattern's cells after applying `orientation`, relative to the top-left corner.
+    pub fn oriented(self, orientation: Orientation) -> Vec<(usize, usize)> {
+        let mut cells = self.cells().to_vec();
+        let mut width = cells.iter().map(|&(x, _)| x).max().unwrap_or_default();
+        let mut height = cells.iter().map(|&(_, y)| y).max().unwrap_or_default();
+        if orientation.flipped {
+            for (x, _) in &mut cells {
+                *x = width - *x;
+            }
+        }
+        for _ in 0..orientation.quarter_turns % 4 {
+            // Clockwise: the left column becomes the top row.
+            for (x, y) in &mut cells {
+                (*x, *y) = (height - *y, *x);
+            }
+            (width, height) = (height, width);
+        }
+        cells
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Orientation {
+    /// Clockwise quarter turns, applied after flipping.
+    pub quarter_turns: u8,
+    /// Mirrored horizontally.
+    pub flipped: bool,
+}
+impl Orientation {
+    pub const fn rotated(self) -> Self {
+        Self {
+            quarter_turns: (self.quarter_turns + 1) % 4,
+            ..self
+        }
+    }
+    pub const fn flipped(self) -> Self {
+        Self {
+            flipped: !self.flipped,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut cells: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn orientation() {
+        let vertical = Preset::Blinker.oriented(Orientation::default().rotated());
+        assert_eq!(sorted(vertical), vec![(0, 0), (0, 1), (0, 2)]);
+
+        let flipped = Preset::Glider.oriented(Orientation::default().flipped());
+        assert_eq!(
+            sorted(flipped),
+            vec![(0, 1), (0, 2), (1, 0), (1, 2), (2, 2)]
+        );
+
+        let mut orientation = Orientation::default().flipped();
+        for _ in 0..4 {
+            orientation = orientation.rotated();
+        }
+        assert_eq!(
+            sorted(Preset::GliderGun.oriented(orientation)),
+            sorted(Preset::GliderGun.oriented(Orientation::default().flipped()))
+        );
+    }
+}
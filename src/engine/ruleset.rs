@@ -0,0 +1,2226 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+
+use serde::{
+    de::{self, IntoDeserializer, Visitor},
+    Deserialize, Serialize,
+};
+
+use super::{
+    condition::{Condition, ConditionIndex, ConditionVariant, Direction, Operator},
+    grid::{Boundary, Cell, GridBackend},
+    id::{Identifiable, UniqueId},
+    material::{GroupId, Material, MaterialColor, MaterialGroup, MaterialId, MaterialMap},
+    pattern::Pattern,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ruleset {
+    pub name: String,
+    /// How a cell matched by several rules picks which one to apply.
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    /// The grid size to switch to when this ruleset is selected, if it needs a particular one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_size: Option<usize>,
+    /// The boundary to switch to when this ruleset is selected, if it needs a particular one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_boundary: Option<Boundary>,
+    /// The material cells become when no rule matches them. Without one they stay the same.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<MaterialId>,
+    /// Notes about the ruleset, such as who made it. Rulesets without any leave the table out.
+    #[serde(default, skip_serializing_if = "RulesetMetadata::is_empty")]
+    pub metadata: RulesetMetadata,
+    pub rules: Vec<Rule>,
+    pub materials: MaterialMap,
+    pub groups: Vec<MaterialGroup>,
+}
+
+impl Ruleset {
+    pub const PATH: &str = "./rulesets/";
+    /// Marks autosave files, between the ruleset's name and the format's extension.
+    const AUTOSAVE_SUFFIX: &str = "autosave";
+
+    pub fn new() -> Self {
+        Self {
+            name: String::from("New Ruleset"),
+            conflict_policy: ConflictPolicy::default(),
+            default_size: None,
+            default_boundary: None,
+            fallback: None,
+            metadata: RulesetMetadata::default(),
+            rules: vec![],
+            materials: MaterialMap::new(Material::default()),
+            groups: vec![],
+        }
+    }
+
+    pub fn blank() -> Self {
+        Self {
+            name: String::from("Blank"),
+            conflict_policy: ConflictPolicy::default(),
+            default_size: None,
+            default_boundary: None,
+            fallback: None,
+            metadata: RulesetMetadata::default(),
+            rules: Vec::new(),
+            materials: MaterialMap::new(Material::blank()),
+            groups: vec![],
+        }
+    }
+    /// Renames and recolors the default material, such as to pick the background of a new
+    /// ruleset. It stays first, so erasing and new grids keep using it.
+    pub fn restyle_default_material(&mut self, name: Option<&str>, color: Option<MaterialColor>) {
+        let Some(default) = self.materials.get_mut_at(0) else {
+            return;
+        };
+        if let Some(name) = name {
+            name.clone_into(&mut default.name);
+        }
+        if let Some(color) = color {
+            default.color = color;
+        }
+    }
+    /// Conway's Game of Life: dead cells with exactly three live neighbors are born, and live
+    /// cells survive with two or three.
+    pub fn game_of_life() -> Self {
+        Self::life_like("Game of Life", vec![3], vec![2, 3])
+    }
+    /// Builds a two-state ruleset from a Life-like rulestring such as `B3/S23`, listing the live
+    /// neighbor counts at which dead cells are born and live cells survive.
+    pub fn from_life_string(rule: &str) -> Result<Self, String> {
+        let rule = rule.trim();
+        let invalid = |reason: String| format!("Invalid rulestring '{rule}': {reason}");
+        let mut born = None;
+        let mut survive = None;
+        for part in rule.split('/') {
+            let mut chars = part.trim().chars();
+            let counts = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => &mut born,
+                Some('S') => &mut survive,
+                _ => return Err(invalid(format!("'{part}' doesn't start with 'B' or 'S'"))),
+            };
+            if counts.is_some() {
+                return Err(invalid(format!("'{part}' repeats a part")));
+            }
+            let mut digits = chars
+                .map(|c| {
+                    c.to_digit(10)
+                        .filter(|&digit| digit <= 8)
+                        .and_then(|digit| u8::try_from(digit).ok())
+                        .ok_or_else(|| invalid(format!("'{c}' isn't a neighbor count from 0 to 8")))
+                })
+                .collect::<Result<Vec<u8>, String>>()?;
+            digits.sort_unstable();
+            digits.dedup();
+            *counts = Some(digits);
+        }
+        let (Some(born), Some(survive)) = (born, survive) else {
+            return Err(invalid(String::from("it needs both a 'B' and an 'S' part")));
+        };
+        let digits = |counts: &[u8]| counts.iter().map(u8::to_string).collect::<String>();
+        let name = format!("B{}/S{}", digits(&born), digits(&survive));
+        Ok(Self::life_like(&name, born, survive))
+    }
+    /// A two-state ruleset where dead cells come alive with a number of live neighbors in
+    /// `born`, and live cells die unless that number is in `survive`.
+    fn life_like(name: &str, born: Vec<u8>, survive: Vec<u8>) -> Self {
+        let mut ruleset = Self::blank();
+        ruleset.name = String::from(name);
+        let mut dead = Material::blank();
+        dead.name = String::from("Dead");
+        let dead_id = dead.id();
+        ruleset.materials = MaterialMap::new(dead);
+        let mut alive = Material::new(&ruleset);
+        alive.name = String::from("Alive");
+        let alive_id = alive.id();
+        ruleset.materials.push(alive);
+
+        let count = |counts: Vec<u8>, inverted: bool| Condition {
+            variant: ConditionVariant::Count(Operator::List(counts)),
+            pattern: Pattern::Material(alive_id),
+            inverted,
+            ..Condition::new(&ruleset)
+        };
+        let rules = vec![
+            Rule {
+                label: String::from("Birth"),
+                input: Pattern::Material(dead_id),
+                outputs: vec![(RuleOutput::Material(alive_id), Rule::DEFAULT_WEIGHT)],
+                conditions: vec![count(born, false)],
+            },
+            Rule {
+                label: String::from("Death"),
+                input: Pattern::Material(alive_id),
+                outputs: vec![(RuleOutput::Material(dead_id), Rule::DEFAULT_WEIGHT)],
+                conditions: vec![count(survive, true)],
+            },
+        ];
+        ruleset.rules = rules;
+        ruleset
+    }
+    pub fn path(&self, format: RulesetFormat) -> PathBuf {
+        self.path_in(Path::new(Self::PATH), format)
+    }
+    fn path_in(&self, directory: &Path, format: RulesetFormat) -> PathBuf {
+        directory.join(format!("{}.{}", self.name, format.extension()))
+    }
+    /// The index of the first ruleset in `rulesets` named `name`. Since [`Ruleset::load_from`]
+    /// loads files in a fixed order, rulesets sharing a name always resolve to the same one.
+    pub fn position_by_name(rulesets: &[Self], name: &str) -> Option<usize> {
+        rulesets.iter().position(|ruleset| ruleset.name == name)
+    }
+    /// Returns whether any ruleset in `rulesets` other than the one at `index` shares this name,
+    /// meaning that saving this ruleset would overwrite that one's file.
+    pub fn shares_name(&self, rulesets: &[Self], index: usize) -> bool {
+        rulesets
+            .iter()
+            .enumerate()
+            .any(|(i, ruleset)| i != index && ruleset.name == self.name)
+    }
+    pub fn save(&self) -> Result<(), String> {
+        self.save_to(Path::new(Self::PATH), RulesetFormat::Toml)
+    }
+    pub fn save_json(&self) -> Result<(), String> {
+        self.save_to(Path::new(Self::PATH), RulesetFormat::Json)
+    }
+    /// Saves this ruleset into `directory` in `format`, replacing any previous save only once
+    /// the new one has been written in full.
+    pub fn save_to(&self, directory: &Path, format: RulesetFormat) -> Result<(), String> {
+        check_file_name(&self.name)
+            .map_err(|err| format!("Could not save ruleset '{}'; {err}", self.name))?;
+        let path = self.path_in(directory, format);
+        // The file would be taken for an autosave and never loaded as a ruleset.
+        if Self::is_autosave(&path) {
+            return Err(format!(
+                "Could not save ruleset '{}'; its name can't end in \".{}\"",
+                self.name,
+                Self::AUTOSAVE_SUFFIX
+            ));
+        }
+        let string = match format {
+            RulesetFormat::Toml => toml::to_string(self).map_err(|err| err.to_string()),
+            RulesetFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(|err| err.to_string())
+            }
+        }
+        .map_err(|err| format!("Could not save ruleset '{self:?}'; serialization failed: {err}"))?;
+        replace_file(&path, |file| file.write_all(string.as_bytes()))
+            .map_err(|err| format!("Could not save ruleset '{self:?}'; file IO failed: {err}"))
+    }
+    pub fn load_all() -> (Vec<Self>, Vec<(PathBuf, String)>) {
+        Self::load_from(Path::new(Self::PATH))
+    }
+    /// Loads every ruleset in `directory` after the built-in [`Ruleset::blank`], creating the
+    /// directory if it doesn't exist yet. Files that can't be read or parsed are skipped and
+    /// returned alongside why, as is the directory itself if it can't be read at all.
+    pub fn load_from(directory: &Path) -> (Vec<Self>, Vec<(PathBuf, String)>) {
+        let mut rulesets = vec![Self::blank()];
+        let mut errors = Vec::new();
+        let entries = match fs::create_dir_all(directory).and_then(|()| directory.read_dir()) {
+            Ok(entries) => entries,
+            Err(err) => {
+                errors.push((
+                    directory.to_owned(),
+                    format!("directory reading failed: {err}"),
+                ));
+                return (rulesets, errors);
+            }
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|file| {
+                if let Ok(file) = file {
+                    let path = file.path();
+                    return RulesetFormat::of(&path)
+                        .filter(|_| !Self::is_autosave(&path))
+                        .map(|format| (path, format));
+                }
+                println!("Could not read file: {file:?}");
+                None
+            })
+            .collect();
+        // Directories list their files in no particular order; sort them so every run loads
+        // the rulesets in the same order.
+        paths.sort_unstable_by(|(first, _), (second, _)| first.cmp(second));
+        for (path, format) in paths {
+            let ruleset = match format {
+                RulesetFormat::Toml => Self::load_toml(&path),
+                RulesetFormat::Json => Self::load_json(&path),
+            };
+            match ruleset {
+                Ok(ruleset) => rulesets.push(ruleset),
+                Err(err) => errors.push((path, err)),
+            }
+        }
+        (rulesets, errors)
+    }
+    pub fn load_toml(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("could not read file: {err}"))?;
+        toml::from_str(&text).map_err(|err| format!("deserialization failed: {err}"))
+    }
+    pub fn load_json(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("could not read file: {err}"))?;
+        serde_json::from_str(&text).map_err(|err| format!("deserialization failed: {err}"))
+    }
+
+    fn autosave_path_in(&self, directory: &Path) -> PathBuf {
+        directory.join(format!(
+            "{}.{}.{}",
+            self.name,
+            Self::AUTOSAVE_SUFFIX,
+            RulesetFormat::Toml.extension()
+        ))
+    }
+    /// Whether `path` is an autosave, or the temporary file of one being written.
+    pub fn is_autosave(path: &Path) -> bool {
+        path.file_stem()
+            .and_then(|stem| Path::new(stem).extension())
+            .is_some_and(|suffix| suffix == Self::AUTOSAVE_SUFFIX)
+    }
+    pub fn autosave(&self) -> Result<(), String> {
+        self.autosave_to(Path::new(Self::PATH))
+    }
+    /// Saves this ruleset as TOML into `directory`, alongside rather than over its own file.
+    pub fn autosave_to(&self, directory: &Path) -> Result<(), String> {
+        check_file_name(&self.name)
+            .map_err(|err| format!("Could not autosave ruleset '{}'; {err}", self.name))?;
+        let string = toml::to_string(self).map_err(|err| {
+            format!(
+                "Could not autosave ruleset '{}'; serialization failed: {err}",
+                self.name
+            )
+        })?;
+        replace_file(&self.autosave_path_in(directory), |file| {
+            file.write_all(string.as_bytes())
+        })
+        .map_err(|err| {
+            format!(
+                "Could not autosave ruleset '{}'; file IO failed: {err}",
+                self.name
+            )
+        })
+    }
+    pub fn remove_autosave(&self) -> Result<(), String> {
+        self.remove_autosave_in(Path::new(Self::PATH))
+    }
+    /// Deletes this ruleset's autosave in `directory`, if it has one.
+    pub fn remove_autosave_in(&self, directory: &Path) -> Result<(), String> {
+        match fs::remove_file(self.autosave_path_in(directory)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(format!(
+                "Could not remove autosave of ruleset '{}': {err}",
+                self.name
+            )),
+            _ => Ok(()),
+        }
+    }
+    pub fn load_autosaves() -> (Vec<Self>, Vec<(PathBuf, String)>) {
+        Self::load_autosaves_from(Path::new(Self::PATH))
+    }
+    /// Loads every autosave in `directory` written since its ruleset was last saved, or whose
+    /// ruleset was never saved at all. Autosaves that can't be loaded are skipped and returned
+    /// with the reason, like [`Ruleset::load_from`] does for rulesets.
+    pub fn load_autosaves_from(directory: &Path) -> (Vec<Self>, Vec<(PathBuf, String)>) {
+        let mut errors = Vec::new();
+        let Ok(entries) = directory.read_dir() else {
+            return (Vec::new(), errors);
+        };
+        let modified = |path: &Path| fs::metadata(path).and_then(|data| data.modified()).ok();
+        let mut paths: Vec<_> = entries
+            .filter_map(|file| file.ok().map(|file| file.path()))
+            .filter(|path| {
+                Self::is_autosave(path) && RulesetFormat::of(path) == Some(RulesetFormat::Toml)
+            })
+            .collect();
+        paths.sort_unstable();
+        let rulesets = paths
+            .into_iter()
+            .filter_map(|path| {
+                let ruleset = match Self::load_toml(&path) {
+                    Ok(ruleset) => ruleset,
+                    Err(err) => {
+                        errors.push((path, err));
+                        return None;
+                    }
+                };
+                let autosaved = modified(&path);
+                let outdated = RulesetFormat::ALL.into_iter().any(|format| {
+                    modified(&ruleset.path_in(directory, format))
+                        .is_some_and(|saved| Some(saved) >= autosaved)
+                });
+                (!outdated).then_some(ruleset)
+            })
+            .collect();
+        (rulesets, errors)
+    }
+
+    /// Encodes this ruleset as compressed, base64-encoded TOML that can be pasted elsewhere.
+    pub fn to_share_string(&self) -> Result<String, String> {
+        let text = toml::to_string(self)
+            .map_err(|err| format!("Could not create share code; serialization failed: {err}"))?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder
+            .write_all(text.as_bytes())
+            .and_then(|()| encoder.finish())
+            .map(|bytes| URL_SAFE_NO_PAD.encode(bytes))
+            .map_err(|err| format!("Could not create share code; compression failed: {err}"))
+    }
+    /// Decodes a ruleset created by [`Ruleset::to_share_string`].
+    pub fn from_share_string(code: &str) -> Result<Self, String> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(code.trim())
+            .map_err(|err| format!("Invalid share code; it is not valid base64: {err}"))?;
+        // Gzip's trailing checksum makes truncated codes fail here rather than parse partially.
+        let mut text = String::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut text)
+            .map_err(|err| format!("Invalid share code; it is truncated or corrupted: {err}"))?;
+        toml::from_str(&text)
+            .map_err(|err| format!("Invalid share code; it does not contain a ruleset: {err}"))
+    }
+
+    pub fn group(&self, id: GroupId) -> Option<&MaterialGroup> {
+        self.groups.iter().find(|group| group.id() == id)
+    }
+
+    /// The group with this id followed by every group it contains, directly or through other
+    /// groups, each listed once even if the groups form a cycle.
+    fn reachable_groups(&self, id: GroupId) -> Vec<&MaterialGroup> {
+        let mut reached: Vec<&MaterialGroup> = Vec::new();
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            if reached.iter().any(|group| group.id() == id) {
+                continue;
+            }
+            if let Some(group) = self.group(id) {
+                reached.push(group);
+                pending.extend(group.subgroups().iter().rev());
+            }
+        }
+        reached
+    }
+    /// Whether cells of this material are protected from rules and painting.
+    pub fn is_immovable(&self, material: MaterialId) -> bool {
+        self.materials
+            .get(material)
+            .is_some_and(|material| material.immovable)
+    }
+
+    /// Whether the group with this id contains the material, directly or through its subgroups.
+    pub fn group_contains(&self, id: GroupId, material: MaterialId) -> bool {
+        let Some(group) = self.group(id) else {
+            return false;
+        };
+        group.contains(material)
+            || (!group.subgroups().is_empty()
+                && self
+                    .reachable_groups(id)
+                    .iter()
+                    .any(|group| group.contains(material)))
+    }
+    /// Every material in the group with this id or its subgroups, each listed once.
+    pub fn group_materials(&self, id: GroupId) -> Vec<MaterialId> {
+        let mut materials = Vec::new();
+        for group in self.reachable_groups(id) {
+            for &material in group.materials() {
+                if !materials.contains(&material) {
+                    materials.push(material);
+                }
+            }
+        }
+        materials
+    }
+    /// Whether making `subgroup` a member of `group` would let a group contain itself.
+    pub fn would_cycle(&self, group: GroupId, subgroup: GroupId) -> bool {
+        self.reachable_groups(subgroup)
+            .iter()
+            .any(|reached| reached.id() == group)
+    }
+
+    pub fn index_of_group(&self, id: GroupId) -> Option<usize> {
+        self.groups.iter().position(|group| group.id() == id)
+    }
+
+    pub fn pattern_values(&self) -> Vec<String> {
+        let material_names = self.materials.iter().map(|m| m.name.clone());
+        let group_names = self.groups.iter().map(|g| format!("#{}", g.name.clone()));
+        material_names
+            .chain(group_names)
+            .chain(std::iter::once(String::from("Self")))
+            .collect()
+    }
+
+    /// Lists every rule, condition, and group, and the fallback, that refers to a material or
+    /// group no longer present in this ruleset.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if let Some(material) = self.fallback {
+            if self.materials.get(material).is_none() {
+                issues.push(ValidationIssue::Fallback { material });
+            }
+        }
+        for (rule_index, rule) in self.rules.iter().enumerate() {
+            let outputs = rule.outputs.iter().filter_map(|&(output, _)| match output {
+                RuleOutput::Material(id) => Some(Pattern::Material(id)),
+                RuleOutput::CopyDirection(_) | RuleOutput::SwapDirection(_) => None,
+            });
+            let patterns = std::iter::once(rule.input)
+                .chain(outputs)
+                .chain(rule.conditions.iter().flat_map(Condition::patterns));
+            issues.extend(
+                patterns
+                    .filter(|&pattern| !self.contains_pattern(pattern))
+                    .map(|reference| ValidationIssue::Rule {
+                        rule: rule_index,
+                        title: rule.title(rule_index),
+                        reference,
+                    }),
+            );
+        }
+        for (group_index, group) in self.groups.iter().enumerate() {
+            issues.extend(
+                group
+                    .materials()
+                    .iter()
+                    .filter(|&&id| self.materials.get(id).is_none())
+                    .map(|&material| ValidationIssue::Group {
+                        group: group_index,
+                        material,
+                    }),
+            );
+            issues.extend(
+                group
+                    .subgroups()
+                    .iter()
+                    .filter(|&&id| self.group(id).is_none())
+                    .map(|&subgroup| ValidationIssue::Subgroup {
+                        group: group_index,
+                        subgroup,
+                    }),
+            );
+            if group
+                .subgroups()
+                .iter()
+                .any(|&subgroup| self.would_cycle(group.id(), subgroup))
+            {
+                issues.push(ValidationIssue::Cycle { group: group_index });
+            }
+        }
+        issues
+    }
+
+    /// Remaps every dangling reference reported by [`Ruleset::validate`] to the default material,
+    /// except a missing fallback, which is cleared rather than turning every cell into default.
+    pub fn fix_references(&mut self) {
+        let default = self.materials.default().id();
+        let material_ids: Vec<MaterialId> = self.materials.iter().map(Identifiable::id).collect();
+        self.fallback = self.fallback.filter(|id| material_ids.contains(id));
+        let group_ids: Vec<GroupId> = self.groups.iter().map(Identifiable::id).collect();
+        let fix_pattern = |pattern: &mut Pattern| {
+            let exists = match *pattern {
+                Pattern::Material(id) => material_ids.contains(&id),
+                Pattern::Group(id) => group_ids.contains(&id),
+                Pattern::SelfSame => true,
+            };
+            if !exists {
+                *pattern = Pattern::Material(default);
+            }
+        };
+        for rule in &mut self.rules {
+            fix_pattern(&mut rule.input);
+            for (output, _) in &mut rule.outputs {
+                if let RuleOutput::Material(id) = *output {
+                    if !material_ids.contains(&id) {
+                        *output = RuleOutput::Material(default);
+                    }
+                }
+            }
+            for pattern in rule.conditions.iter_mut().flat_map(Condition::patterns_mut) {
+                fix_pattern(pattern);
+            }
+        }
+        for group in &mut self.groups {
+            for id in group.materials_mut() {
+                if !material_ids.contains(id) {
+                    *id = default;
+                }
+            }
+            group.retain_subgroups(|id| group_ids.contains(id));
+        }
+        // Break cycles by dropping each membership that leads back to the group holding it.
+        for index in 0..self.groups.len() {
+            let id = self.groups[index].id();
+            let cyclic: Vec<GroupId> = self.groups[index]
+                .subgroups()
+                .iter()
+                .copied()
+                .filter(|&subgroup| self.would_cycle(id, subgroup))
+                .collect();
+            self.groups[index].retain_subgroups(|subgroup| !cyclic.contains(subgroup));
+        }
+    }
+
+    /// Appends `other`'s materials, groups, and rules to this ruleset, giving its materials and
+    /// groups fresh ids so they can't alias existing ones. Fails without changing anything if
+    /// `other` has dangling references or duplicate ids.
+    pub fn merge(&mut self, other: Self) -> Result<(), String> {
+        if let Some(issue) = other.validate().first() {
+            return Err(format!("Could not merge ruleset '{}': {issue}", other.name));
+        }
+        let mut merged = self.clone();
+
+        let mut material_ids = HashMap::new();
+        for material in other.materials.iter() {
+            let id = merged.materials.next_id();
+            if material_ids.insert(material.id(), id).is_some() {
+                return Err(format!(
+                    "Could not merge ruleset '{}': material id {} is used twice",
+                    other.name,
+                    material.id()
+                ));
+            }
+            merged.materials.push(material.clone().with_id(id));
+        }
+        let mut group_ids = HashMap::new();
+        for group in other.groups {
+            let id = UniqueId::next(&merged.groups);
+            if group_ids.insert(group.id(), id).is_some() {
+                return Err(format!(
+                    "Could not merge ruleset '{}': group id {} is used twice",
+                    other.name,
+                    group.id()
+                ));
+            }
+            merged.groups.push(group.with_id(id));
+        }
+
+        let first_merged_group = merged.groups.len() - group_ids.len();
+        let mut rules = other.rules;
+        remap_ids(
+            &mut merged.groups[first_merged_group..],
+            &mut rules,
+            &material_ids,
+            &group_ids,
+        );
+        merged.rules.extend(rules);
+        *self = merged;
+        Ok(())
+    }
+
+    /// A copy of this ruleset named with ` copy` appended, whose materials and groups have fresh
+    /// random ids, with every reference to them remapped. The copy can then be edited or merged
+    /// without its ids aliasing the original's. Fails if this ruleset has dangling references or
+    /// duplicate ids.
+    pub fn duplicate(&self) -> Result<Self, String> {
+        if let Some(issue) = self.validate().first() {
+            return Err(format!(
+                "Could not duplicate ruleset '{}': {issue}",
+                self.name
+            ));
+        }
+        let mut materials = Vec::new();
+        let mut material_ids = HashMap::new();
+        for material in self.materials.iter() {
+            let id = UniqueId::new(&materials);
+            if material_ids.insert(material.id(), id).is_some() {
+                return Err(format!(
+                    "Could not duplicate ruleset '{}': material id {} is used twice",
+                    self.name,
+                    material.id()
+                ));
+            }
+            materials.push(material.clone().with_id(id));
+        }
+        let mut groups = Vec::new();
+        let mut group_ids = HashMap::new();
+        for group in &self.groups {
+            let id = UniqueId::new(&groups);
+            if group_ids.insert(group.id(), id).is_some() {
+                return Err(format!(
+                    "Could not duplicate ruleset '{}': group id {} is used twice",
+                    self.name,
+                    group.id()
+                ));
+            }
+            groups.push(group.clone().with_id(id));
+        }
+
+        let mut rules = self.rules.clone();
+        remap_ids(&mut groups, &mut rules, &material_ids, &group_ids);
+        Ok(Self {
+            name: format!("{} copy", self.name),
+            fallback: self.fallback.map(|id| material_ids[&id]),
+            rules,
+            materials: MaterialMap::new_unchecked(materials),
+            groups,
+            ..self.clone()
+        })
+    }
+
+    /// Describes every rule and group that refers to the material with this id.
+    pub fn references_to(&self, id: MaterialId) -> Vec<String> {
+        let pattern = Pattern::Material(id);
+        let rules = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| {
+                rule.input == pattern
+                    || rule
+                        .outputs
+                        .iter()
+                        .any(|&(output, _)| output == RuleOutput::Material(id))
+                    || rule
+                        .conditions
+                        .iter()
+                        .flat_map(Condition::patterns)
+                        .any(|condition_pattern| condition_pattern == pattern)
+            })
+            .map(|(index, rule)| rule.title(index));
+        let groups = self
+            .groups
+            .iter()
+            .filter(|group| group.contains(id))
+            .map(|group| format!("Group '{}'", group.name));
+        let fallback = (self.fallback == Some(id)).then(|| String::from("The fallback"));
+        rules.chain(groups).chain(fallback).collect()
+    }
+
+    /// Removes a material, remapping any references to it to the default material.
+    pub fn remove_material(&mut self, id: MaterialId) {
+        self.materials.remove(id);
+        self.fix_references();
+    }
+
+    /// Pairs each rule that can never fire with the earlier rule that always applies in its
+    /// place. An earlier rule shadows a later one if its input covers the later input (the same
+    /// pattern, [`Pattern::SelfSame`], or a group containing the later material), every one of its
+    /// conditions also appears in the later rule, and all its outputs are materials, since copies
+    /// and swaps can fail past an edge. Under [`ConflictPolicy::MostSpecific`] it must also be at
+    /// least as specific, and under [`ConflictPolicy::Random`] no rule is shadowed.
+    ///
+    /// This is a heuristic: conditions are only compared for equality, so a rule shadowed through
+    /// conditions that are merely looser, such as a wider count, isn't reported.
+    pub fn find_shadowed_rules(&self) -> Vec<(usize, usize)> {
+        if matches!(self.conflict_policy, ConflictPolicy::Random(_)) {
+            return Vec::new();
+        }
+        self.rules
+            .iter()
+            .enumerate()
+            .filter_map(|(later, rule)| {
+                self.rules[..later]
+                    .iter()
+                    .position(|earlier| self.shadows(earlier, rule))
+                    .map(|earlier| (later, earlier))
+            })
+            .collect()
+    }
+    fn shadows(&self, earlier: &Rule, later: &Rule) -> bool {
+        let covers_input = earlier.input == later.input
+            || match (earlier.input, later.input) {
+                (Pattern::SelfSame, _) => true,
+                (Pattern::Group(group), Pattern::Material(material)) => {
+                    self.group_contains(group, material)
+                }
+                _ => false,
+            };
+        let always_applies = earlier
+            .outputs
+            .iter()
+            .all(|&(output, _)| matches!(output, RuleOutput::Material(_)));
+        let looser = earlier
+            .conditions
+            .iter()
+            .all(|condition| later.conditions.contains(condition));
+        let preferred = self.conflict_policy != ConflictPolicy::MostSpecific
+            || earlier.specificity() >= later.specificity();
+        covers_input && always_applies && looser && preferred
+    }
+
+    fn contains_pattern(&self, pattern: Pattern) -> bool {
+        match pattern {
+            Pattern::Material(id) => self.materials.get(id).is_some(),
+            Pattern::Group(id) => self.group(id).is_some(),
+            Pattern::SelfSame => true,
+        }
+    }
+
+    /// Describes every rule in prose, one sentence per line in the order they're checked,
+    /// followed by the fallback if there is one.
+    pub fn describe(&self) -> String {
+        let mut lines = vec![self.name.clone()];
+        lines.extend(
+            self.rules
+                .iter()
+                .enumerate()
+                .map(|(index, rule)| rule.describe(self, index)),
+        );
+        if let Some(fallback) = self.fallback {
+            lines.push(format!(
+                "Every cell no rule applies to becomes {}.",
+                Pattern::Material(fallback).describe(self)
+            ));
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+    /// Writes [`Ruleset::describe`] to a text file beside the saved rulesets, returning its path.
+    pub fn save_description(&self) -> Result<PathBuf, String> {
+        check_file_name(&self.name)
+            .map_err(|err| format!("Could not describe ruleset '{}'; {err}", self.name))?;
+        let path = Path::new(Self::PATH).join(format!("{}.txt", self.name));
+        replace_file(&path, |file| file.write_all(self.describe().as_bytes())).map_err(|err| {
+            format!(
+                "Could not describe ruleset '{}'; file IO failed: {err}",
+                self.name
+            )
+        })?;
+        Ok(path)
+    }
+
+    pub fn output_values(&self) -> Vec<String> {
+        let material_names = self.materials.iter().map(|m| m.name.clone());
+        let copy_names = Direction::ALL
+            .iter()
+            .map(|direction| format!("Copy {direction:?}"));
+        let swap_names = Direction::ALL
+            .iter()
+            .map(|direction| format!("Swap {direction:?}"));
+        material_names.chain(copy_names).chain(swap_names).collect()
+    }
+}
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Notes kept alongside a ruleset that don't affect how it runs. Every field is optional, and
+/// blank ones are left out when saving.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RulesetMetadata {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub author: String,
+    /// Shown when hovering over the ruleset selector.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// When the ruleset was made, written however its author likes.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub created: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+impl RulesetMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+    pub fn get(&self, field: MetadataField) -> &str {
+        match field {
+            MetadataField::Author => &self.author,
+            MetadataField::Description => &self.description,
+            MetadataField::Created => &self.created,
+            MetadataField::Notes => &self.notes,
+        }
+    }
+    pub fn get_mut(&mut self, field: MetadataField) -> &mut String {
+        match field {
+            MetadataField::Author => &mut self.author,
+            MetadataField::Description => &mut self.description,
+            MetadataField::Created => &mut self.created,
+            MetadataField::Notes => &mut self.notes,
+        }
+    }
+}
+
+/// One of the fields of [`RulesetMetadata`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataField {
+    Author,
+    Description,
+    Created,
+    Notes,
+}
+impl MetadataField {
+    pub const ALL: [Self; 4] = [Self::Author, Self::Description, Self::Created, Self::Notes];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Author => "Author",
+            Self::Description => "Description",
+            Self::Created => "Created",
+            Self::Notes => "Notes",
+        }
+    }
+}
+
+/// A starting point for new rulesets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RulesetTemplate {
+    #[default]
+    Empty,
+    GameOfLife,
+}
+impl RulesetTemplate {
+    pub const ALL: [Self; 2] = [Self::Empty, Self::GameOfLife];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Empty => "Empty",
+            Self::GameOfLife => "Game of Life",
+        }
+    }
+    pub fn names() -> Vec<String> {
+        Self::ALL
+            .iter()
+            .map(|template| template.name().to_owned())
+            .collect()
+    }
+    pub fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|&template| template == self)
+            .unwrap_or_default()
+    }
+    pub fn build(self) -> Ruleset {
+        match self {
+            Self::Empty => Ruleset::new(),
+            Self::GameOfLife => Ruleset::game_of_life(),
+        }
+    }
+}
+
+/// A file format rulesets can be saved in and loaded from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RulesetFormat {
+    #[default]
+    Toml,
+    Json,
+}
+impl RulesetFormat {
+    pub const ALL: [Self; 2] = [Self::Toml, Self::Json];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Toml => "TOML",
+            Self::Json => "JSON",
+        }
+    }
+    pub fn names() -> Vec<String> {
+        Self::ALL
+            .iter()
+            .map(|format| format.name().to_owned())
+            .collect()
+    }
+    pub fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|&format| format == self)
+            .unwrap_or_default()
+    }
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            Self::Json => "json",
+        }
+    }
+    /// The format of the file at `path` going by its extension, or `None` if it isn't a ruleset.
+    pub fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?;
+        Self::ALL
+            .into_iter()
+            .find(|format| extension == format.extension())
+    }
+}
+
+/// Points the members and subgroups of `groups`, and every pattern and output of `rules`, at the
+/// ids `material_ids` and `group_ids` map them to, which must cover every one they refer to.
+fn remap_ids(
+    groups: &mut [MaterialGroup],
+    rules: &mut [Rule],
+    material_ids: &HashMap<MaterialId, MaterialId>,
+    group_ids: &HashMap<GroupId, GroupId>,
+) {
+    for group in groups {
+        for material in group.materials_mut() {
+            *material = material_ids[&*material];
+        }
+        for subgroup in group.subgroups_mut() {
+            *subgroup = group_ids[&*subgroup];
+        }
+    }
+    let remap = |pattern: Pattern| match pattern {
+        Pattern::Material(id) => Pattern::Material(material_ids[&id]),
+        Pattern::Group(id) => Pattern::Group(group_ids[&id]),
+        Pattern::SelfSame => Pattern::SelfSame,
+    };
+    for rule in rules {
+        rule.input = remap(rule.input);
+        for (output, _) in &mut rule.outputs {
+            if let RuleOutput::Material(id) = *output {
+                *output = RuleOutput::Material(material_ids[&id]);
+            }
+        }
+        for pattern in rule.conditions.iter_mut().flat_map(Condition::patterns_mut) {
+            *pattern = remap(*pattern);
+        }
+    }
+}
+
+/// Checks that `name` can be used as a file name on every platform, rather than escaping its
+/// directory or being rejected by the filesystem partway through a save.
+pub fn check_file_name(name: &str) -> Result<(), String> {
+    const ILLEGAL: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+    if name.trim().is_empty() || name == "." || name == ".." {
+        return Err(String::from("its name can't be used as a file name"));
+    }
+    name.chars()
+        .find(|&c| ILLEGAL.contains(&c) || c.is_control())
+        .map_or(Ok(()), |c| Err(format!("its name can't contain {c:?}")))
+}
+
+/// Replaces the file at `path` with what `write` puts into a temporary file beside it.
+/// Renaming is atomic on the same filesystem, so if writing fails or is interrupted the
+/// original file is left whole.
+fn replace_file(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> io::Result<()>,
+) -> io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    let result = fs::File::create(&temporary)
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temporary, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    result
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// A rule's input, output, or one of its conditions refers to something missing.
+    Rule {
+        rule: usize,
+        /// The rule's [`Rule::title`], so messages can tell rules apart.
+        title: String,
+        reference: Pattern,
+    },
+    /// A group contains a material that no longer exists.
+    Group { group: usize, material: MaterialId },
+    /// A group contains a group that no longer exists.
+    Subgroup { group: usize, subgroup: GroupId },
+    /// A group contains itself, directly or through other groups.
+    Cycle { group: usize },
+    /// The fallback material no longer exists.
+    Fallback { material: MaterialId },
+}
+impl ValidationIssue {
+    pub const fn rule(&self) -> Option<usize> {
+        match self {
+            Self::Rule { rule, .. } => Some(*rule),
+            Self::Group { .. }
+            | Self::Subgroup { .. }
+            | Self::Cycle { .. }
+            | Self::Fallback { .. } => None,
+        }
+    }
+}
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rule {
+                title,
+                reference: Pattern::Material(id),
+                ..
+            } => write!(f, "{title} refers to missing material {id:?}"),
+            Self::Rule {
+                title,
+                reference: Pattern::Group(id),
+                ..
+            } => write!(f, "{title} refers to missing group {id:?}"),
+            Self::Rule {
+                title,
+                reference: Pattern::SelfSame,
+                ..
+            } => write!(f, "{title} refers to the cell itself"),
+            Self::Group { group, material } => {
+                write!(
+                    f,
+                    "Group {} contains missing material {material:?}",
+                    group + 1
+                )
+            }
+            Self::Subgroup { group, subgroup } => {
+                write!(f, "Group {} contains missing group {subgroup:?}", group + 1)
+            }
+            Self::Cycle { group } => {
+                write!(
+                    f,
+                    "Group {} contains itself through its subgroups",
+                    group + 1
+                )
+            }
+            Self::Fallback { material } => {
+                write!(f, "The fallback is missing material {material:?}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleIndex {
+    index: usize,
+}
+impl RuleIndex {
+    const fn new(rule_index: usize) -> Self {
+        Self { index: rule_index }
+    }
+
+    pub const fn value(self) -> usize {
+        self.index
+    }
+    pub const fn with_condition(self, condition_index: usize) -> ConditionIndex {
+        ConditionIndex::new(self.index, condition_index)
+    }
+    pub fn rule(self, ruleset: &Ruleset) -> &Rule {
+        ruleset.rules.get(self.index).expect("invalid rule index")
+    }
+
+    pub fn rule_mut(self, ruleset: &mut Ruleset) -> &mut Rule {
+        ruleset
+            .rules
+            .get_mut(self.index)
+            .expect("invalid rule index")
+    }
+}
+impl From<usize> for RuleIndex {
+    fn from(value: usize) -> Self {
+        Self::new(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleOutput {
+    Material(MaterialId),
+    /// Becomes whatever material is in the neighboring cell in this direction.
+    CopyDirection(Direction),
+    /// Exchanges materials with the neighboring cell in this direction.
+    /// See [`super::grid::Grid`]'s `next_generation` for how conflicting swaps are resolved.
+    SwapDirection(Direction),
+}
+impl RuleOutput {
+    const SWAP_PREFIX: &str = "Swap ";
+
+    pub fn from_index(ruleset: &Ruleset, index: usize) -> Option<Self> {
+        let direction_count = Direction::ALL.len();
+        ruleset
+            .materials
+            .get_at(index)
+            .map(|m| Self::Material(m.id()))
+            .or_else(|| {
+                let index = index - ruleset.materials.len();
+                let direction = *Direction::ALL.get(index % direction_count)?;
+                match index / direction_count {
+                    0 => Some(Self::CopyDirection(direction)),
+                    1 => Some(Self::SwapDirection(direction)),
+                    _ => None,
+                }
+            })
+    }
+
+    /// Describes the output in prose, such as `becomes Alive` or `swaps with its South neighbor`.
+    pub fn describe(self, ruleset: &Ruleset) -> String {
+        match self {
+            Self::Material(id) => format!("becomes {}", Pattern::Material(id).describe(ruleset)),
+            Self::CopyDirection(direction) => format!("copies its {direction:?} neighbor"),
+            Self::SwapDirection(direction) => format!("swaps with its {direction:?} neighbor"),
+        }
+    }
+
+    pub fn index(self, ruleset: &Ruleset) -> Option<usize> {
+        let direction_index = |direction| Direction::ALL.iter().position(|&d| d == direction);
+        match self {
+            Self::Material(id) => ruleset.materials.index_of(id),
+            Self::CopyDirection(direction) => {
+                direction_index(direction).map(|index| ruleset.materials.len() + index)
+            }
+            Self::SwapDirection(direction) => direction_index(direction)
+                .map(|index| ruleset.materials.len() + Direction::ALL.len() + index),
+        }
+    }
+}
+impl Serialize for RuleOutput {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Material(id) => id.serialize(serializer),
+            Self::CopyDirection(direction) => direction.serialize(serializer),
+            Self::SwapDirection(direction) => {
+                serializer.serialize_str(&format!("{}{direction:?}", Self::SWAP_PREFIX))
+            }
+        }
+    }
+}
+struct RuleOutputVisitor;
+impl<'de> Visitor<'de> for RuleOutputVisitor {
+    type Value = RuleOutput;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "a material id or a direction")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let id = u32::try_from(v)
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Signed(v), &self))?;
+        Ok(RuleOutput::Material(UniqueId::new_unchecked(id)))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let id = u32::try_from(v)
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Unsigned(v), &self))?;
+        Ok(RuleOutput::Material(UniqueId::new_unchecked(id)))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        v.strip_prefix(RuleOutput::SWAP_PREFIX).map_or_else(
+            || Direction::deserialize(v.into_deserializer()).map(RuleOutput::CopyDirection),
+            |direction| {
+                Direction::deserialize(direction.into_deserializer()).map(RuleOutput::SwapDirection)
+            },
+        )
+    }
+}
+impl<'de> Deserialize<'de> for RuleOutput {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(RuleOutputVisitor)
+    }
+}
+
+/// Decides which rule applies when more than one matches the same cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ConflictPolicy {
+    /// The earliest matching rule.
+    #[default]
+    FirstMatch,
+    /// The matching rule with the highest [`Rule::specificity`], or the earliest of those tied.
+    MostSpecific,
+    /// A matching rule chosen at random. The same seed and grid always make the same choices.
+    Random(u32),
+}
+impl ConflictPolicy {
+    pub const NAMES: [&str; 3] = ["First Match", "Most Specific", "Random"];
+
+    pub const fn index(self) -> usize {
+        match self {
+            Self::FirstMatch => 0,
+            Self::MostSpecific => 1,
+            Self::Random(_) => 2,
+        }
+    }
+    /// The policy at `index` in [`Self::NAMES`], keeping the current seed if there is one.
+    pub const fn with_index(self, index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Self::FirstMatch),
+            1 => Some(Self::MostSpecific),
+            2 => Some(Self::Random(self.seed())),
+            _ => None,
+        }
+    }
+    /// The seed random choices are drawn from, or 0 for policies that never draw any.
+    pub const fn seed(self) -> u32 {
+        match self {
+            Self::Random(seed) => seed,
+            Self::FirstMatch | Self::MostSpecific => 0,
+        }
+    }
+
+    /// Picks the position in `matches` of the transformation to apply, where each match holds
+    /// the index of the rule in `rules` that produced it, in ascending order.
+    /// Returns `None` if there are no matches, or if the choice is random and `random` is `None`.
+    pub fn choose<R: Rng>(
+        self,
+        rules: &[Rule],
+        matches: &[(usize, Transformation)],
+        random: Option<&mut R>,
+    ) -> Option<usize> {
+        if matches.is_empty() {
+            return None;
+        }
+        match self {
+            Self::FirstMatch => Some(0),
+            // `max_by_key` keeps the last of equal elements, so search in reverse to favor
+            // earlier rules.
+            Self::MostSpecific => matches
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, (rule, _))| rules.get(*rule).map_or(0, Rule::specificity))
+                .map(|(position, _)| position),
+            Self::Random(_) => random.map(|random| random.gen_range(0..matches.len())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transformation {
+    Replace(Cell),
+    /// Swap materials with the cell at this index.
+    Swap(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rule {
+    /// A description shown above the rule in the editor. Empty if unlabeled.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub label: String,
+    pub input: Pattern,
+    /// What a matching cell becomes, each with a weight. When there are several, one is drawn
+    /// at random in proportion to its weight. Never empty.
+    #[serde(rename = "output", serialize_with = "serialize_outputs")]
+    pub outputs: Vec<(RuleOutput, u32)>,
+    pub conditions: Vec<Condition>,
+}
+/// A rule with a single output writes it alone, as rules did before weighted outputs.
+fn serialize_outputs<S>(outputs: &[(RuleOutput, u32)], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match outputs {
+        [(output, _)] => output.serialize(serializer),
+        outputs => outputs.serialize(serializer),
+    }
+}
+impl Rule {
+    /// The weight new outputs start with.
+    pub const DEFAULT_WEIGHT: u32 = 1;
+
+    pub fn new(ruleset: &Ruleset) -> Self {
+        Self {
+            label: String::new(),
+            input: Pattern::Material(ruleset.materials.default().id()),
+            outputs: vec![(
+                RuleOutput::Material(ruleset.materials.default().id()),
+                Self::DEFAULT_WEIGHT,
+            )],
+            conditions: Vec::new(),
+        }
+    }
+
+    /// Names the rule at `index` for messages, including its label if it has one.
+    pub fn title(&self, index: usize) -> String {
+        if self.label.is_empty() {
+            format!("Rule {}", index + 1)
+        } else {
+            format!("Rule {} '{}'", index + 1, self.label)
+        }
+    }
+
+    /// Describes the rule at `index` in prose, such as
+    /// `Rule 1: Each Dead cell that has exactly 3 Alive neighbors becomes Alive.`
+    /// Several outputs are listed with their weights.
+    pub fn describe(&self, ruleset: &Ruleset, index: usize) -> String {
+        let input = match self.input {
+            Pattern::SelfSame => String::from("Every cell"),
+            pattern => format!("Each {} cell", pattern.describe(ruleset)),
+        };
+        let conditions: Vec<String> = self
+            .conditions
+            .iter()
+            .map(|condition| condition.describe(ruleset))
+            .collect();
+        let conditions = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" that {}", conditions.join(" and "))
+        };
+        let outputs: Vec<String> = match self.outputs.as_slice() {
+            [(output, _)] => vec![output.describe(ruleset)],
+            outputs => outputs
+                .iter()
+                .map(|(output, weight)| format!("{} (weight {weight})", output.describe(ruleset)))
+                .collect(),
+        };
+        format!(
+            "{}: {input}{conditions} {}.",
+            self.title(index),
+            outputs.join(" or ")
+        )
+    }
+
+    /// How narrowly the rule targets cells: one point per condition, plus one if its input is
+    /// a single material rather than a group.
+    pub fn specificity(&self) -> usize {
+        self.conditions.len() + usize::from(matches!(self.input, Pattern::Material(_)))
+    }
+
+    /// Draws one of the outputs in proportion to their weights. Without `random`, or if every
+    /// weight is zero, the heaviest output is taken instead, favoring earlier ones on ties.
+    /// A single output is returned without drawing anything.
+    pub fn output<R: Rng>(&self, random: Option<&mut R>) -> Option<RuleOutput> {
+        let heaviest = || {
+            // `max_by_key` keeps the last of equal elements, so search in reverse.
+            self.outputs
+                .iter()
+                .rev()
+                .max_by_key(|&&(_, weight)| weight)
+                .map(|&(output, _)| output)
+        };
+        match (self.outputs.as_slice(), random) {
+            ([(output, _)], _) => Some(*output),
+            (outputs, Some(random)) => {
+                WeightedIndex::new(outputs.iter().map(|&(_, weight)| weight)).map_or_else(
+                    |_| heaviest(),
+                    |distribution| Some(outputs[distribution.sample(random)].0),
+                )
+            }
+            (_, None) => heaviest(),
+        }
+    }
+
+    /// Whether the rule applies to `cell` at `index`: its input matches the cell and all its
+    /// conditions hold. An applying rule can still leave the cell alone, if its output reaches
+    /// past a dead boundary.
+    pub fn matches(&self, grid: &impl GridBackend, cell: Cell, index: usize) -> bool {
+        self.input.matches(grid.ruleset(), cell, cell)
+            && self.conditions.iter().all(|condition| {
+                let neighbors = grid.neighbors(index, condition.neighbor_radius());
+                condition.matches(cell, &neighbors, grid.ruleset(), grid.generation())
+            })
+    }
+    /// What the rule does to `cell` at `index`, or `None` if it doesn't match. Among several
+    /// outputs, one is drawn from `random`; see [`Rule::output`].
+    pub fn transformed<R: Rng>(
+        &self,
+        grid: &impl GridBackend,
+        cell: Cell,
+        index: usize,
+        random: Option<&mut R>,
+    ) -> Option<Transformation> {
+        if !self.matches(grid, cell, index) {
+            return None;
+        }
+        match self.output(random)? {
+            RuleOutput::Material(id) => Some(Transformation::Replace(Cell::new(id))),
+            RuleOutput::CopyDirection(direction) => {
+                let (x, y) = direction.offset();
+                grid.get_neighbor(index, x, y).map(Transformation::Replace)
+            }
+            RuleOutput::SwapDirection(direction) => {
+                let (x, y) = direction.offset();
+                grid.neighbor_index(index, x, y).map(Transformation::Swap)
+            }
+        }
+    }
+}
+/// Reads a rule's outputs, either as a list of `[output, weight]` pairs or as the single output
+/// rules were saved with before they could have several.
+struct RuleOutputs(Vec<(RuleOutput, u32)>);
+struct RuleOutputsVisitor;
+impl<'de> Visitor<'de> for RuleOutputsVisitor {
+    type Value = RuleOutputs;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "an output or a list of weighted outputs")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        RuleOutputVisitor
+            .visit_i64(v)
+            .map(|output| RuleOutputs(vec![(output, Rule::DEFAULT_WEIGHT)]))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        RuleOutputVisitor
+            .visit_u64(v)
+            .map(|output| RuleOutputs(vec![(output, Rule::DEFAULT_WEIGHT)]))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        RuleOutputVisitor
+            .visit_str(v)
+            .map(|output| RuleOutputs(vec![(output, Rule::DEFAULT_WEIGHT)]))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut outputs = Vec::new();
+        while let Some(output) = seq.next_element()? {
+            outputs.push(output);
+        }
+        if outputs.is_empty() {
+            return Err(de::Error::invalid_length(0, &self));
+        }
+        Ok(RuleOutputs(outputs))
+    }
+}
+impl<'de> Deserialize<'de> for RuleOutputs {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(RuleOutputsVisitor)
+    }
+}
+struct RuleVisitor;
+impl<'de> Visitor<'de> for RuleVisitor {
+    type Value = Rule;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "struct Rule")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut label = None;
+        let mut input = None;
+        let mut output = None;
+        let mut conditions = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "label" => {
+                    if label.is_some() {
+                        return Err(de::Error::duplicate_field("label"));
+                    }
+                    label = Some(map.next_value()?);
+                }
+                "input" => {
+                    if input.is_some() {
+                        return Err(de::Error::duplicate_field("input"));
+                    }
+                    input = Some(map.next_value()?);
+                }
+                "output" => {
+                    if output.is_some() {
+                        return Err(de::Error::duplicate_field("output"));
+                    }
+                    output = Some(map.next_value::<RuleOutputs>()?.0);
+                }
+                "conditions" => {
+                    if conditions.is_some() {
+                        return Err(de::Error::duplicate_field("conditions"));
+                    }
+                    conditions = Some(map.next_value()?);
+                }
+                _ => {
+                    return Err(de::Error::unknown_field(
+                        &key,
+                        &["label", "input", "output", "conditions"],
+                    ))
+                }
+            }
+        }
+
+        let label = label.unwrap_or_default();
+        let input = input.ok_or_else(|| de::Error::missing_field("input"))?;
+        let output = output.ok_or_else(|| de::Error::missing_field("output"))?;
+        let conditions = conditions.ok_or_else(|| de::Error::missing_field("conditions"))?;
+
+        Ok(Rule {
+            label,
+            input,
+            outputs: output,
+            conditions,
+        })
+    }
+}
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            "Rule",
+            &["label", "input", "output", "conditions"],
+            RuleVisitor,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{
+        condition::{ConditionVariant, Direction, DirectionalMode, Neighborhood, Operator},
+        id::UniqueId,
+        material::MaterialColor,
+        ruleset::Rule,
+    };
+
+    use super::*;
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn serde_rule() {
+        let rule = Rule {
+            label: "Falling sand".to_owned(),
+            input: Pattern::Material(UniqueId::new_unchecked(10)),
+            outputs: vec![(RuleOutput::Material(UniqueId::new_unchecked(100)), 1)],
+            conditions: vec![
+                Condition {
+                    variant: ConditionVariant::Count(Operator::List(vec![1, 2, 3])),
+                    pattern: Pattern::Group(UniqueId::new_unchecked(20)),
+                    inverted: false,
+                    neighborhood: Neighborhood::Moore,
+                    radius: 1,
+                    directional_mode: DirectionalMode::Any,
+                },
+                Condition {
+                    variant: ConditionVariant::Directional(vec![
+                        Direction::North,
+                        Direction::South,
+                    ]),
+                    pattern: Pattern::Group(UniqueId::new_unchecked(200)),
+                    inverted: false,
+                    neighborhood: Neighborhood::Moore,
+                    radius: 1,
+                    directional_mode: DirectionalMode::Any,
+                },
+            ],
+        };
+
+        dbg!(&rule);
+
+        let rule_string = toml::to_string(&rule).unwrap();
+        println!("{rule_string:?}");
+
+        let new_rule: Rule = toml::from_str(&rule_string).unwrap();
+
+        dbg!(&new_rule);
+
+        assert_eq!(rule, new_rule);
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn serde_rule_output() {
+        for output in [
+            RuleOutput::CopyDirection(Direction::North),
+            RuleOutput::SwapDirection(Direction::South),
+        ] {
+            let rule = Rule {
+                label: String::new(),
+                input: Pattern::Material(UniqueId::new_unchecked(10)),
+                outputs: vec![(output, 1)],
+                conditions: vec![],
+            };
+            let rule_string = toml::to_string(&rule).unwrap();
+            println!("{rule_string:?}");
+            let new_rule: Rule = toml::from_str(&rule_string).unwrap();
+            assert_eq!(rule, new_rule);
+        }
+
+        let material_rule: Rule =
+            toml::from_str("input = \"10m\"\noutput = 100\nconditions = []").unwrap();
+        assert_eq!(
+            material_rule.outputs,
+            vec![(RuleOutput::Material(UniqueId::new_unchecked(100)), 1)]
+        );
+
+        let weighted = Rule {
+            outputs: vec![
+                (RuleOutput::Material(UniqueId::new_unchecked(100)), 9),
+                (RuleOutput::SwapDirection(Direction::South), 1),
+            ],
+            ..material_rule
+        };
+        let weighted_string = toml::to_string(&weighted).unwrap();
+        assert_eq!(toml::from_str::<Rule>(&weighted_string).unwrap(), weighted);
+        assert!(toml::from_str::<Rule>("input = \"10m\"\noutput = []\nconditions = []").is_err());
+    }
+
+    #[test]
+    fn shares_name() {
+        let mut first = Ruleset::new();
+        first.name = String::from("Sand");
+        let second = Ruleset::new();
+        let rulesets = vec![Ruleset::blank(), first.clone(), second];
+
+        assert!(!first.shares_name(&rulesets, 1));
+
+        let mut renamed = rulesets[2].clone();
+        renamed.name = String::from("Sand");
+        assert!(renamed.shares_name(&rulesets, 2));
+    }
+
+    #[test]
+    fn rule_title() {
+        let mut rule = Rule::new(&Ruleset::new());
+        assert_eq!(rule.title(0), "Rule 1");
+        rule.label = String::from("Gravity");
+        assert_eq!(rule.title(2), "Rule 3 'Gravity'");
+    }
+
+    #[test]
+    fn dangling_reference() {
+        let mut ruleset = Ruleset::new();
+        let material = Material::new(&ruleset);
+        let id = material.id();
+        ruleset.materials.push(material);
+        let mut group = MaterialGroup::new(&ruleset);
+        group.push(id);
+        ruleset.groups.push(group);
+        let mut rule = Rule::new(&ruleset);
+        rule.outputs = vec![(RuleOutput::Material(id), 1)];
+        ruleset.rules.push(rule);
+        assert!(ruleset.validate().is_empty());
+
+        ruleset.materials.remove(id);
+        assert_eq!(
+            ruleset.validate(),
+            vec![
+                ValidationIssue::Rule {
+                    rule: 0,
+                    title: "Rule 1".to_owned(),
+                    reference: Pattern::Material(id),
+                },
+                ValidationIssue::Group {
+                    group: 0,
+                    material: id,
+                },
+            ]
+        );
+
+        ruleset.fix_references();
+        assert!(ruleset.validate().is_empty());
+        assert_eq!(
+            ruleset.rules[0].outputs[0].0,
+            RuleOutput::Material(ruleset.materials.default().id())
+        );
+    }
+
+    #[test]
+    fn remove_referenced_material() {
+        let mut ruleset = Ruleset::new();
+        let material = Material::new(&ruleset);
+        let id = material.id();
+        ruleset.materials.push(material);
+        let mut group = MaterialGroup::new(&ruleset);
+        group.push(id);
+        ruleset.groups.push(group);
+        let mut rule = Rule::new(&ruleset);
+        rule.input = Pattern::Material(id);
+        let mut condition = Condition::new(&ruleset);
+        condition.pattern = Pattern::Material(id);
+        rule.conditions.push(condition);
+        ruleset.rules.push(rule);
+        ruleset.rules.push(Rule::new(&ruleset));
+
+        assert_eq!(
+            ruleset.references_to(id),
+            vec![String::from("Rule 1"), String::from("Group 'New Group'")]
+        );
+
+        ruleset.remove_material(id);
+        let default = Pattern::Material(ruleset.materials.default().id());
+        assert!(ruleset.materials.get(id).is_none());
+        assert!(ruleset.validate().is_empty());
+        assert!(ruleset.references_to(id).is_empty());
+        assert_eq!(ruleset.rules[0].input, default);
+        assert_eq!(ruleset.rules[0].conditions[0].pattern, default);
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn share_string() {
+        let mut ruleset = Ruleset::new();
+        ruleset.name = String::from("Shared");
+        ruleset.rules.push(Rule::new(&ruleset));
+        let code = ruleset.to_share_string().unwrap();
+
+        assert_eq!(Ruleset::from_share_string(&code).unwrap(), ruleset);
+        assert!(Ruleset::from_share_string("not a share code!").is_err());
+        assert!(Ruleset::from_share_string(&code[..code.len() / 2]).is_err());
+        assert!(Ruleset::from_share_string(&URL_SAFE_NO_PAD.encode("name = 1")).is_err());
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn load_missing_and_empty_directory() {
+        let directory = std::env::temp_dir().join(format!("rulesets-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        // A missing directory is created, and an empty one only holds the built-in ruleset.
+        assert_eq!(
+            Ruleset::load_from(&directory),
+            (vec![Ruleset::blank()], vec![])
+        );
+        assert!(directory.is_dir());
+        assert_eq!(
+            Ruleset::load_from(&directory),
+            (vec![Ruleset::blank()], vec![])
+        );
+
+        // Broken files are reported without losing the rest.
+        let ruleset = Ruleset::new();
+        fs::write(
+            directory.join("good.toml"),
+            toml::to_string(&ruleset).unwrap(),
+        )
+        .unwrap();
+        fs::write(directory.join("bad.toml"), "not a ruleset").unwrap();
+        let (rulesets, errors) = Ruleset::load_from(&directory);
+        assert_eq!(rulesets, vec![Ruleset::blank(), ruleset]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, directory.join("bad.toml"));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn restyle_default_material() {
+        let gray = MaterialColor::new(128, 128, 128);
+        let mut ruleset = RulesetTemplate::GameOfLife.build();
+        let default_id = ruleset.materials.default().id();
+        let alive = ruleset.materials.get_at(1).cloned();
+        ruleset.restyle_default_material(Some("Air"), Some(gray));
+        let default = ruleset.materials.default();
+        assert_eq!((default.name.as_str(), default.color), ("Air", gray));
+        assert_eq!(default.id(), default_id);
+        assert_eq!(ruleset.materials.get_at(1).cloned(), alive);
+
+        // Leaving either out keeps what the template had.
+        ruleset.restyle_default_material(None, Some(MaterialColor::DEFAULT));
+        assert_eq!(ruleset.materials.default().name, "Air");
+        assert_eq!(ruleset.materials.default().color, MaterialColor::DEFAULT);
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn position_by_name() {
+        let directory = std::env::temp_dir().join(format!("names-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+
+        // Two files holding rulesets of the same name always resolve to the one sorting first.
+        let mut sand = Ruleset::new();
+        sand.name = String::from("Sand");
+        let mut copy = sand.clone();
+        copy.default_size = Some(20);
+        fs::write(directory.join("b.toml"), toml::to_string(&copy).unwrap()).unwrap();
+        fs::write(directory.join("a.toml"), toml::to_string(&sand).unwrap()).unwrap();
+        let (rulesets, errors) = Ruleset::load_from(&directory);
+        assert!(errors.is_empty());
+        assert_eq!(rulesets, vec![Ruleset::blank(), sand, copy]);
+        assert_eq!(Ruleset::position_by_name(&rulesets, "Sand"), Some(1));
+        assert_eq!(Ruleset::position_by_name(&rulesets, "Blank"), Some(0));
+        assert_eq!(Ruleset::position_by_name(&rulesets, "Water"), None);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn atomic_save() {
+        let directory = std::env::temp_dir().join(format!("saves-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let mut ruleset = Ruleset::new();
+        ruleset.name = String::from("Saved");
+        ruleset.save_to(&directory, RulesetFormat::Toml).unwrap();
+        let path = ruleset.path_in(&directory, RulesetFormat::Toml);
+        let saved = fs::read_to_string(&path).unwrap();
+
+        // A write that stops halfway leaves the previous save untouched and cleans up after itself.
+        let interrupted = replace_file(&path, |file| {
+            file.write_all(&saved.as_bytes()[..saved.len() / 2])?;
+            Err(io::Error::new(io::ErrorKind::Interrupted, "crashed"))
+        });
+        assert!(interrupted.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), saved);
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+
+        for name in ["../Escaped", "a/b", "What?", "", ".."] {
+            ruleset.name = String::from(name);
+            assert!(
+                ruleset.save_to(&directory, RulesetFormat::Toml).is_err(),
+                "{name:?} was saved"
+            );
+        }
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn autosave() {
+        let directory = std::env::temp_dir().join(format!("autosaves-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let mut ruleset = Ruleset::new();
+        ruleset.name = String::from("Unsaved");
+        ruleset.autosave_to(&directory).unwrap();
+        assert!(Ruleset::is_autosave(&ruleset.autosave_path_in(&directory)));
+
+        // Autosaves aren't rulesets of their own, but one that was never saved is recovered.
+        assert_eq!(Ruleset::load_from(&directory).0.len(), 1);
+        assert_eq!(
+            Ruleset::load_autosaves_from(&directory),
+            (vec![ruleset.clone()], Vec::new())
+        );
+
+        // Saving the ruleset itself outdates the autosave.
+        ruleset.save_to(&directory, RulesetFormat::Toml).unwrap();
+        assert!(Ruleset::load_autosaves_from(&directory).0.is_empty());
+
+        // Autosaves that can't be read are reported rather than recovered.
+        let broken = directory.join("Broken.autosave.toml");
+        fs::write(&broken, "name = 5").unwrap();
+        assert_eq!(Ruleset::load_autosaves_from(&directory).1.len(), 1);
+        fs::remove_file(&broken).unwrap();
+        assert_eq!(Ruleset::load_from(&directory).0.len(), 2);
+
+        ruleset.remove_autosave_in(&directory).unwrap();
+        ruleset.remove_autosave_in(&directory).unwrap();
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+
+        // A ruleset can't be saved under a name its file would be mistaken for an autosave by.
+        ruleset.name = String::from("Unsaved.autosave");
+        assert!(ruleset.save_to(&directory, RulesetFormat::Json).is_err());
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn json_round_trip() {
+        let mut ruleset = Ruleset::new();
+        ruleset.name = String::from("Json");
+        let mut fire = Material::new(&ruleset);
+        fire.color = MaterialColor::new(255, 128, 0);
+        let fire_id = fire.id();
+        ruleset.materials.push(fire);
+        let mut group = MaterialGroup::new(&ruleset);
+        group.push(fire_id);
+        let group_id = group.id();
+        ruleset.groups.push(group);
+        let mut rule = Rule::new(&ruleset);
+        rule.input = Pattern::Group(group_id);
+        rule.outputs = vec![(RuleOutput::SwapDirection(Direction::ALL[0]), 1)];
+        let mut condition = Condition::new(&ruleset);
+        condition.pattern = Pattern::SelfSame;
+        rule.conditions.push(condition);
+        ruleset.rules.push(rule);
+        ruleset.conflict_policy = ConflictPolicy::Random(7);
+
+        let directory = std::env::temp_dir().join(format!("json-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        ruleset.save_to(&directory, RulesetFormat::Json).unwrap();
+        let path = ruleset.path_in(&directory, RulesetFormat::Json);
+        let text = fs::read_to_string(&path).unwrap();
+        // Patterns and colors keep their compact string forms.
+        assert!(text.contains(&format!("\"{group_id}g\"")));
+        assert!(text.contains("\"self\""));
+        assert!(text.contains("\"#FF8000\""));
+        assert_eq!(Ruleset::load_json(&path).unwrap(), ruleset);
+        assert_eq!(Ruleset::load_from(&directory).0[1], ruleset);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn describe() {
+        let mut life = Ruleset::game_of_life();
+        assert_eq!(
+            life.describe(),
+            "Game of Life\n\
+             Rule 1 'Birth': Each Dead cell that has exactly 3 Alive neighbors becomes Alive.\n\
+             Rule 2 'Death': Each Alive cell that does not have 2 or 3 Alive neighbors \
+             becomes Dead.\n"
+        );
+
+        let dead = life.rules[0].input;
+        let alive = life.rules[1].input;
+        let Pattern::Material(alive_id) = alive else {
+            panic!("the Death rule should apply to Alive cells");
+        };
+        let directional = Condition {
+            variant: ConditionVariant::Directional(vec![Direction::North, Direction::East]),
+            pattern: alive,
+            ..Condition::new(&life)
+        };
+        life.rules[0].conditions.push(directional);
+        life.rules[0].outputs = vec![
+            (RuleOutput::Material(alive_id), 3),
+            (RuleOutput::SwapDirection(Direction::South), 1),
+        ];
+        assert_eq!(
+            life.rules[0].describe(&life, 0),
+            "Rule 1 'Birth': Each Dead cell that has exactly 3 Alive neighbors and has at least \
+             one Alive neighbor to the North or the East becomes Alive (weight 3) or swaps with \
+             its South neighbor (weight 1)."
+        );
+
+        let generation = Condition {
+            variant: ConditionVariant::Generation {
+                modulus: 2,
+                remainder: 1,
+            },
+            inverted: true,
+            ..Condition::new(&life)
+        };
+        assert_eq!(
+            generation.describe(&life),
+            "is not on a generation that leaves 1 when divided by 2"
+        );
+        assert_eq!(dead.describe(&life), "Dead");
+        assert_eq!(Pattern::SelfSame.describe(&life), "same-material");
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn life_string() {
+        let life = Ruleset::from_life_string("b3/s32").unwrap();
+        assert_eq!(life.name, "B3/S23");
+        assert_eq!(life.rules, Ruleset::game_of_life().rules);
+
+        let high_life = Ruleset::from_life_string(" S23/B36 ").unwrap();
+        assert_eq!(high_life.name, "B36/S23");
+        assert_eq!(
+            high_life.rules[0].conditions[0].variant,
+            ConditionVariant::Count(Operator::List(vec![3, 6]))
+        );
+        assert!(Ruleset::from_life_string("B/S").is_ok());
+
+        for invalid in ["B39/S23", "B3", "X3/S23", "B3/S2/B4", "B3/S2a", ""] {
+            assert!(
+                Ruleset::from_life_string(invalid).is_err(),
+                "{invalid:?} was accepted"
+            );
+        }
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn grid_defaults() {
+        let mut ruleset = Ruleset::new();
+        let text = toml::to_string(&ruleset).unwrap();
+        assert!(!text.contains("default_size") && !text.contains("default_boundary"));
+        // Rulesets saved before these settings existed load without them.
+        assert_eq!(toml::from_str::<Ruleset>(&text).unwrap(), ruleset);
+
+        ruleset.default_size = Some(64);
+        ruleset.default_boundary = Some(Boundary::Wrap);
+        let text = toml::to_string(&ruleset).unwrap();
+        assert!(text.contains("default_boundary = \"Wrap\""));
+        assert_eq!(toml::from_str::<Ruleset>(&text).unwrap(), ruleset);
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn metadata() {
+        let mut ruleset = Ruleset::new();
+        let text = toml::to_string(&ruleset).unwrap();
+        assert!(!text.contains("metadata"));
+
+        ruleset.metadata.author = String::from("Ada");
+        *ruleset.metadata.get_mut(MetadataField::Notes) = String::from("Sand piles up.\nSlowly.");
+        let text = toml::to_string(&ruleset).unwrap();
+        assert!(text.contains("[metadata]") && !text.contains("description"));
+        let loaded = toml::from_str::<Ruleset>(&text).unwrap();
+        assert_eq!(loaded.metadata.get(MetadataField::Author), "Ada");
+        assert_eq!(loaded, ruleset);
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn merge() {
+        let mut ruleset = Ruleset::new();
+        let mut other = Ruleset::new();
+        let sand = Material::new(&other);
+        let sand_id = sand.id();
+        other.materials.push(sand);
+        let mut group = MaterialGroup::new(&other);
+        group.push(sand_id);
+        let group_id = group.id();
+        other.groups.push(group);
+        let mut rule = Rule::new(&other);
+        rule.input = Pattern::Group(group_id);
+        rule.outputs = vec![(RuleOutput::Material(sand_id), 1)];
+        other.rules.push(rule);
+
+        // Both rulesets start with a material of the same id.
+        ruleset.materials.push(Material::new(&ruleset));
+        assert!(ruleset.materials.get(sand_id).is_some());
+
+        ruleset.merge(other).unwrap();
+        assert!(ruleset.validate().is_empty());
+        assert_eq!(ruleset.materials.len(), 4);
+        let rule = &ruleset.rules[0];
+        let Pattern::Group(merged_group) = rule.input else {
+            panic!("Merged rule input should remain a group.");
+        };
+        let RuleOutput::Material(merged_sand) = rule.outputs[0].0 else {
+            panic!("Merged rule output should remain a material.");
+        };
+        assert_ne!(merged_sand, sand_id);
+        assert!(ruleset.group(merged_group).unwrap().contains(merged_sand));
+
+        let mut dangling = Ruleset::new();
+        dangling.rules.push(Rule {
+            label: String::new(),
+            input: Pattern::Material(UniqueId::new_unchecked(100)),
+            outputs: vec![(RuleOutput::Material(dangling.materials.default().id()), 1)],
+            conditions: vec![],
+        });
+        assert!(ruleset.clone().merge(dangling).is_err());
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn duplicate() {
+        let mut ruleset = Ruleset::new();
+        let sand = Material::new(&ruleset);
+        let sand_id = sand.id();
+        ruleset.materials.push(sand);
+        let mut group = MaterialGroup::new(&ruleset);
+        group.push(sand_id);
+        ruleset.groups.push(group);
+        let mut rule = Rule::new(&ruleset);
+        rule.input = Pattern::Group(ruleset.groups[0].id());
+        rule.outputs = vec![(RuleOutput::Material(sand_id), 1)];
+        ruleset.rules.push(rule);
+        ruleset.fallback = Some(sand_id);
+
+        let copy = ruleset.duplicate().unwrap();
+        assert_eq!(copy.name, format!("{} copy", ruleset.name));
+        assert!(copy.validate().is_empty());
+        assert_eq!(copy.materials.len(), ruleset.materials.len());
+        let copy_sand = copy.materials.get_at(1).unwrap().id();
+        assert_ne!(copy_sand, sand_id);
+        assert_eq!(copy.fallback, Some(copy_sand));
+        let Pattern::Group(copy_group) = copy.rules[0].input else {
+            panic!("Duplicated rule input should remain a group.");
+        };
+        assert!(copy.group(copy_group).unwrap().contains(copy_sand));
+        assert_eq!(copy.rules[0].outputs[0].0, RuleOutput::Material(copy_sand));
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn nested_groups() {
+        let mut ruleset = Ruleset::new();
+        let wood = Material::new(&ruleset);
+        let wood_id = wood.id();
+        ruleset.materials.push(wood);
+        let mut timber = MaterialGroup::new(&ruleset);
+        timber.push(wood_id);
+        let timber_id = timber.id();
+        ruleset.groups.push(timber);
+        let mut flammable = MaterialGroup::new(&ruleset);
+        flammable.toggle_subgroup(timber_id);
+        let flammable_id = flammable.id();
+        ruleset.groups.push(flammable);
+
+        // Membership carries through subgroups.
+        assert!(ruleset.group_contains(flammable_id, wood_id));
+        assert!(Pattern::Group(flammable_id).matches(
+            &ruleset,
+            Cell::new(wood_id),
+            Cell::new(wood_id)
+        ));
+        assert_eq!(ruleset.group_materials(flammable_id), [wood_id]);
+        assert!(ruleset.validate().is_empty());
+
+        // Subgroups survive serialization, and groups without any still load.
+        let text = toml::to_string(&ruleset).unwrap();
+        assert_eq!(toml::from_str::<Ruleset>(&text).unwrap(), ruleset);
+        let legacy: MaterialGroup =
+            toml::from_str("id = 4\nname = \"Old\"\nmaterials = [1]").unwrap();
+        assert!(legacy.subgroups().is_empty());
+
+        // A group can't contain itself, directly or indirectly.
+        assert!(ruleset.would_cycle(timber_id, flammable_id));
+        assert!(ruleset.would_cycle(timber_id, timber_id));
+        assert!(!ruleset.would_cycle(flammable_id, timber_id));
+        ruleset.groups[0].toggle_subgroup(flammable_id);
+        assert!(!ruleset.group_contains(timber_id, UniqueId::new_unchecked(999)));
+        assert_eq!(
+            ruleset.validate(),
+            [
+                ValidationIssue::Cycle { group: 0 },
+                ValidationIssue::Cycle { group: 1 }
+            ]
+        );
+        ruleset.fix_references();
+        assert!(ruleset.validate().is_empty());
+        assert!(ruleset.group_contains(flammable_id, wood_id));
+    }
+
+    #[test]
+    fn shadowed_rules() {
+        let mut ruleset = Ruleset::blank();
+        let wood = Material::new(&ruleset);
+        let wood_id = wood.id();
+        ruleset.materials.push(wood);
+        let mut timber = MaterialGroup::new(&ruleset);
+        timber.push(wood_id);
+        let timber_id = timber.id();
+        ruleset.groups.push(timber);
+
+        let mut burns = Rule::new(&ruleset);
+        burns.input = Pattern::Group(timber_id);
+        let mut smolders = Rule::new(&ruleset);
+        smolders.input = Pattern::Material(wood_id);
+        smolders.conditions.push(Condition::new(&ruleset));
+        ruleset.rules = vec![burns, smolders];
+        assert_eq!(ruleset.find_shadowed_rules(), [(1, 0)]);
+
+        // The later rule is more specific, so it wins when specificity decides.
+        ruleset.conflict_policy = ConflictPolicy::MostSpecific;
+        assert!(ruleset.find_shadowed_rules().is_empty());
+        ruleset.conflict_policy = ConflictPolicy::Random(0);
+        assert!(ruleset.find_shadowed_rules().is_empty());
+
+        // A swap can fail past an edge, leaving the cell to later rules.
+        ruleset.conflict_policy = ConflictPolicy::FirstMatch;
+        ruleset.rules[0].outputs = vec![(RuleOutput::SwapDirection(Direction::South), 1)];
+        assert!(ruleset.find_shadowed_rules().is_empty());
+        ruleset.rules[0].outputs = vec![(RuleOutput::Material(wood_id), 1)];
+        // An extra condition on the earlier rule lets the later one fire when it fails.
+        let mut generation = Condition::new(&ruleset);
+        generation.variant = ConditionVariant::Generation {
+            modulus: 2,
+            remainder: 0,
+        };
+        ruleset.rules[0].conditions.push(generation);
+        assert!(ruleset.find_shadowed_rules().is_empty());
+    }
+}
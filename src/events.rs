@@ -1,36 +1,134 @@
-use vizia::input::MouseButton;
+use std::time::Duration;
+
+use vizia::{input::MouseButton, style::Color};
 
 use crate::{
     condition::{ConditionIndex, ConditionVariant, Direction},
-    display::EditorTab,
-    material::MaterialId,
-    ruleset::RuleIndex,
+    display::{EditorTab, PaintTool, SymmetryMode},
+    grid::{Boundary, GridRect},
+    material::{ColorChannel, GroupId, MaterialId},
+    preset::Preset,
+    ruleset::{MetadataField, RuleIndex},
 };
 
 type Index = usize;
 type HexColor = String;
 
+/// Something in the editor whose delete button needs a second press to go through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeletionTarget {
+    Material(MaterialId),
+    Group(Index),
+    Rule(RuleIndex),
+    Condition(ConditionIndex),
+}
+
 pub enum UpdateEvent {
     WindowSizeChanged,
-    CellHovered { x: usize, y: usize },
+    CellHovered {
+        x: usize,
+        y: usize,
+    },
     CellUnhovered,
     CellClicked(MouseButton),
     MaterialSelected(MaterialId),
+    /// Chooses the material right-clicking paints.
+    SecondaryMaterialSelected(MaterialId),
+    ToolSelected(PaintTool),
+    SymmetrySelected(SymmetryMode),
+    PresetSelected(Preset),
+    PresetRotated,
+    PresetFlipped,
+    StatusPosted(String, Color),
+    StatusCleared,
+    InspectorToggled,
+    /// Shows or hides how many cells each rule fired on last generation.
+    RuleCountsToggled,
+    /// Sets how much darker the corners of displayed cells are than their centers.
+    CellGradientSet(u8),
+    /// Turns stretching the grid to fill its panel on or off.
+    GridFillToggled,
+    /// Returns an armed delete button to normal once its second press is too late.
+    DeletionDisarmed,
+    /// Writes the open ruleset's autosave if it has changed since the last one.
+    AutosaveDue,
+}
+impl UpdateEvent {
+    pub fn success(message: impl Into<String>) -> Self {
+        Self::StatusPosted(message.into(), Color::rgb(46, 125, 50))
+    }
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::StatusPosted(message.into(), Color::rgb(183, 28, 28))
+    }
 }
 
 pub enum RulesetEvent {
+    /// Opens the ruleset at the index, first asking whether to save the open one's edits.
     Selected(Index),
+    /// Saves the open ruleset and then opens the one awaiting `pending_switch`.
+    SwitchSaved,
+    /// Drops the open ruleset's edits and opens the one awaiting `pending_switch`.
+    SwitchDiscarded,
+    SwitchCancelled,
     Saved,
+    OverwriteConfirmed,
+    OverwriteCancelled,
+    /// Selects the file format rulesets are saved in.
+    FormatSelected(Index),
     Created,
+    /// Adds a copy of the ruleset at the index, with fresh material and group ids, and selects it.
+    Duplicated(Index),
+    /// Selects what the New button starts rulesets from.
+    TemplateSelected(Index),
+    /// Names the default material of rulesets the New button creates; blank keeps the template's.
+    NewDefaultNamed(String),
+    /// Colors the default material of rulesets the New button creates, from hex; blank keeps the
+    /// template's.
+    NewDefaultRecolored(HexColor),
     Renamed(String),
+    ConflictPolicySelected(Index),
+    ConflictSeedSet(u32),
+    /// Sets the grid size the ruleset starts with, or lets it keep the current size.
+    DefaultSizeSet(Option<usize>),
+    /// Selects the ruleset's default boundary, where the first index keeps the current one.
+    DefaultBoundarySelected(Index),
+    /// Selects the material cells become when no rule matches, where the first index is none.
+    FallbackSelected(Index),
+    MetadataEdited(MetadataField, String),
     Reloaded,
+    /// Reloads the rulesets after their files changed while watched, only reporting failures.
+    FilesChanged,
+    WatchToggled,
+    /// Turns periodically saving edits to a separate autosave file on or off.
+    AutosaveToggled,
+    /// Selects the first ruleset in `pending_recoveries` and opens its autosave in its place.
+    RecoveryAccepted,
+    /// Deletes the autosave of the first ruleset in `pending_recoveries`.
+    RecoveryDismissed,
+    ReferencesFixed,
+    ShareCodeCopied,
+    ShareCodeImported,
+    /// Writes the ruleset's rules out in prose to a text file.
+    Described,
+    /// Creates a ruleset from a Life-like rulestring such as `B3/S23`.
+    LifeRuleImported(String),
+    ShareCodeMerged,
 }
 
 pub enum MaterialEvent {
     Created,
     Renamed(Index, String),
     Recolored(Index, HexColor),
+    ChannelSet(Index, ColorChannel, u8),
+    PickerToggled(Index),
+    LockToggled(Index),
+    ImmovableToggled(Index),
+    /// Moves the material at the first index to the second.
+    Moved(Index, Index),
+    PaletteGenerated,
     Deleted(MaterialId),
+    DeletionConfirmed,
+    DeletionCancelled,
 }
 
 pub enum GroupEvent {
@@ -42,18 +140,31 @@ pub enum GroupEvent {
         new_material_index: Index,
     },
     Renamed(Index, String),
+    MembershipToggled {
+        group_index: Index,
+        material_id: MaterialId,
+    },
     EntryDeleted {
         group_index: Index,
         entry_index: Index,
     },
     EntryAdded(Index),
+    SubgroupToggled {
+        group_index: Index,
+        subgroup: GroupId,
+    },
 }
 
 pub enum RuleEvent {
     Created,
     Deleted(RuleIndex),
     Copied(RuleIndex),
-    OutputSet(RuleIndex, Index),
+    Renamed(RuleIndex, String),
+    /// Sets the output at the second index to the one at the third in the output list.
+    OutputSet(RuleIndex, Index, Index),
+    OutputAdded(RuleIndex),
+    OutputRemoved(RuleIndex, Index),
+    OutputWeightSet(RuleIndex, Index, u32),
     InputSet(RuleIndex, Index),
 }
 pub enum ConditionEvent {
@@ -66,18 +177,93 @@ pub enum ConditionEvent {
     VariantChanged(ConditionIndex, ConditionVariant),
     OperatorChanged(ConditionIndex),
     Inverted(ConditionIndex),
+    NeighborhoodToggled(ConditionIndex),
+    DirectionalModeToggled(ConditionIndex),
+    RadiusSet(ConditionIndex, usize),
+    ModulusSet(ConditionIndex, usize),
+    RemainderSet(ConditionIndex, usize),
+    ComparisonToggled(ConditionIndex),
+    ComparedPatternSet(ConditionIndex, Index),
+    AlternativeAdded(ConditionIndex),
+    /// Sets the alternative at the second index to the pattern at the third.
+    AlternativeSet(ConditionIndex, Index, Index),
+    AlternativeRemoved(ConditionIndex, Index),
+    OffsetAdded(ConditionIndex),
+    /// Parses the text into the offset at the second index.
+    OffsetSet(ConditionIndex, Index, String),
+    OffsetRemoved(ConditionIndex, Index),
 }
 pub enum GridEvent {
     Stepped,
+    /// The run's timer fired, this long after it was due.
+    Ticked(Duration),
     Toggled,
+    /// Sets the speed in generations per second.
     SpeedSet(f32),
+    /// Turns blending between generations on or off.
+    InterpolationToggled,
+    /// Turns pausing while the window is unfocused on or off.
+    FocusPauseToggled,
+    /// Fills the grid with the default material.
+    Cleared,
+    /// Shows the generation at this position in the timeline, pausing the run.
+    TimelineScrubbed(usize),
+    /// Stamps the selected preset onto the center of the grid.
+    PresetPlaced,
     Resized(usize),
+    BoundarySelected(Boundary),
     StateSaved,
     StateLoaded,
+    /// Switches between loading states at their own size and clipping them to the grid's.
+    StateClipToggled,
+    StateFileNamed(String),
+    StateWritten,
+    StateRead,
+    CsvExported,
+    CsvImported,
+    /// Chooses the ruleset, by index, that comparisons run the grid under.
+    ComparedRulesetSelected(usize),
+    /// Sets how many generations comparisons run for.
+    ComparisonLengthSet(usize),
+    /// Runs the grid under its own ruleset and the compared one side by side, exporting how many
+    /// cells differ after each generation as CSV.
+    RulesetsCompared,
+    PeriodReset,
+    HeatmapToggled,
+    ActivityReset,
+    /// Sets the percentage of each cell's activity kept per generation.
+    ActivityDecaySet(u8),
+    PlotToggled(MaterialId),
+    PopulationCleared,
+    ImagePathSet(String),
+    ImageImported,
+    ImageApplied,
+    ImageDiscarded,
+    /// Fills a region with every material in equal proportion, drawn from the app's seeded
+    /// random number generator.
+    RandomizedRegion(GridRect),
+    /// Advances only the cells in a region a generation, leaving the rest frozen.
+    RegionStepped(GridRect),
+    /// Restarts every random choice from this seed.
+    SeedSet(u64),
+    /// Restarts every random choice from a fresh random seed.
+    SeedRerolled,
+    /// Turns recording the seed in saved state files on or off.
+    SeedRecordingToggled,
+    FlippedHorizontal,
+    FlippedVertical,
+    RotatedClockwise,
+    RotatedCounterclockwise,
+    /// Copies the selected cells to the clipboard.
+    SelectionCopied,
+    /// Pastes the clipboard with its top left corner on the hovered cell.
+    ClipboardPasted,
 }
 
 pub enum EditorEvent {
     Enabled,
     Disabled,
     TabSwitched(EditorTab),
+    /// Shows or hides the row of fields for the ruleset's metadata.
+    MetadataToggled,
 }
@@ -0,0 +1,129 @@
+use vizia::{
+    binding::{Data, Lens},
+    context::{Context, DrawContext},
+    vg,
+    view::{Handle, View},
+};
+
+use crate::{
+    grid::Grid,
+    id::Identifiable,
+    material::{MaterialColor, MaterialId},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PopulationSeries {
+    pub material: MaterialId,
+    pub color: MaterialColor,
+    /// Population of each recorded generation, oldest first.
+    pub counts: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PopulationPlot {
+    /// The most generations a series can hold; the x axis always spans this many.
+    pub capacity: usize,
+    pub series: Vec<PopulationSeries>,
+}
+impl PopulationPlot {
+    pub fn new(grid: &Grid) -> Self {
+        let series = grid
+            .ruleset
+            .materials
+            .iter()
+            .enumerate()
+            .map(|(index, material)| PopulationSeries {
+                material: material.id(),
+                color: material.color,
+                counts: grid
+                    .population()
+                    .iter()
+                    .map(|counts| counts[index])
+                    .collect(),
+            })
+            .collect();
+        Self {
+            capacity: Grid::POPULATION_HISTORY,
+            series,
+        }
+    }
+}
+impl Data for PopulationPlot {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// A line chart of each material's population over recent generations.
+pub struct PopulationGraph<L1, L2>
+where
+    L1: Lens<Target = PopulationPlot>,
+    L2: Lens<Target = Vec<MaterialId>>,
+{
+    plot: L1,
+    hidden: L2,
+}
+impl<L1, L2> PopulationGraph<L1, L2>
+where
+    L1: Lens<Target = PopulationPlot>,
+    L2: Lens<Target = Vec<MaterialId>>,
+{
+    const LINE_WIDTH: f32 = 2.0;
+
+    pub fn new(cx: &mut Context, plot: L1, hidden: L2) -> Handle<Self> {
+        Self { plot, hidden }
+            .build(cx, move |_| {})
+            .bind(plot, |mut cx, _| cx.needs_redraw())
+            .bind(hidden, |mut cx, _| cx.needs_redraw())
+    }
+}
+impl<L1, L2> View for PopulationGraph<L1, L2>
+where
+    L1: Lens<Target = PopulationPlot>,
+    L2: Lens<Target = Vec<MaterialId>>,
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn draw(&self, cx: &mut DrawContext, canvas: &vg::Canvas) {
+        let bounds = cx.bounds();
+        let mut background = vg::Paint::default();
+        background.set_color(cx.background_color());
+        canvas.draw_rect(
+            vg::Rect::from_xywh(bounds.x, bounds.y, bounds.w, bounds.h),
+            &background,
+        );
+
+        let plot = self.plot.get(cx);
+        let hidden = self.hidden.get(cx);
+        let visible: Vec<&PopulationSeries> = plot
+            .series
+            .iter()
+            .filter(|series| !hidden.contains(&series.material))
+            .collect();
+        let max = visible
+            .iter()
+            .flat_map(|series| series.counts.iter().copied())
+            .max()
+            .unwrap_or(0)
+            .max(1) as f32;
+        let step = bounds.width() / plot.capacity.saturating_sub(1).max(1) as f32;
+
+        for series in visible {
+            let mut path = vg::Path::new();
+            for (generation, &count) in series.counts.iter().enumerate() {
+                let x = (generation as f32).mul_add(step, bounds.left());
+                let y = bounds.bottom() - bounds.height() * count as f32 / max;
+                if generation == 0 {
+                    path.move_to((x, y));
+                } else {
+                    path.line_to((x, y));
+                }
+            }
+            let mut paint = vg::Paint::default();
+            paint.set_color(series.color);
+            paint.set_style(vg::PaintStyle::Stroke);
+            paint.set_stroke_width(Self::LINE_WIDTH);
+            paint.set_anti_alias(true);
+            canvas.draw_path(&path, &paint);
+        }
+    }
+}
@@ -1,53 +1,214 @@
 #![allow(clippy::expl_impl_clone_on_copy)]
 
-use condition::{Condition, ConditionVariant, Operator};
-use display::Screen;
+use std::{collections::VecDeque, fs, path::PathBuf, time::Instant};
+
+use condition::{Comparison, Condition, ConditionVariant, Operator};
+use display::{PaintTool, Screen, SymmetryMode};
 use events::{
-    ConditionEvent, EditorEvent, GridEvent, GroupEvent, MaterialEvent, RuleEvent, RulesetEvent,
-    UpdateEvent,
+    ConditionEvent, DeletionTarget, EditorEvent, GridEvent, GroupEvent, MaterialEvent, RuleEvent,
+    RulesetEvent, UpdateEvent,
 };
-use grid::{Cell, FunctionalGridState, Grid};
+use grid::{Boundary, Cell, FunctionalGridState, Grid, GridBackend, GridRect, GridRegion};
 use id::Identifiable;
-use material::{Material, MaterialColor, MaterialGroup, MaterialId};
+use material::{Material, MaterialColor, MaterialGroup, MaterialId, MaterialMap};
 use pattern::Pattern;
-use ruleset::{Rule, Ruleset};
+use preset::{Orientation, Preset};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use ruleset::{ConflictPolicy, Rule, RuleOutput, Ruleset, RulesetFormat, RulesetTemplate};
+use settings::Settings;
 use vizia::prelude::*;
 
-mod condition;
+use engine::{condition, grid, id, material, pattern, preset, ruleset};
+
 mod display;
+mod engine;
 mod events;
-mod grid;
-mod id;
-mod material;
-mod pattern;
-mod ruleset;
+mod graph;
+mod settings;
+mod watcher;
 
 const INITIAL_WINDOW_SIZE: (u32, u32) = (1920 / 2, 1080 / 2);
+/// The slowest and fastest selectable speeds, in generations per second.
+const MIN_SPEED: f32 = 0.1;
+const MAX_SPEED: f32 = 60.0;
+/// Above this speed, in generations per second, blending between generations is too quick to see.
+const MAX_INTERPOLATED_SPEED: f32 = 20.0;
+/// How strongly each new step affects the displayed step rate; lower values smooth more.
+const STEP_RATE_SMOOTHING: f32 = 0.2;
+/// How long a status message stays on screen.
+const STATUS_DURATION: Duration = Duration::from_secs(4);
+/// How long an armed delete button waits for the press that confirms it.
+const DELETION_WINDOW: Duration = Duration::from_secs(3);
+/// How often the open ruleset is autosaved while it has unsaved edits.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// How many edits to the open ruleset trigger an autosave without waiting for the interval.
+const AUTOSAVE_EDITS: usize = 20;
+/// Keys that select the material at their position in the palette.
+const MATERIAL_KEYS: [Code; 9] = [
+    Code::Digit1,
+    Code::Digit2,
+    Code::Digit3,
+    Code::Digit4,
+    Code::Digit5,
+    Code::Digit6,
+    Code::Digit7,
+    Code::Digit8,
+    Code::Digit9,
+];
 
 #[derive(Debug, Lens)]
 pub struct AppData {
+    /// The window's bounds in logical pixels.
     window_size: BoundingBox,
+    /// The settings as last saved, to tell when they need saving again.
+    settings: Settings,
 
     rulesets: Vec<Ruleset>,
+    /// Whether each ruleset in the list was loaded from the rulesets directory or saved there
+    /// since, so reloading replaces it with what's on disk. The others, like new or imported
+    /// rulesets, only exist in the list.
+    rulesets_on_disk: Vec<bool>,
     screen: Screen,
     selected_ruleset: usize,
+    /// Reloads rulesets when their files change on disk, while present.
+    ruleset_watcher: Option<notify::RecommendedWatcher>,
     selected_material: MaterialId,
+    /// The material right-clicking paints, which is the default material unless another is chosen.
+    /// Materials missing from the ruleset fall back to the default.
+    secondary_material: MaterialId,
+    paint_tool: PaintTool,
+    /// Which mirror images of each painted cell are painted too.
+    symmetry: SymmetryMode,
+    selected_preset: Preset,
+    preset_orientation: Orientation,
     running: bool,
+    /// Whether running pauses while the window is unfocused, resuming once it's refocused.
+    pause_unfocused: bool,
+    /// Whether running was paused by the window losing focus, rather than by the user.
+    paused_unfocused: bool,
+    /// Requested generations per second.
     speed: f32,
+    /// Smoothed generations per second actually achieved while running.
+    step_rate: f32,
+    /// Whether the last step took longer than the requested speed allows.
+    step_limited: bool,
+    /// Whether cell colors blend from one generation to the next while running.
+    interpolation: bool,
+    /// How long the grid display blends each generation in over, in seconds; zero to snap.
+    transition: f32,
+    last_step: Option<Instant>,
     timer: Timer,
+    status_timer: Timer,
+    /// Disarms `armed_deletion` once it runs out.
+    deletion_timer: Timer,
+    /// Autosaves the open ruleset every `AUTOSAVE_INTERVAL` while autosaving is enabled.
+    autosave_timer: Timer,
     grid_size: usize,
+    /// The boundary new grids get when their ruleset doesn't choose one.
+    boundary: Boundary,
+    /// Seeds every random choice, from randomized regions to the rules themselves.
+    /// The same seed, ruleset and starting cells always play out the same way.
+    seed: u64,
+    /// Draws random values for app commands, restarted whenever `seed` changes.
+    random: StdRng,
+    /// Whether state files record `seed`, so loading them restores it.
+    record_seed: bool,
+    /// Whether loaded states are clipped or padded to the current grid size instead of
+    /// resizing the grid to match them.
+    clip_loaded_states: bool,
     saved_state: Option<FunctionalGridState>,
+    /// The most recent generations, oldest first, for scrubbing back through them.
+    timeline: VecDeque<FunctionalGridState>,
+    /// The position in `timeline` of the generation shown; the last one unless scrubbed back.
+    timeline_position: usize,
+    state_file_name: String,
+    image_path: String,
+    /// The grid as it was before an image was imported, kept until the import is applied.
+    image_backup: Option<FunctionalGridState>,
+    /// Materials left out of the population graph.
+    hidden_plots: Vec<MaterialId>,
+    /// The index of the ruleset the grid's run is compared against.
+    compared_ruleset: usize,
+    /// How many generations a comparison runs for.
+    comparison_length: usize,
 
+    /// Describes the hovered cell: its material, coordinates, and index.
     tooltip: String,
+    /// A recent success or error, shown in a banner until `status_timer` runs out.
+    status_message: Option<(String, Color)>,
     hovered_index: Option<usize>,
+    /// Whether the grid is tinted by how active each cell has been recently.
+    heatmap_enabled: bool,
+    /// Whether the right panel explains which rules match the hovered cell.
+    inspector_enabled: bool,
+    /// Whether the right panel lists how many cells each rule fired on last generation.
+    rule_counts_enabled: bool,
+    /// How much darker the corners of displayed cells are than their centers; 0 for flat cells.
+    cell_gradient: u8,
+    /// Whether the grid stretches to fill the center panel rather than keeping square cells.
+    fill_panel: bool,
+    /// The last cell painted in the current stroke, cleared when the mouse is released.
+    last_painted_index: Option<usize>,
+    /// The cells region commands act on, if any have been selected.
+    selection: Option<GridRect>,
+    /// The cell the current selection drag started from, cleared when the mouse is released.
+    selection_anchor: Option<usize>,
+    /// The most recently copied cells, ready to paste.
+    clipboard: Option<GridRegion>,
     selected_tab: display::EditorTab,
+    /// Whether the editor shows the row of fields for the ruleset's metadata.
+    metadata_shown: bool,
     group_material_index: usize,
+    /// The most recent Life-like rulestring entered, such as `B3/S23`.
+    life_rule: String,
+    /// What the New button starts rulesets from.
+    template: RulesetTemplate,
+    /// The name and color the New button gives the default material, or `None` to keep the
+    /// template's.
+    new_default_name: Option<String>,
+    new_default_color: Option<MaterialColor>,
+    /// The format the Save button writes rulesets in.
+    ruleset_format: RulesetFormat,
+    /// The name of a ruleset file that saving would overwrite, awaiting confirmation.
+    pending_overwrite: Option<String>,
+    /// Whether the open ruleset has been edited since it was last saved or selected.
+    dirty: bool,
+    /// The index of a ruleset selected while the open one had unsaved edits, awaiting a choice
+    /// between saving and discarding them.
+    pending_switch: Option<usize>,
+    /// The index of a ruleset to switch to once the open one's save is confirmed, when saving it
+    /// before the switch would overwrite another ruleset's file.
+    switch_after_overwrite: Option<usize>,
+    /// Whether edits to the open ruleset are periodically written to its autosave file.
+    autosave_enabled: bool,
+    /// How many edits the open ruleset has had since it was last saved or autosaved.
+    unsaved_edits: usize,
+    /// Autosaves found on launch that are newer than their rulesets, awaiting recovery.
+    pending_recoveries: Vec<Ruleset>,
+    /// A referenced material awaiting confirmation before deletion, with a description of its uses.
+    pending_deletion: Option<(MaterialId, String)>,
+    /// The delete button pressed once, which deletes on a second press before `deletion_timer`
+    /// runs out or anything else in the editor changes.
+    armed_deletion: Option<DeletionTarget>,
+    /// The index of the material whose color picker is open.
+    color_picker: Option<usize>,
+    /// The most recent invalid color entered for a material, and why it was rejected.
+    color_error: Option<(usize, String)>,
 
     editor_enabled: bool,
 }
 #[allow(clippy::cast_precision_loss)]
 impl AppData {
-    fn new(timer: Timer) -> Self {
+    /// How many generations the timeline keeps.
+    const TIMELINE_LENGTH: usize = 100;
+
+    fn new(
+        timer: Timer,
+        status_timer: Timer,
+        deletion_timer: Timer,
+        autosave_timer: Timer,
+        settings: Settings,
+    ) -> Self {
         let mut ruleset = Ruleset::blank();
         let mut second_material = Material::new(&ruleset);
         second_material.color = MaterialColor::new(255, 0, 0);
@@ -61,35 +222,353 @@ impl AppData {
         r2m2.name = String::from("Green");
         ruleset_2.materials.push(r2m2);
 
+        let (rulesets, mut errors) = Ruleset::load_all();
+        let (pending_recoveries, autosave_errors) = Ruleset::load_autosaves();
+        errors.extend(autosave_errors);
+        let status_message =
+            load_error_message(&errors).map(|message| (message, Color::rgb(183, 28, 28)));
+        let selected_ruleset = settings
+            .ruleset
+            .as_ref()
+            .and_then(|name| Ruleset::position_by_name(&rulesets, name));
+        let ruleset = selected_ruleset.map_or(ruleset, |index| rulesets[index].clone());
+
         let material = ruleset.materials.default().id();
-        let grid = Grid::new(ruleset.clone(), 5);
+        let seed = rand::random();
+        let mut grid = Grid::new(ruleset, settings.grid_size);
+        grid.set_boundary(settings.boundary);
+        grid.set_seed(seed);
+        let grid_size = grid.size;
         Self {
             window_size: BoundingBox {
                 x: 0.,
                 y: 0.,
-                w: INITIAL_WINDOW_SIZE.0 as f32,
-                h: INITIAL_WINDOW_SIZE.1 as f32,
+                w: settings.window_size.0 as f32,
+                h: settings.window_size.1 as f32,
             },
 
-            rulesets: Ruleset::load_all().unwrap_or_else(|err| {
-                println!("Failed to load rulesets; falling back: {err}");
-                vec![ruleset]
-            }),
-            selected_ruleset: 0,
+            rulesets_on_disk: vec![true; rulesets.len()],
+            rulesets,
+            selected_ruleset: selected_ruleset.unwrap_or(0),
+            ruleset_watcher: None,
             screen: Screen::Grid(grid),
             selected_material: material,
+            secondary_material: material,
+            paint_tool: PaintTool::Brush,
+            symmetry: SymmetryMode::default(),
+            selected_preset: Preset::Glider,
+            preset_orientation: Orientation::default(),
             running: false,
-            speed: 1.0,
+            pause_unfocused: settings.pause_unfocused,
+            paused_unfocused: false,
+            speed: settings.speed.clamp(MIN_SPEED, MAX_SPEED),
+            step_rate: 0.0,
+            step_limited: false,
+            interpolation: settings.interpolation,
+            transition: 0.0,
+            last_step: None,
             timer,
-            grid_size: 5,
+            status_timer,
+            deletion_timer,
+            autosave_timer,
+            grid_size,
+            boundary: settings.boundary,
+            seed,
+            random: StdRng::seed_from_u64(seed),
+            record_seed: false,
+            clip_loaded_states: false,
             saved_state: None,
+            timeline: VecDeque::new(),
+            timeline_position: 0,
+            state_file_name: String::from("state"),
+            image_path: String::new(),
+            image_backup: None,
+            hidden_plots: Vec::new(),
+            compared_ruleset: 0,
+            comparison_length: 100,
 
             tooltip: String::new(),
+            status_message,
             hovered_index: None,
+            heatmap_enabled: settings.heatmap,
+            inspector_enabled: false,
+            rule_counts_enabled: false,
+            cell_gradient: settings.cell_gradient,
+            fill_panel: settings.fill_panel,
+            last_painted_index: None,
+            selection: None,
+            selection_anchor: None,
+            clipboard: None,
             selected_tab: display::EditorTab::Materials,
+            metadata_shown: false,
             group_material_index: 0,
+            life_rule: String::from("B3/S23"),
+            template: RulesetTemplate::default(),
+            new_default_name: None,
+            new_default_color: None,
+            ruleset_format: RulesetFormat::default(),
+            pending_overwrite: None,
+            dirty: false,
+            pending_switch: None,
+            switch_after_overwrite: None,
+            autosave_enabled: settings.autosave,
+            unsaved_edits: 0,
+            pending_recoveries,
+            pending_deletion: None,
+            armed_deletion: None,
+            color_picker: None,
+            color_error: None,
 
             editor_enabled: false,
+            settings,
+        }
+    }
+}
+
+impl AppData {
+    /// The settings to keep for the next session.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn settings(&self) -> Settings {
+        Settings {
+            window_size: (self.window_size.w as u32, self.window_size.h as u32),
+            grid_size: self.grid_size,
+            speed: self.speed,
+            boundary: self.boundary,
+            interpolation: self.interpolation,
+            pause_unfocused: self.pause_unfocused,
+            heatmap: self.heatmap_enabled,
+            cell_gradient: self.cell_gradient,
+            fill_panel: self.fill_panel,
+            autosave: self.autosave_enabled,
+            ruleset: self
+                .rulesets
+                .get(self.selected_ruleset)
+                .map(|ruleset| ruleset.name.clone()),
+        }
+    }
+    /// Saves the settings if any have changed since they were last saved.
+    fn save_settings(&mut self) {
+        let settings = self.settings();
+        if settings == self.settings {
+            return;
+        }
+        if let Err(err) = settings.save() {
+            println!("{err}");
+        }
+        self.settings = settings;
+    }
+
+    fn record_step(&mut self, now: Instant) {
+        if let Some(last_step) = self.last_step {
+            let elapsed = now.duration_since(last_step).as_secs_f32();
+            if elapsed > 0.0 {
+                let rate = elapsed.recip();
+                self.step_rate = if self.step_rate <= 0.0 {
+                    rate
+                } else {
+                    STEP_RATE_SMOOTHING.mul_add(rate - self.step_rate, self.step_rate)
+                };
+            }
+        }
+        self.last_step = Some(now);
+    }
+
+    /// Starts or stops stepping the grid on the timer.
+    fn set_running(&mut self, cx: &mut EventContext, running: bool) {
+        self.running = running;
+        self.last_step = None;
+        self.step_rate = 0.0;
+        self.step_limited = false;
+        if running {
+            cx.start_timer(self.timer);
+        } else {
+            cx.stop_timer(self.timer);
+        }
+        self.update_transition();
+    }
+
+    fn update_transition(&mut self) {
+        self.transition =
+            if self.interpolation && self.running && self.speed <= MAX_INTERPOLATED_SPEED {
+                self.speed.recip()
+            } else {
+                0.0
+            };
+    }
+
+    /// Creates an empty grid for `ruleset`, switching to its default size and boundary if it
+    /// has them and keeping the current ones otherwise.
+    fn new_grid(&mut self, ruleset: Ruleset) -> Grid {
+        self.grid_size = ruleset.default_size.unwrap_or(self.grid_size);
+        self.boundary = ruleset.default_boundary.unwrap_or(self.boundary);
+        let mut grid = Grid::new(ruleset, self.grid_size);
+        self.grid_size = grid.size;
+        grid.set_boundary(self.boundary);
+        grid.set_seed(self.seed);
+        self.clear_timeline();
+        grid
+    }
+
+    fn clear_timeline(&mut self) {
+        self.timeline.clear();
+        self.timeline_position = 0;
+    }
+    /// Advances the grid a generation, recording it in the timeline. Stepping from a generation
+    /// scrubbed back to drops the ones after it.
+    fn step(&mut self) {
+        self.advance(GridBackend::next_generation);
+    }
+    /// Advances the grid with `step`, recording the result in the timeline like [`Self::step`].
+    fn advance(&mut self, step: impl FnOnce(&mut Grid)) {
+        let Screen::Grid(ref mut grid) = self.screen else {
+            return;
+        };
+        // The shown generation may have been painted on since it was recorded.
+        self.timeline.truncate(self.timeline_position);
+        self.timeline.push_back(grid.functional_state());
+        step(grid);
+        self.timeline.push_back(grid.functional_state());
+        while self.timeline.len() > Self::TIMELINE_LENGTH {
+            self.timeline.pop_front();
+        }
+        self.timeline_position = self.timeline.len() - 1;
+    }
+
+    /// Restarts every random choice, in the app and the current grid, from `seed`.
+    fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.random = StdRng::seed_from_u64(seed);
+        if let Screen::Grid(ref mut grid) = self.screen {
+            grid.set_seed(seed);
+        }
+    }
+
+    /// Loads `state` into the current grid, at its own size or clipped to the grid's depending on
+    /// `clip_loaded_states`, keeping `grid_size` in step either way. Reports any cells whose
+    /// materials the ruleset doesn't have.
+    fn load_state(&mut self, cx: &mut EventContext, state: FunctionalGridState) {
+        if let Screen::Grid(ref mut grid) = self.screen {
+            let missing = if self.clip_loaded_states {
+                grid.load_state_clipped(state)
+            } else {
+                grid.load_state(state)
+            };
+            self.grid_size = grid.size;
+            report_missing_materials(cx, missing, "loaded", &grid.ruleset);
+        }
+    }
+
+    /// Opens the ruleset at `index` in the list, dropping any unsaved edits to the open one
+    /// except for what autosaving keeps of them.
+    fn select_ruleset(&mut self, cx: &mut EventContext, index: usize) {
+        self.autosave(cx);
+        self.selected_ruleset = index;
+        self.pending_overwrite = None;
+        self.switch_after_overwrite = None;
+        self.pending_deletion = None;
+        self.dirty = false;
+        self.open_ruleset(self.rulesets[index].clone());
+    }
+
+    /// Adds a ruleset that has no file yet to the end of the list, returning its index.
+    fn add_ruleset(&mut self, ruleset: Ruleset) -> usize {
+        self.rulesets.push(ruleset);
+        self.rulesets_on_disk.push(false);
+        self.rulesets.len() - 1
+    }
+
+    /// Replaces the rulesets that came from disk with `loaded`, keeping the ones that only exist
+    /// in the list after them. If the open ruleset has no edits, it's replaced with its reloaded
+    /// copy so changes made to its file elsewhere take effect.
+    fn reload_rulesets(&mut self, mut loaded: Vec<Ruleset>) {
+        let loaded_len = loaded.len();
+        let mut on_disk = vec![true; loaded_len];
+        // Where each old list entry ended up, to keep indices into the list pointing at it.
+        let mut positions = Vec::with_capacity(self.rulesets.len());
+        let entries = self.rulesets.drain(..).zip(self.rulesets_on_disk.drain(..));
+        for (index, (ruleset, was_on_disk)) in entries.enumerate() {
+            let position = was_on_disk
+                .then(|| Ruleset::position_by_name(&loaded[..loaded_len], &ruleset.name))
+                .flatten();
+            // A file deleted elsewhere drops its ruleset, unless that ruleset is open.
+            if position.is_some() || (was_on_disk && index != self.selected_ruleset) {
+                positions.push(position);
+                continue;
+            }
+            loaded.push(ruleset);
+            on_disk.push(false);
+            positions.push(Some(loaded.len() - 1));
+        }
+        let new_index = |index: usize| positions.get(index).copied().flatten();
+        self.selected_ruleset = new_index(self.selected_ruleset).unwrap_or(0);
+        self.compared_ruleset = new_index(self.compared_ruleset).unwrap_or(0);
+        self.pending_switch = self.pending_switch.and_then(new_index);
+        self.switch_after_overwrite = self.switch_after_overwrite.and_then(new_index);
+        self.rulesets = loaded;
+        self.rulesets_on_disk = on_disk;
+
+        let reloaded = &self.rulesets[self.selected_ruleset];
+        if !self.dirty && reloaded != self.screen.ruleset() {
+            self.open_ruleset(reloaded.clone());
+        }
+    }
+
+    /// Replaces the open ruleset with `ruleset`, on a fresh grid if the grid is showing.
+    fn open_ruleset(&mut self, ruleset: Ruleset) {
+        match self.screen {
+            Screen::Grid(_) => self.screen = Screen::Grid(self.new_grid(ruleset)),
+            Screen::Editor(_) => self.screen = Screen::Editor(ruleset),
+        }
+    }
+
+    /// Saves the open ruleset, unless that would overwrite another ruleset's file, in which case
+    /// it asks for confirmation instead. Returns whether the ruleset was saved.
+    fn save_open_ruleset(&mut self, cx: &mut EventContext) -> bool {
+        let ruleset = self.screen.ruleset();
+        if ruleset.path(self.ruleset_format).exists()
+            && ruleset.shares_name(&self.rulesets, self.selected_ruleset)
+        {
+            self.pending_overwrite = Some(ruleset.name.clone());
+            return false;
+        }
+        self.write_open_ruleset(cx)
+    }
+
+    /// Writes the open ruleset to its file and list entry, returning whether that succeeded.
+    fn write_open_ruleset(&mut self, cx: &mut EventContext) -> bool {
+        let ruleset = self.screen.ruleset();
+        if !save_ruleset(cx, ruleset, self.ruleset_format) {
+            return false;
+        }
+        self.rulesets[self.selected_ruleset] = ruleset.clone();
+        self.rulesets_on_disk[self.selected_ruleset] = true;
+        self.dirty = false;
+        self.unsaved_edits = 0;
+        true
+    }
+
+    /// Writes the open ruleset to its autosave file if it has unsaved edits.
+    fn autosave(&mut self, cx: &mut EventContext) {
+        if self.unsaved_edits == 0 {
+            return;
+        }
+        self.unsaved_edits = 0;
+        if let Err(err) = self.screen.ruleset().autosave() {
+            cx.emit(UpdateEvent::error(err));
+        }
+    }
+
+    /// Handles a press of the delete button for `target`, returning whether to go ahead.
+    /// The first press only arms the button; a second press confirms it.
+    fn confirm_deletion(&mut self, cx: &mut EventContext, target: DeletionTarget) -> bool {
+        if self.armed_deletion == Some(target) {
+            self.armed_deletion = None;
+            cx.stop_timer(self.deletion_timer);
+            true
+        } else {
+            self.armed_deletion = Some(target);
+            cx.start_timer(self.deletion_timer);
+            false
         }
     }
 }
@@ -97,8 +576,24 @@ impl AppData {
 impl Model for AppData {
     #[allow(clippy::too_many_lines)]
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        if edits_ruleset(event, self.armed_deletion) {
+            self.dirty = true;
+            if self.autosave_enabled {
+                self.unsaved_edits += 1;
+            }
+        }
+        if disarms_deletion(event) {
+            self.armed_deletion = None;
+        }
         event.map(|event: &UpdateEvent, _| match event {
-            UpdateEvent::WindowSizeChanged => self.window_size = cx.bounds(),
+            UpdateEvent::WindowSizeChanged => {
+                let bounds = cx.bounds();
+                self.window_size = BoundingBox {
+                    w: cx.physical_to_logical(bounds.w),
+                    h: cx.physical_to_logical(bounds.h),
+                    ..bounds
+                };
+            }
             UpdateEvent::CellHovered { x, y } => {
                 if let Screen::Grid(ref grid) = self.screen {
                     let index = grid.cell_index(*x, *y);
@@ -106,6 +601,11 @@ impl Model for AppData {
                         return;
                     }
                     self.hovered_index = Some(index);
+                    let name = grid
+                        .cell_at(*x, *y)
+                        .and_then(|cell| grid.ruleset.materials.get(cell.material_id))
+                        .map_or("Unknown", |material| material.name.as_str());
+                    self.tooltip = format!("{name} ({x}, {y}) #{index}");
                     let mouse_state = cx.mouse();
                     let button = match (mouse_state.left.state, mouse_state.right.state) {
                         (MouseButtonState::Pressed, MouseButtonState::Released) => {
@@ -117,57 +617,363 @@ impl Model for AppData {
                     cx.emit(UpdateEvent::CellClicked(button));
                 }
             }
-            UpdateEvent::CellUnhovered => self.hovered_index = None,
+            UpdateEvent::CellUnhovered => {
+                self.hovered_index = None;
+                self.tooltip.clear();
+            }
             UpdateEvent::CellClicked(button) => {
                 let Screen::Grid(ref mut grid) = self.screen else {
                     return;
                 };
+                let eyedropper = self.paint_tool == PaintTool::Eyedropper || cx.modifiers().alt();
+                if eyedropper && *button == MouseButton::Left {
+                    let cell = self
+                        .hovered_index
+                        .and_then(|index| grid.cell_at(index % grid.size, index / grid.size));
+                    if let Some(cell) = cell {
+                        cx.emit(UpdateEvent::MaterialSelected(cell.material_id));
+                    }
+                    return;
+                }
+                if self.paint_tool == PaintTool::Stamp && *button == MouseButton::Left {
+                    if let Some(index) = self.hovered_index {
+                        let offsets = self.selected_preset.oriented(self.preset_orientation);
+                        let center = grid.cell_coordinates(index);
+                        grid.stamp(&offsets, center, Cell::new(self.selected_material));
+                    }
+                    return;
+                }
+                if self.paint_tool == PaintTool::Paste && *button == MouseButton::Left {
+                    // Paste once per click rather than along the whole drag.
+                    if self.last_painted_index.is_none() {
+                        self.last_painted_index = self.hovered_index;
+                        cx.emit(GridEvent::ClipboardPasted);
+                    }
+                    return;
+                }
+                if self.paint_tool == PaintTool::Select {
+                    match (button, self.hovered_index) {
+                        (MouseButton::Left, Some(index)) => {
+                            let anchor = *self.selection_anchor.get_or_insert(index);
+                            self.selection = Some(GridRect::spanning(
+                                grid.cell_coordinates(anchor),
+                                grid.cell_coordinates(index),
+                            ));
+                        }
+                        (MouseButton::Right, _) => self.selection = None,
+                        _ => {}
+                    }
+                    return;
+                }
                 let new_material: MaterialId = match button {
                     MouseButton::Left => self.selected_material,
-                    MouseButton::Right => grid.ruleset.materials.default().id(),
+                    MouseButton::Right => grid
+                        .ruleset
+                        .materials
+                        .get_or_default(self.secondary_material)
+                        .id(),
                     _ => return,
                 };
                 let cell = Cell::new(new_material);
                 let Some(index) = self.hovered_index else {
                     return;
                 };
-                let x = index % grid.size;
-                let y = index / grid.size;
-                grid.set_cell(x, y, cell);
+                // Fill in any cells skipped by a fast drag since the last painted one.
+                let stroke = self
+                    .last_painted_index
+                    .map_or_else(|| vec![index], |last| grid.line(last, index));
+                for index in stroke {
+                    let position = grid.cell_coordinates(index);
+                    for (x, y) in self.symmetry.images(position, grid.size) {
+                        // Immovable cells can be erased, but not painted over.
+                        let protected = *button == MouseButton::Left
+                            && grid
+                                .cell_at(x, y)
+                                .is_some_and(|old| grid.ruleset.is_immovable(old.material_id));
+                        if !protected {
+                            grid.set_cell(x, y, cell);
+                        }
+                    }
+                }
+                self.last_painted_index = Some(index);
             }
             UpdateEvent::MaterialSelected(material_id) => self.selected_material = *material_id,
+            UpdateEvent::SecondaryMaterialSelected(material_id) => {
+                self.secondary_material = *material_id;
+            }
+            UpdateEvent::ToolSelected(tool) => self.paint_tool = *tool,
+            UpdateEvent::SymmetrySelected(mode) => self.symmetry = *mode,
+            UpdateEvent::PresetSelected(preset) => self.selected_preset = *preset,
+            UpdateEvent::PresetRotated => {
+                self.preset_orientation = self.preset_orientation.rotated()
+            }
+            UpdateEvent::PresetFlipped => {
+                self.preset_orientation = self.preset_orientation.flipped()
+            }
+            UpdateEvent::StatusPosted(message, color) => {
+                self.status_message = Some((message.clone(), *color));
+                cx.start_timer(self.status_timer);
+            }
+            UpdateEvent::StatusCleared => self.status_message = None,
+            UpdateEvent::InspectorToggled => self.inspector_enabled ^= true,
+            UpdateEvent::RuleCountsToggled => self.rule_counts_enabled ^= true,
+            UpdateEvent::CellGradientSet(darken) => self.cell_gradient = *darken,
+            UpdateEvent::GridFillToggled => self.fill_panel ^= true,
+            UpdateEvent::DeletionDisarmed => self.armed_deletion = None,
+            UpdateEvent::AutosaveDue => self.autosave(cx),
         });
         event.map(|event: &RulesetEvent, _| match event {
             RulesetEvent::Selected(index) => {
-                self.selected_ruleset = *index;
-                let ruleset = self.rulesets[*index].clone();
-                match self.screen {
-                    Screen::Grid(_) => {
-                        self.screen = Screen::Grid(Grid::new(ruleset, self.grid_size));
+                // Switching rulesets drops unsaved edits, so ask what to do with them first.
+                if self.dirty {
+                    self.pending_switch = Some(*index);
+                } else {
+                    self.select_ruleset(cx, *index);
+                }
+            }
+            RulesetEvent::SwitchSaved => {
+                if let Some(index) = self.pending_switch.take() {
+                    if self.save_open_ruleset(cx) {
+                        self.select_ruleset(cx, index);
+                    } else if self.pending_overwrite.is_some() {
+                        self.switch_after_overwrite = Some(index);
+                    }
+                }
+            }
+            RulesetEvent::SwitchDiscarded => {
+                if let Some(index) = self.pending_switch.take() {
+                    self.unsaved_edits = 0;
+                    if let Err(err) = self.screen.ruleset().remove_autosave() {
+                        cx.emit(UpdateEvent::error(err));
                     }
-                    Screen::Editor(_) => self.screen = Screen::Editor(ruleset),
+                    self.select_ruleset(cx, index);
                 }
             }
+            RulesetEvent::SwitchCancelled => self.pending_switch = None,
             RulesetEvent::Saved => {
-                if let Err(err) = self.screen.ruleset().save() {
-                    println!("{err}");
+                self.save_open_ruleset(cx);
+            }
+            RulesetEvent::OverwriteConfirmed => {
+                if self.pending_overwrite.take().is_some() {
+                    let switch = self.switch_after_overwrite.take();
+                    if self.write_open_ruleset(cx) {
+                        if let Some(index) = switch {
+                            self.select_ruleset(cx, index);
+                        }
+                    }
+                }
+            }
+            RulesetEvent::OverwriteCancelled => {
+                self.pending_overwrite = None;
+                self.switch_after_overwrite = None;
+            }
+            RulesetEvent::FormatSelected(index) => {
+                if let Some(&format) = RulesetFormat::ALL.get(*index) {
+                    self.ruleset_format = format;
+                }
+            }
+            RulesetEvent::TemplateSelected(index) => {
+                if let Some(&template) = RulesetTemplate::ALL.get(*index) {
+                    self.template = template;
                 }
             }
             RulesetEvent::Created => {
-                let new_ruleset = Ruleset::new();
-                self.rulesets.push(new_ruleset);
-
-                cx.emit(RulesetEvent::Selected(self.rulesets.len() - 1));
+                let mut new_ruleset = self.template.build();
+                new_ruleset.restyle_default_material(
+                    self.new_default_name.as_deref(),
+                    self.new_default_color,
+                );
+                let index = self.add_ruleset(new_ruleset);
+                cx.emit(RulesetEvent::Selected(index));
+            }
+            RulesetEvent::NewDefaultNamed(name) => {
+                let name = name.trim();
+                self.new_default_name = (!name.is_empty()).then(|| name.to_owned());
+            }
+            RulesetEvent::NewDefaultRecolored(color) => {
+                let color = color.trim();
+                if color.is_empty() {
+                    self.new_default_color = None;
+                    return;
+                }
+                match color.parse() {
+                    Ok(color) => self.new_default_color = Some(color),
+                    Err(err) => cx.emit(UpdateEvent::error(format!("Invalid color: {err}"))),
+                }
+            }
+            RulesetEvent::Duplicated(index) => {
+                // The selected ruleset's unsaved edits live in `screen`, not in its list entry.
+                let original = if *index == self.selected_ruleset {
+                    self.screen.ruleset()
+                } else {
+                    &self.rulesets[*index]
+                };
+                match original.duplicate() {
+                    Ok(copy) => {
+                        let index = self.add_ruleset(copy);
+                        cx.emit(RulesetEvent::Selected(index));
+                    }
+                    Err(err) => cx.emit(UpdateEvent::error(err)),
+                }
             }
             RulesetEvent::Renamed(name) => {
+                self.pending_overwrite = None;
+                self.switch_after_overwrite = None;
+                // The list entry keeps its saved name until the rename is saved too, so discarding
+                // the edits also discards the rename.
                 self.screen.ruleset_mut().name.clone_from(name);
-                self.rulesets[self.selected_ruleset].name.clone_from(name);
             }
-            RulesetEvent::Reloaded => {
-                self.rulesets = Ruleset::load_all().unwrap_or_else(|err| {
-                    println!("Failed to load rulesets; falling back: {err}");
-                    vec![Ruleset::blank()]
+            RulesetEvent::ConflictPolicySelected(index) => {
+                let ruleset = self.screen.ruleset_mut();
+                if let Some(policy) = ruleset.conflict_policy.with_index(*index) {
+                    ruleset.conflict_policy = policy;
+                }
+            }
+            RulesetEvent::ConflictSeedSet(seed) => {
+                self.screen.ruleset_mut().conflict_policy = ConflictPolicy::Random(*seed);
+            }
+            RulesetEvent::DefaultSizeSet(size) => self.screen.ruleset_mut().default_size = *size,
+            RulesetEvent::MetadataEdited(field, text) => {
+                self.screen
+                    .ruleset_mut()
+                    .metadata
+                    .get_mut(*field)
+                    .clone_from(text);
+            }
+            RulesetEvent::FallbackSelected(index) => {
+                let ruleset = self.screen.ruleset_mut();
+                ruleset.fallback = index
+                    .checked_sub(1)
+                    .and_then(|index| ruleset.materials.get_at(index))
+                    .map(Identifiable::id);
+            }
+            RulesetEvent::DefaultBoundarySelected(index) => {
+                // The first option leaves the boundary up to whatever the grid already had.
+                self.screen.ruleset_mut().default_boundary = index
+                    .checked_sub(1)
+                    .and_then(|index| Boundary::ALL.get(index).copied());
+            }
+            RulesetEvent::Reloaded | RulesetEvent::FilesChanged => {
+                let (rulesets, errors) = Ruleset::load_all();
+                match load_error_message(&errors) {
+                    Some(message) => cx.emit(UpdateEvent::error(message)),
+                    // Saving and autosaving set off the watcher too, and their own banners matter
+                    // more than this one.
+                    None if matches!(event, RulesetEvent::FilesChanged) => {}
+                    None => cx.emit(UpdateEvent::success(format!(
+                        "Reloaded {} rulesets",
+                        rulesets.len()
+                    ))),
+                }
+                self.reload_rulesets(rulesets);
+            }
+            RulesetEvent::AutosaveToggled => {
+                self.autosave_enabled ^= true;
+                self.unsaved_edits = 0;
+                if self.autosave_enabled {
+                    cx.start_timer(self.autosave_timer);
+                } else {
+                    cx.stop_timer(self.autosave_timer);
+                }
+            }
+            RulesetEvent::RecoveryAccepted => {
+                if self.pending_recoveries.is_empty() {
+                    return;
+                }
+                if self.dirty {
+                    cx.emit(UpdateEvent::error(
+                        "Save or discard the open ruleset's changes before recovering another",
+                    ));
+                    return;
+                }
+                let recovered = self.pending_recoveries.remove(0);
+                // The list entry keeps its saved copy, so discarding the recovered edits returns
+                // to it. A ruleset that was never saved has no other copy to keep.
+                let index = Ruleset::position_by_name(&self.rulesets, &recovered.name)
+                    .unwrap_or_else(|| self.add_ruleset(recovered.clone()));
+                self.select_ruleset(cx, index);
+                self.open_ruleset(recovered);
+                // The recovered edits still haven't been saved to the ruleset's own file.
+                self.dirty = true;
+            }
+            RulesetEvent::RecoveryDismissed => {
+                if self.pending_recoveries.is_empty() {
+                    return;
+                }
+                if let Err(err) = self.pending_recoveries.remove(0).remove_autosave() {
+                    cx.emit(UpdateEvent::error(err));
+                }
+            }
+            RulesetEvent::ReferencesFixed => self.screen.ruleset_mut().fix_references(),
+            RulesetEvent::ShareCodeCopied => {
+                let result = self.screen.ruleset().to_share_string().and_then(|code| {
+                    cx.set_clipboard(code)
+                        .map_err(|err| format!("Could not copy share code: {err}"))
                 });
+                match result {
+                    Ok(()) => cx.emit(UpdateEvent::success("Copied share code to clipboard")),
+                    Err(err) => cx.emit(UpdateEvent::error(err)),
+                }
+            }
+            RulesetEvent::Described => match self.screen.ruleset().save_description() {
+                Ok(path) => cx.emit(UpdateEvent::success(format!(
+                    "Wrote description to {}",
+                    path.display()
+                ))),
+                Err(err) => cx.emit(UpdateEvent::error(err)),
+            },
+            RulesetEvent::ShareCodeMerged => {
+                let result = cx
+                    .get_clipboard()
+                    .map_err(|err| format!("Could not read clipboard: {err}"))
+                    .and_then(|code| Ruleset::from_share_string(&code))
+                    .and_then(|other| {
+                        let name = other.name.clone();
+                        self.screen.ruleset_mut().merge(other).map(|()| name)
+                    });
+                match result {
+                    Ok(name) => cx.emit(UpdateEvent::success(format!("Merged ruleset '{name}'"))),
+                    Err(err) => cx.emit(UpdateEvent::error(err)),
+                }
+            }
+            RulesetEvent::LifeRuleImported(rule) => {
+                self.life_rule.clone_from(rule);
+                match Ruleset::from_life_string(rule) {
+                    Ok(ruleset) => {
+                        cx.emit(UpdateEvent::success(format!(
+                            "Imported ruleset '{}'",
+                            ruleset.name
+                        )));
+                        let index = self.add_ruleset(ruleset);
+                        cx.emit(RulesetEvent::Selected(index));
+                    }
+                    Err(err) => cx.emit(UpdateEvent::error(err)),
+                }
+            }
+            RulesetEvent::ShareCodeImported => {
+                let result = cx
+                    .get_clipboard()
+                    .map_err(|err| format!("Could not read clipboard: {err}"))
+                    .and_then(|code| Ruleset::from_share_string(&code));
+                match result {
+                    Ok(ruleset) => {
+                        cx.emit(UpdateEvent::success(format!(
+                            "Imported ruleset '{}'",
+                            ruleset.name
+                        )));
+                        let index = self.add_ruleset(ruleset);
+                        cx.emit(RulesetEvent::Selected(index));
+                    }
+                    Err(err) => cx.emit(UpdateEvent::error(err)),
+                }
+            }
+            RulesetEvent::WatchToggled => {
+                if self.ruleset_watcher.take().is_none() {
+                    match watcher::watch_rulesets(cx.get_proxy()) {
+                        Ok(watcher) => self.ruleset_watcher = Some(watcher),
+                        Err(err) => cx.emit(UpdateEvent::error(err)),
+                    }
+                }
             }
         });
         event.map(|event: &MaterialEvent, _| match event {
@@ -182,14 +988,94 @@ impl Model for AppData {
             }
             MaterialEvent::Recolored(index, color) => {
                 if let Some(material) = self.screen.ruleset_mut().materials.get_mut_at(*index) {
-                    if let Ok(color) = color.parse() {
-                        material.color = color;
+                    match color.parse() {
+                        Ok(color) => {
+                            material.color = color;
+                            self.color_error = None;
+                        }
+                        Err(err) => {
+                            self.color_error = Some((*index, format!("Invalid color: {err}")));
+                        }
                     }
                 }
             }
+            MaterialEvent::ChannelSet(index, channel, value) => {
+                if let Some(material) = self.screen.ruleset_mut().materials.get_mut_at(*index) {
+                    material.color = material.color.with_channel(*channel, *value);
+                    self.color_error = None;
+                }
+            }
+            MaterialEvent::Moved(from, to) => {
+                if self.screen.ruleset_mut().materials.reorder(*from, *to) {
+                    let reordered = |index| MaterialMap::reordered_index(index, *from, *to);
+                    self.color_picker = self.color_picker.map(reordered);
+                    if let Some((index, _)) = &mut self.color_error {
+                        *index = reordered(*index);
+                    }
+                }
+            }
+            MaterialEvent::LockToggled(index) => {
+                if let Some(material) = self.screen.ruleset_mut().materials.get_mut_at(*index) {
+                    material.locked ^= true;
+                }
+            }
+            MaterialEvent::ImmovableToggled(index) => {
+                if let Some(material) = self.screen.ruleset_mut().materials.get_mut_at(*index) {
+                    material.immovable ^= true;
+                }
+            }
+            MaterialEvent::PaletteGenerated => {
+                let materials = &self.screen.ruleset().materials;
+                let taken: Vec<MaterialColor> = materials
+                    .iter()
+                    .filter(|material| material.locked)
+                    .map(|material| material.color)
+                    .collect();
+                let unlocked: Vec<usize> = materials
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, material)| !material.locked)
+                    .map(|(index, _)| index)
+                    .collect();
+                let palette = MaterialColor::distinct_palette(unlocked.len(), &taken);
+                for (index, color) in unlocked.into_iter().zip(palette) {
+                    cx.emit(MaterialEvent::Recolored(index, color.to_string()));
+                }
+            }
+            MaterialEvent::PickerToggled(index) => {
+                self.color_picker = if self.color_picker == Some(*index) {
+                    None
+                } else {
+                    Some(*index)
+                };
+            }
             MaterialEvent::Deleted(material_id) => {
-                self.screen.ruleset_mut().materials.remove(*material_id);
+                if !self.confirm_deletion(cx, DeletionTarget::Material(*material_id)) {
+                    return;
+                }
+                let ruleset = self.screen.ruleset_mut();
+                if *material_id == ruleset.materials.default().id() {
+                    cx.emit(UpdateEvent::error("The default material cannot be deleted"));
+                    return;
+                }
+                let references = ruleset.references_to(*material_id);
+                if references.is_empty() {
+                    ruleset.remove_material(*material_id);
+                } else {
+                    let name = ruleset
+                        .materials
+                        .get(*material_id)
+                        .map_or_else(String::new, |material| material.name.clone());
+                    let description = format!("'{name}' is used by {}", references.join(", "));
+                    self.pending_deletion = Some((*material_id, description));
+                }
+            }
+            MaterialEvent::DeletionConfirmed => {
+                if let Some((material_id, _)) = self.pending_deletion.take() {
+                    self.screen.ruleset_mut().remove_material(material_id);
+                }
             }
+            MaterialEvent::DeletionCancelled => self.pending_deletion = None,
         });
         event.map(|event: &GroupEvent, _| match event {
             GroupEvent::Created => {
@@ -197,7 +1083,9 @@ impl Model for AppData {
                 ruleset.groups.push(MaterialGroup::new(ruleset));
             }
             GroupEvent::Deleted(group_index) => {
-                self.screen.ruleset_mut().groups.remove(*group_index);
+                if self.confirm_deletion(cx, DeletionTarget::Group(*group_index)) {
+                    self.screen.ruleset_mut().groups.remove(*group_index);
+                }
             }
             GroupEvent::Edited {
                 group_index,
@@ -226,6 +1114,14 @@ impl Model for AppData {
                     group.name.clone_from(name);
                 }
             }
+            GroupEvent::MembershipToggled {
+                group_index,
+                material_id,
+            } => {
+                if let Some(group) = self.screen.ruleset_mut().groups.get_mut(*group_index) {
+                    group.toggle(*material_id);
+                }
+            }
             GroupEvent::EntryDeleted {
                 group_index,
                 entry_index,
@@ -235,6 +1131,27 @@ impl Model for AppData {
                     group.remove_at(*entry_index);
                 }
             }
+            GroupEvent::SubgroupToggled {
+                group_index,
+                subgroup,
+            } => {
+                let ruleset = self.screen.ruleset_mut();
+                let Some(group) = ruleset.groups.get(*group_index) else {
+                    return;
+                };
+                if !group.subgroups().contains(subgroup)
+                    && ruleset.would_cycle(group.id(), *subgroup)
+                {
+                    cx.emit(UpdateEvent::error(format!(
+                        "Group '{}' can't contain a group that contains it",
+                        group.name
+                    )));
+                    return;
+                }
+                if let Some(group) = ruleset.groups.get_mut(*group_index) {
+                    group.toggle_subgroup(*subgroup);
+                }
+            }
             GroupEvent::EntryAdded(group_index) => {
                 let ruleset = self.screen.ruleset_mut();
                 if let Some(group) = ruleset.groups.get_mut(*group_index) {
@@ -255,14 +1172,40 @@ impl Model for AppData {
                 ruleset.rules.insert(index.value(), rule.clone());
             }
             RuleEvent::Deleted(index) => {
-                self.screen.ruleset_mut().rules.remove(index.value());
+                if self.confirm_deletion(cx, DeletionTarget::Rule(*index)) {
+                    self.screen.ruleset_mut().rules.remove(index.value());
+                }
             }
-            RuleEvent::OutputSet(rule_index, material_index) => {
+            RuleEvent::Renamed(rule_index, label) => {
+                rule_index.rule_mut(self.screen.ruleset_mut()).label = label.trim().to_owned();
+            }
+            RuleEvent::OutputSet(rule_index, position, output_index) => {
                 let ruleset = self.screen.ruleset_mut();
-                let Some(material) = ruleset.materials.get_at(*material_index) else {
+                let Some(output) = RuleOutput::from_index(ruleset, *output_index) else {
                     return;
                 };
-                rule_index.rule_mut(ruleset).output = material.id();
+                if let Some(entry) = rule_index.rule_mut(ruleset).outputs.get_mut(*position) {
+                    entry.0 = output;
+                }
+            }
+            RuleEvent::OutputAdded(rule_index) => {
+                let rule = rule_index.rule_mut(self.screen.ruleset_mut());
+                if let Some(&(output, _)) = rule.outputs.last() {
+                    rule.outputs.push((output, Rule::DEFAULT_WEIGHT));
+                }
+            }
+            RuleEvent::OutputRemoved(rule_index, position) => {
+                let rule = rule_index.rule_mut(self.screen.ruleset_mut());
+                // Every rule keeps at least one output.
+                if rule.outputs.len() > 1 && *position < rule.outputs.len() {
+                    rule.outputs.remove(*position);
+                }
+            }
+            RuleEvent::OutputWeightSet(rule_index, position, weight) => {
+                let rule = rule_index.rule_mut(self.screen.ruleset_mut());
+                if let Some(entry) = rule.outputs.get_mut(*position) {
+                    entry.1 = *weight;
+                }
             }
             RuleEvent::InputSet(rule_index, pattern_index) => {
                 let ruleset = self.screen.ruleset_mut();
@@ -287,8 +1230,10 @@ impl Model for AppData {
                     .insert(index.values().1, new_condition);
             }
             ConditionEvent::Deleted(index) => {
-                let ruleset = self.screen.ruleset_mut();
-                index.rule_mut(ruleset).conditions.remove(index.values().1);
+                if self.confirm_deletion(cx, DeletionTarget::Condition(*index)) {
+                    let ruleset = self.screen.ruleset_mut();
+                    index.rule_mut(ruleset).conditions.remove(index.values().1);
+                }
             }
             ConditionEvent::PatternSet(condition_index, pattern_index) => {
                 let ruleset = self.screen.ruleset_mut();
@@ -314,19 +1259,10 @@ impl Model for AppData {
             }
             ConditionEvent::CountUpdated(index, count_string) => {
                 let condition = index.condition_mut(self.screen.ruleset_mut());
-
-                let ConditionVariant::Count(variant) = &condition.variant else {
-                    return;
-                };
-
-                let mut elements: Vec<u8> = count_string
-                    .chars()
-                    .filter_map(|char| char.to_digit(10).and_then(|num| num.try_into().ok()))
-                    .filter(|&n| n <= 8)
-                    .collect();
-                elements.sort_unstable();
-                elements.dedup();
-                condition.variant = ConditionVariant::Count(variant.with_elements(elements));
+                let max_count = condition.max_count();
+                if let Some(operator) = condition.variant.operator_mut() {
+                    operator.update_counts(count_string, max_count);
+                }
             }
             ConditionEvent::VariantChanged(index, variant) => {
                 let ruleset = self.screen.ruleset_mut();
@@ -335,46 +1271,226 @@ impl Model for AppData {
             ConditionEvent::OperatorChanged(index) => {
                 let ruleset = self.screen.ruleset_mut();
                 let condition = index.condition_mut(ruleset);
-                let ConditionVariant::Count(variant) = &condition.variant else {
+                let Some(variant) = condition.variant.operator_mut() else {
                     return;
                 };
-                let new_variant = match variant {
+                *variant = match variant {
                     Operator::List(vec) => Operator::Greater(vec.first().copied().unwrap_or(0)),
                     Operator::Greater(value) => Operator::Less(*value),
-                    Operator::Less(value) => Operator::List(vec![*value]),
+                    Operator::Less(value) => Operator::Range(*value, *value),
+                    Operator::Range(..) => Operator::Percent(50, Comparison::Greater),
+                    Operator::Percent(..) => Operator::List(vec![0]),
                 };
-                condition.variant = ConditionVariant::Count(new_variant);
             }
             ConditionEvent::Inverted(index) => {
                 let ruleset = self.screen.ruleset_mut();
                 let condition = index.condition_mut(ruleset);
                 condition.inverted = !condition.inverted;
             }
+            ConditionEvent::DirectionalModeToggled(index) => {
+                let condition = index.condition_mut(self.screen.ruleset_mut());
+                condition.directional_mode = condition.directional_mode.toggled();
+            }
+            ConditionEvent::NeighborhoodToggled(index) => {
+                let ruleset = self.screen.ruleset_mut();
+                let condition = index.condition_mut(ruleset);
+                condition.neighborhood = condition.neighborhood.toggled();
+                let max_count = condition.max_count();
+                if let Some(operator) = condition.variant.operator_mut() {
+                    operator.clamp(max_count);
+                }
+            }
+            ConditionEvent::RadiusSet(index, radius) => {
+                let ruleset = self.screen.ruleset_mut();
+                let condition = index.condition_mut(ruleset);
+                condition.radius = (*radius).clamp(1, Condition::MAX_RADIUS);
+                let max_count = condition.max_count();
+                if let Some(operator) = condition.variant.operator_mut() {
+                    operator.clamp(max_count);
+                }
+            }
+            ConditionEvent::ComparisonToggled(index) => {
+                let condition = index.condition_mut(self.screen.ruleset_mut());
+                match &mut condition.variant {
+                    ConditionVariant::Compared { comparison, .. }
+                    | ConditionVariant::Count(Operator::Percent(_, comparison))
+                    | ConditionVariant::CountAny {
+                        counts: Operator::Percent(_, comparison),
+                        ..
+                    } => *comparison = comparison.toggled(),
+                    _ => {}
+                }
+            }
+            ConditionEvent::ComparedPatternSet(index, pattern_index) => {
+                let ruleset = self.screen.ruleset_mut();
+                let Some(pattern) = Pattern::from_index(ruleset, *pattern_index) else {
+                    return;
+                };
+                let condition = index.condition_mut(ruleset);
+                if let ConditionVariant::Compared { other, .. } = &mut condition.variant {
+                    *other = pattern;
+                }
+            }
+            ConditionEvent::AlternativeAdded(index) => {
+                let condition = index.condition_mut(self.screen.ruleset_mut());
+                let pattern = condition.pattern;
+                if let ConditionVariant::CountAny { alternatives, .. } = &mut condition.variant {
+                    alternatives.push(pattern);
+                }
+            }
+            ConditionEvent::AlternativeSet(index, alternative_index, pattern_index) => {
+                let ruleset = self.screen.ruleset_mut();
+                let Some(pattern) = Pattern::from_index(ruleset, *pattern_index) else {
+                    return;
+                };
+                let condition = index.condition_mut(ruleset);
+                if let ConditionVariant::CountAny { alternatives, .. } = &mut condition.variant {
+                    if let Some(alternative) = alternatives.get_mut(*alternative_index) {
+                        *alternative = pattern;
+                    }
+                }
+            }
+            ConditionEvent::AlternativeRemoved(index, alternative_index) => {
+                let condition = index.condition_mut(self.screen.ruleset_mut());
+                if let ConditionVariant::CountAny { alternatives, .. } = &mut condition.variant {
+                    if *alternative_index < alternatives.len() {
+                        alternatives.remove(*alternative_index);
+                    }
+                }
+            }
+            ConditionEvent::OffsetAdded(index) => {
+                let condition = index.condition_mut(self.screen.ruleset_mut());
+                if let ConditionVariant::OffsetPattern(offsets) = &mut condition.variant {
+                    offsets.push((0, 1));
+                }
+            }
+            ConditionEvent::OffsetSet(index, offset_index, text) => {
+                let Some(offset) = ConditionVariant::parse_offset(text) else {
+                    cx.emit(UpdateEvent::error(format!(
+                        "Offsets should be two numbers like \"1, -2\", not \"{text}\""
+                    )));
+                    return;
+                };
+                let condition = index.condition_mut(self.screen.ruleset_mut());
+                if let ConditionVariant::OffsetPattern(offsets) = &mut condition.variant {
+                    if let Some(existing) = offsets.get_mut(*offset_index) {
+                        *existing = offset;
+                    }
+                }
+            }
+            ConditionEvent::OffsetRemoved(index, offset_index) => {
+                let condition = index.condition_mut(self.screen.ruleset_mut());
+                if let ConditionVariant::OffsetPattern(offsets) = &mut condition.variant {
+                    if *offset_index < offsets.len() {
+                        offsets.remove(*offset_index);
+                    }
+                }
+            }
+            ConditionEvent::ModulusSet(index, new_modulus) => {
+                let condition = index.condition_mut(self.screen.ruleset_mut());
+                if let ConditionVariant::Generation { modulus, remainder } = &mut condition.variant
+                {
+                    *modulus = (*new_modulus).max(1);
+                    *remainder %= *modulus;
+                }
+            }
+            ConditionEvent::RemainderSet(index, new_remainder) => {
+                let condition = index.condition_mut(self.screen.ruleset_mut());
+                if let ConditionVariant::Generation { modulus, remainder } = &mut condition.variant
+                {
+                    *remainder = new_remainder % (*modulus).max(1);
+                }
+            }
         });
         event.map(|event: &GridEvent, _| match event {
             GridEvent::Stepped => {
-                if let Screen::Grid(ref mut grid) = self.screen {
-                    grid.next_generation();
+                self.step();
+                if self.running {
+                    self.record_step(Instant::now());
                 }
             }
-            GridEvent::Toggled => {
-                self.running = !self.running;
-                if self.running {
-                    cx.start_timer(self.timer);
-                } else {
-                    cx.stop_timer(self.timer);
+            GridEvent::Ticked(lateness) => {
+                let interval = Duration::from_secs_f32(self.speed.recip());
+                // The timer fires again at once for every interval a slow step overran.
+                // Dropping those ticks slows the run down instead of queueing up steps.
+                if !self.running || *lateness >= interval {
+                    return;
                 }
+                let started = Instant::now();
+                self.step();
+                let now = Instant::now();
+                self.step_limited = now.duration_since(started) > interval;
+                self.record_step(now);
             }
-            GridEvent::SpeedSet(speed) => {
-                self.speed = (*speed * 100.0).round() / 100.0;
+            GridEvent::Toggled => {
+                self.paused_unfocused = false;
+                self.set_running(cx, !self.running);
+            }
+            GridEvent::SpeedSet(speed) if speed.is_finite() => {
+                self.speed = ((*speed * 10.0).round() / 10.0).clamp(MIN_SPEED, MAX_SPEED);
                 cx.modify_timer(self.timer, |state| {
-                    state.set_interval(Duration::from_secs_f32(self.speed));
+                    state.set_interval(Duration::from_secs_f32(self.speed.recip()));
                 });
+                self.update_transition();
+            }
+            GridEvent::InterpolationToggled => {
+                self.interpolation ^= true;
+                self.update_transition();
+            }
+            GridEvent::FocusPauseToggled => self.pause_unfocused ^= true,
+            GridEvent::SpeedSet(_) => {}
+            GridEvent::Cleared => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    let boundary = grid.boundary();
+                    *grid = Grid::new(grid.ruleset.clone(), grid.size);
+                    grid.set_boundary(boundary);
+                    grid.set_seed(self.seed);
+                }
+                self.clear_timeline();
+            }
+            GridEvent::TimelineScrubbed(position) => {
+                let Some(state) = self.timeline.get(*position) else {
+                    return;
+                };
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    // States loaded at their own size may leave the timeline with mixed sizes.
+                    let missing = grid.load_state(state.clone());
+                    self.grid_size = grid.size;
+                    report_missing_materials(cx, missing, "loaded", &grid.ruleset);
+                    self.timeline_position = *position;
+                }
+                if self.running {
+                    self.set_running(cx, false);
+                }
+            }
+            GridEvent::PresetPlaced => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    let offsets = self.selected_preset.oriented(self.preset_orientation);
+                    let center = (grid.size / 2, grid.size / 2);
+                    grid.stamp(&offsets, center, Cell::new(self.selected_material));
+                }
             }
             GridEvent::Resized(size) => {
-                self.grid_size = *size;
-                if let Screen::Grid(ref grid) = self.screen {
-                    self.screen = Screen::Grid(Grid::new(grid.ruleset.clone(), *size));
+                let clamped = (*size).clamp(Grid::MIN_SIZE, Grid::MAX_SIZE);
+                if clamped != *size {
+                    cx.emit(UpdateEvent::error(format!(
+                        "Grid size must be between {} and {}; using {clamped}",
+                        Grid::MIN_SIZE,
+                        Grid::MAX_SIZE
+                    )));
+                }
+                self.grid_size = clamped;
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    if let Err(err) = grid.resize(clamped) {
+                        cx.emit(UpdateEvent::error(err));
+                    }
+                }
+                self.clear_timeline();
+            }
+            GridEvent::BoundarySelected(boundary) => {
+                self.boundary = *boundary;
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    grid.set_boundary(*boundary);
                 }
             }
             GridEvent::StateSaved => {
@@ -383,9 +1499,268 @@ impl Model for AppData {
                 };
             }
             GridEvent::StateLoaded => {
+                if let Some(state) = self.saved_state.clone() {
+                    self.load_state(cx, state);
+                }
+            }
+            GridEvent::StateClipToggled => self.clip_loaded_states ^= true,
+            GridEvent::CsvExported => {
+                if let Screen::Grid(ref grid) = self.screen {
+                    let path = match FunctionalGridState::path(
+                        &self.state_file_name,
+                        FunctionalGridState::CSV_EXTENSION,
+                    ) {
+                        Ok(path) => path,
+                        Err(err) => {
+                            cx.emit(UpdateEvent::error(err));
+                            return;
+                        }
+                    };
+                    let result = path
+                        .parent()
+                        .map_or(Ok(()), fs::create_dir_all)
+                        .and_then(|()| fs::write(&path, grid.to_csv()));
+                    match result {
+                        Ok(()) => {
+                            cx.emit(UpdateEvent::success(format!("Exported CSV to {path:?}")))
+                        }
+                        Err(err) => {
+                            let message = format!("Could not export CSV to '{path:?}': {err}");
+                            cx.emit(UpdateEvent::error(message));
+                        }
+                    }
+                }
+            }
+            GridEvent::CsvImported => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    let path = match FunctionalGridState::path(
+                        &self.state_file_name,
+                        FunctionalGridState::CSV_EXTENSION,
+                    ) {
+                        Ok(path) => path,
+                        Err(err) => {
+                            cx.emit(UpdateEvent::error(err));
+                            return;
+                        }
+                    };
+                    let result = fs::read_to_string(&path)
+                        .map_err(|err| format!("Could not import CSV from '{path:?}': {err}"))
+                        .and_then(|text| Grid::from_csv(&text, grid.ruleset.clone()));
+                    match result {
+                        Ok(imported) => {
+                            *grid = imported;
+                            grid.set_seed(self.seed);
+                            self.grid_size = grid.size;
+                            self.clear_timeline();
+                        }
+                        Err(err) => cx.emit(UpdateEvent::error(err)),
+                    }
+                }
+            }
+            GridEvent::PlotToggled(material_id) => {
+                if let Some(position) = self.hidden_plots.iter().position(|id| id == material_id) {
+                    self.hidden_plots.remove(position);
+                } else {
+                    self.hidden_plots.push(*material_id);
+                }
+            }
+            GridEvent::PopulationCleared => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    grid.clear_population();
+                }
+            }
+            GridEvent::PeriodReset => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    grid.reset_period();
+                }
+            }
+            GridEvent::HeatmapToggled => self.heatmap_enabled ^= true,
+            GridEvent::ActivityReset => {
                 if let Screen::Grid(ref mut grid) = self.screen {
-                    if let Some(state) = &self.saved_state {
-                        grid.load_state(state.clone());
+                    grid.reset_activity();
+                }
+            }
+            GridEvent::ActivityDecaySet(decay) => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    grid.set_activity_decay(*decay);
+                }
+            }
+            GridEvent::ImagePathSet(path) => self.image_path.clone_from(path),
+            GridEvent::ImageImported => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    match image::open(&self.image_path) {
+                        Ok(image) => {
+                            let imported =
+                                Grid::from_image(&image, grid.ruleset.clone(), grid.size);
+                            if self.image_backup.is_none() {
+                                self.image_backup = Some(grid.functional_state());
+                            }
+                            grid.load_state(imported.functional_state());
+                        }
+                        Err(err) => {
+                            let message =
+                                format!("Could not import image '{}': {err}", self.image_path);
+                            cx.emit(UpdateEvent::error(message));
+                        }
+                    }
+                }
+            }
+            GridEvent::ComparedRulesetSelected(index) => self.compared_ruleset = *index,
+            GridEvent::ComparisonLengthSet(length) => self.comparison_length = *length,
+            GridEvent::RulesetsCompared => {
+                let Screen::Grid(ref grid) = self.screen else {
+                    return;
+                };
+                let Some(other) = self.rulesets.get(self.compared_ruleset) else {
+                    cx.emit(UpdateEvent::error("Select a ruleset to compare against."));
+                    return;
+                };
+                let divergence = grid.divergence(other.clone(), self.comparison_length);
+                let path = match FunctionalGridState::path(
+                    &format!("{}_divergence", self.state_file_name),
+                    FunctionalGridState::CSV_EXTENSION,
+                ) {
+                    Ok(path) => path,
+                    Err(err) => {
+                        cx.emit(UpdateEvent::error(err));
+                        return;
+                    }
+                };
+                let result = path
+                    .parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|()| fs::write(&path, divergence.to_csv()));
+                match result {
+                    Ok(()) => {
+                        let summary = divergence.first_difference().map_or_else(
+                            || String::from("The runs never differ"),
+                            |generation| {
+                                format!("The runs first differ at generation {generation}")
+                            },
+                        );
+                        cx.emit(UpdateEvent::success(format!(
+                            "{summary}; exported divergence to {path:?}"
+                        )));
+                    }
+                    Err(err) => {
+                        let message = format!("Could not export divergence to '{path:?}': {err}");
+                        cx.emit(UpdateEvent::error(message));
+                    }
+                }
+            }
+            GridEvent::ImageApplied => self.image_backup = None,
+            GridEvent::RandomizedRegion(rect) => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    let weights: Vec<(MaterialId, u32)> = grid
+                        .ruleset
+                        .materials
+                        .iter()
+                        .map(|material| (material.id(), 1))
+                        .collect();
+                    grid.randomize_region(*rect, &weights, self.random.gen());
+                }
+            }
+            GridEvent::RegionStepped(rect) => self.advance(|grid| grid.step_region(*rect)),
+            GridEvent::SeedSet(seed) => self.reseed(*seed),
+            GridEvent::SeedRerolled => self.reseed(rand::random()),
+            GridEvent::SeedRecordingToggled => self.record_seed ^= true,
+            GridEvent::FlippedHorizontal => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    grid.flip_horizontal();
+                }
+            }
+            GridEvent::FlippedVertical => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    grid.flip_vertical();
+                }
+            }
+            GridEvent::RotatedClockwise => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    grid.rotate_cw();
+                }
+            }
+            GridEvent::RotatedCounterclockwise => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    grid.rotate_ccw();
+                }
+            }
+            GridEvent::SelectionCopied => {
+                let Screen::Grid(ref grid) = self.screen else {
+                    return;
+                };
+                match self.selection.and_then(|rect| grid.copy_region(rect)) {
+                    Some(region) => {
+                        cx.emit(UpdateEvent::success(format!(
+                            "Copied {}x{} cells",
+                            region.width(),
+                            region.height()
+                        )));
+                        self.clipboard = Some(region);
+                    }
+                    None => cx.emit(UpdateEvent::error("Select cells to copy first")),
+                }
+            }
+            GridEvent::ClipboardPasted => {
+                let Screen::Grid(ref mut grid) = self.screen else {
+                    return;
+                };
+                let (Some(region), Some(index)) = (&self.clipboard, self.hovered_index) else {
+                    return;
+                };
+                let missing = grid.paste_region(grid.cell_coordinates(index), region);
+                report_missing_materials(cx, missing, "pasted", &grid.ruleset);
+            }
+            GridEvent::ImageDiscarded => {
+                if let Screen::Grid(ref mut grid) = self.screen {
+                    if let Some(backup) = self.image_backup.take() {
+                        grid.load_state(backup);
+                    }
+                }
+            }
+            GridEvent::StateFileNamed(name) => self.state_file_name.clone_from(name),
+            GridEvent::StateWritten => {
+                if let Screen::Grid(ref grid) = self.screen {
+                    let path = match FunctionalGridState::path(
+                        &self.state_file_name,
+                        FunctionalGridState::EXTENSION,
+                    ) {
+                        Ok(path) => path,
+                        Err(err) => {
+                            cx.emit(UpdateEvent::error(err));
+                            return;
+                        }
+                    };
+                    let mut state = grid.functional_state();
+                    if self.record_seed {
+                        state.set_seed(Some(grid.seed()));
+                    }
+                    match state.save_to(&path) {
+                        Ok(()) => cx.emit(UpdateEvent::success(format!("Saved state to {path:?}"))),
+                        Err(err) => cx.emit(UpdateEvent::error(err)),
+                    }
+                }
+            }
+            GridEvent::StateRead => {
+                if let Screen::Grid(_) = self.screen {
+                    let path = match FunctionalGridState::path(
+                        &self.state_file_name,
+                        FunctionalGridState::EXTENSION,
+                    ) {
+                        Ok(path) => path,
+                        Err(err) => {
+                            cx.emit(UpdateEvent::error(err));
+                            return;
+                        }
+                    };
+                    match FunctionalGridState::load_from(&path) {
+                        Ok(state) => {
+                            let seed = state.seed();
+                            self.load_state(cx, state);
+                            if let Some(seed) = seed {
+                                self.reseed(seed);
+                            }
+                        }
+                        Err(err) => cx.emit(UpdateEvent::error(err)),
                     }
                 }
             }
@@ -395,30 +1770,248 @@ impl Model for AppData {
                 self.editor_enabled = true;
                 let ruleset = self.screen.ruleset().clone();
                 self.saved_state = None;
+                self.image_backup = None;
                 self.screen = Screen::Editor(ruleset);
             }
             EditorEvent::Disabled => {
                 self.editor_enabled = false;
                 let ruleset = self.screen.ruleset().clone();
-                self.screen = Screen::Grid(Grid::new(ruleset, self.grid_size));
+                self.screen = Screen::Grid(self.new_grid(ruleset));
             }
             EditorEvent::TabSwitched(tab) => self.selected_tab = *tab,
+            EditorEvent::MetadataToggled => self.metadata_shown ^= true,
         });
+        event.map(|event: &WindowEvent, _| {
+            let code = match event {
+                WindowEvent::KeyDown(code, _) => code,
+                WindowEvent::MouseUp(_) => {
+                    self.last_painted_index = None;
+                    self.selection_anchor = None;
+                    return;
+                }
+                WindowEvent::WindowFocused(false) if self.pause_unfocused && self.running => {
+                    self.paused_unfocused = true;
+                    self.set_running(cx, false);
+                    return;
+                }
+                WindowEvent::WindowFocused(true) if self.paused_unfocused => {
+                    self.paused_unfocused = false;
+                    self.set_running(cx, true);
+                    return;
+                }
+                _ => return,
+            };
+            if self.editor_enabled {
+                return;
+            }
+            // Textboxes mark themselves as checked while being edited; leave their keys alone.
+            let focused = cx.focused();
+            if cx.with_current(focused, |cx| cx.is_checked()) {
+                return;
+            }
+            match code {
+                Code::Space => cx.emit(GridEvent::Toggled),
+                Code::ArrowRight | Code::Period => cx.emit(GridEvent::Stepped),
+                Code::KeyC if cx.modifiers().ctrl() => cx.emit(GridEvent::SelectionCopied),
+                Code::KeyV if cx.modifiers().ctrl() => cx.emit(GridEvent::ClipboardPasted),
+                Code::KeyR => cx.emit(GridEvent::Cleared),
+                Code::KeyH => cx.emit(GridEvent::FlippedHorizontal),
+                Code::KeyV => cx.emit(GridEvent::FlippedVertical),
+                Code::BracketLeft => cx.emit(GridEvent::RotatedCounterclockwise),
+                Code::BracketRight => cx.emit(GridEvent::RotatedClockwise),
+                code => {
+                    let material = MATERIAL_KEYS
+                        .iter()
+                        .position(|key| key == code)
+                        .and_then(|index| self.screen.ruleset().materials.get_at(index));
+                    if let Some(material) = material {
+                        cx.emit(UpdateEvent::MaterialSelected(material.id()));
+                    }
+                }
+            }
+        });
+        if self.unsaved_edits >= AUTOSAVE_EDITS {
+            self.autosave(cx);
+        }
+        self.save_settings();
+    }
+}
+
+/// Reports how many cells just `action`, like "pasted", became the default material because
+/// `ruleset` doesn't have theirs, if any did.
+fn report_missing_materials(
+    cx: &mut EventContext,
+    missing: usize,
+    action: &str,
+    ruleset: &Ruleset,
+) {
+    if missing > 0 {
+        cx.emit(UpdateEvent::error(format!(
+            "Replaced {missing} {action} cells with the default material; their materials aren't in ruleset '{}'",
+            ruleset.name
+        )));
+    }
+}
+
+/// Saves `ruleset` and reports how that went, returning whether it succeeded. A successful
+/// save makes the ruleset's autosave redundant, so that is removed.
+fn save_ruleset(cx: &mut EventContext, ruleset: &Ruleset, format: RulesetFormat) -> bool {
+    let result = match format {
+        RulesetFormat::Toml => ruleset.save(),
+        RulesetFormat::Json => ruleset.save_json(),
+    };
+    match result {
+        Ok(()) => {
+            // The banner shows one message at a time, so a failed cleanup replaces the success.
+            match ruleset.remove_autosave() {
+                Ok(()) => cx.emit(UpdateEvent::success(format!(
+                    "Saved ruleset '{}'",
+                    ruleset.name
+                ))),
+                Err(err) => cx.emit(UpdateEvent::error(err)),
+            }
+            true
+        }
+        Err(err) => {
+            cx.emit(UpdateEvent::error(err));
+            false
+        }
+    }
+}
+
+/// Whether `event` changes the editor in some way other than pressing a delete button, which
+/// disarms any delete button waiting for its second press.
+fn disarms_deletion(event: &mut Event) -> bool {
+    let mut disarms = false;
+    event.map(|event: &MaterialEvent, _| {
+        disarms = !matches!(event, MaterialEvent::Deleted(_));
+    });
+    event.map(|event: &GroupEvent, _| disarms = !matches!(event, GroupEvent::Deleted(_)));
+    event.map(|event: &RuleEvent, _| disarms = !matches!(event, RuleEvent::Deleted(_)));
+    event.map(|event: &ConditionEvent, _| {
+        disarms = !matches!(event, ConditionEvent::Deleted(_));
+    });
+    event.map(|_: &RulesetEvent, _| disarms = true);
+    event.map(|_: &EditorEvent, _| disarms = true);
+    disarms
+}
+
+/// Whether `event` changes the open ruleset, leaving it with unsaved edits. A delete button only
+/// deletes when `armed` shows it was already pressed once.
+fn edits_ruleset(event: &mut Event, armed: Option<DeletionTarget>) -> bool {
+    let mut edits = false;
+    event.map(|event: &MaterialEvent, _| {
+        edits = match event {
+            MaterialEvent::Deleted(id) => armed == Some(DeletionTarget::Material(*id)),
+            MaterialEvent::PickerToggled(_) | MaterialEvent::DeletionCancelled => false,
+            _ => true,
+        };
+    });
+    event.map(|event: &GroupEvent, _| {
+        edits = match event {
+            GroupEvent::Deleted(index) => armed == Some(DeletionTarget::Group(*index)),
+            _ => true,
+        };
+    });
+    event.map(|event: &RuleEvent, _| {
+        edits = match event {
+            RuleEvent::Deleted(index) => armed == Some(DeletionTarget::Rule(*index)),
+            _ => true,
+        };
+    });
+    event.map(|event: &ConditionEvent, _| {
+        edits = match event {
+            ConditionEvent::Deleted(index) => armed == Some(DeletionTarget::Condition(*index)),
+            _ => true,
+        };
+    });
+    event.map(|event: &RulesetEvent, _| {
+        edits = matches!(
+            event,
+            RulesetEvent::Renamed(_)
+                | RulesetEvent::ConflictPolicySelected(_)
+                | RulesetEvent::ConflictSeedSet(_)
+                | RulesetEvent::DefaultSizeSet(_)
+                | RulesetEvent::DefaultBoundarySelected(_)
+                | RulesetEvent::FallbackSelected(_)
+                | RulesetEvent::MetadataEdited(..)
+                | RulesetEvent::ReferencesFixed
+                | RulesetEvent::ShareCodeMerged
+        );
+    });
+    edits
+}
+
+/// Summarizes the ruleset files that failed to load for the status banner, or returns `None`
+/// if everything loaded. When several failed, each one is logged since the banner only counts
+/// them.
+fn load_error_message(errors: &[(PathBuf, String)]) -> Option<String> {
+    if errors.len() > 1 {
+        for (path, err) in errors {
+            println!("Could not load ruleset '{}': {err}", path.display());
+        }
+    }
+    match errors {
+        [] => None,
+        [(path, err)] => Some(format!(
+            "Could not load ruleset '{}': {err}",
+            path.display()
+        )),
+        _ => Some(format!(
+            "Could not load {} ruleset files; see the log for details",
+            errors.len()
+        )),
     }
 }
 
 fn main() -> Result<(), ApplicationError> {
-    Application::new(|cx| {
+    let settings = Settings::load();
+    let window_size = settings.window_size;
+    Application::new(move |cx| {
         cx.add_stylesheet(include_style!("resources/style.css"))
             .expect("failed to add stylesheet.");
 
         let timer = cx.add_timer(Duration::from_secs_f32(1.0), None, |cx, event| {
+            if let TimerAction::Tick(lateness) = event {
+                cx.emit(GridEvent::Ticked(lateness));
+            }
+        });
+
+        let status_timer = cx.add_timer(STATUS_DURATION, Some(STATUS_DURATION), |cx, event| {
+            if let TimerAction::Stop = event {
+                cx.emit(UpdateEvent::StatusCleared);
+            }
+        });
+
+        let deletion_timer = cx.add_timer(DELETION_WINDOW, Some(DELETION_WINDOW), |cx, event| {
+            if let TimerAction::Stop = event {
+                cx.emit(UpdateEvent::DeletionDisarmed);
+            }
+        });
+
+        let autosave_timer = cx.add_timer(AUTOSAVE_INTERVAL, None, |cx, event| {
             if let TimerAction::Tick(_) = event {
-                cx.emit(GridEvent::Stepped);
+                cx.emit(UpdateEvent::AutosaveDue);
             }
         });
 
-        AppData::new(timer).build(cx);
+        let data = AppData::new(
+            timer,
+            status_timer,
+            deletion_timer,
+            autosave_timer,
+            settings,
+        );
+        if data.status_message.is_some() {
+            cx.start_timer(status_timer);
+        }
+        if data.autosave_enabled {
+            cx.start_timer(autosave_timer);
+        }
+        cx.modify_timer(timer, |state| {
+            state.set_interval(Duration::from_secs_f32(data.speed.recip()));
+        });
+        data.build(cx);
         ZStack::new(cx, |cx| {
             Binding::new(cx, AppData::editor_enabled, |cx, enabled| {
                 if enabled.get(cx) {
@@ -427,6 +2020,9 @@ fn main() -> Result<(), ApplicationError> {
                     display::game_board(cx);
                 }
             });
+            display::status_banner(cx);
+            display::recovery_prompt(cx);
+            display::unsaved_prompt(cx);
         })
         .on_geo_changed(|cx, changes| {
             if changes.contains(GeoChanged::WIDTH_CHANGED)
@@ -436,6 +2032,6 @@ fn main() -> Result<(), ApplicationError> {
             }
         });
     })
-    .inner_size(INITIAL_WINDOW_SIZE)
+    .inner_size(window_size)
     .run()
 }
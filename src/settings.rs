@@ -0,0 +1,114 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{display::style, grid::Boundary, INITIAL_WINDOW_SIZE};
+
+/// Preferences kept from one session to the next, separate from rulesets and saved states.
+/// Fields missing from the file keep their defaults.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// The window's logical width and height.
+    pub window_size: (u32, u32),
+    pub grid_size: usize,
+    /// Generations per second.
+    pub speed: f32,
+    pub boundary: Boundary,
+    pub interpolation: bool,
+    pub pause_unfocused: bool,
+    pub heatmap: bool,
+    pub cell_gradient: u8,
+    pub fill_panel: bool,
+    pub autosave: bool,
+    /// The name of the ruleset last selected, selected again on launch if it still exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ruleset: Option<String>,
+}
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window_size: INITIAL_WINDOW_SIZE,
+            grid_size: 5,
+            speed: 1.0,
+            boundary: Boundary::default(),
+            interpolation: false,
+            pause_unfocused: false,
+            heatmap: false,
+            cell_gradient: style::CELL_GRADIENT_DARKEN,
+            fill_panel: false,
+            autosave: false,
+            ruleset: None,
+        }
+    }
+}
+impl Settings {
+    pub const PATH: &str = "./settings.toml";
+
+    pub fn load() -> Self {
+        Self::load_from(Path::new(Self::PATH))
+    }
+    /// Reads the settings at `path`, falling back to the defaults if the file is missing or
+    /// can't be parsed. A speed that isn't a finite number is replaced with the default.
+    pub fn load_from(path: &Path) -> Self {
+        let Ok(text) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        let mut settings: Self = toml::from_str(&text).unwrap_or_else(|err| {
+            println!("Could not load settings from {}: {err}", path.display());
+            Self::default()
+        });
+        if !settings.speed.is_finite() {
+            settings.speed = Self::default().speed;
+        }
+        settings
+    }
+    pub fn save(&self) -> Result<(), String> {
+        self.save_to(Path::new(Self::PATH))
+    }
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string(self)
+            .map_err(|err| format!("Could not save settings; serialization failed: {err}"))?;
+        fs::write(path, text)
+            .map_err(|err| format!("Could not save settings; file IO failed: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("settings-{}.toml", std::process::id()));
+
+        let settings = Settings {
+            grid_size: 40,
+            speed: 12.5,
+            boundary: Boundary::Wrap,
+            heatmap: true,
+            ruleset: Some(String::from("Sand")),
+            ..Settings::default()
+        };
+        settings.save_to(&path).unwrap();
+        assert_eq!(Settings::load_from(&path), settings);
+
+        // Missing fields keep their defaults, and unreadable files fall back entirely.
+        fs::write(&path, "grid_size = 12").unwrap();
+        assert_eq!(
+            Settings::load_from(&path),
+            Settings {
+                grid_size: 12,
+                ..Settings::default()
+            }
+        );
+        fs::write(&path, "speed = nan").unwrap();
+        assert_eq!(Settings::load_from(&path), Settings::default());
+        fs::write(&path, "grid_size = \"big\"").unwrap();
+        assert_eq!(Settings::load_from(&path), Settings::default());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(Settings::load_from(&path), Settings::default());
+    }
+}
@@ -0,0 +1,42 @@
+use std::{path::Path, sync::mpsc, thread, time::Duration};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use vizia::context::ContextProxy;
+
+use crate::{events::RulesetEvent, ruleset::Ruleset};
+
+/// How long the ruleset directory must stay quiet before a burst of changes triggers a reload.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches `Ruleset::PATH`, emitting `RulesetEvent::FilesChanged` through `proxy` whenever its
+/// files change.
+///
+/// Watching stops when the returned watcher is dropped.
+pub fn watch_rulesets(mut proxy: ContextProxy) -> Result<RecommendedWatcher, String> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)
+        .map_err(|err| format!("Could not watch rulesets; watcher creation failed: {err}"))?;
+    watcher
+        .watch(Path::new(Ruleset::PATH), RecursiveMode::NonRecursive)
+        .map_err(|err| format!("Could not watch rulesets in '{}': {err}", Ruleset::PATH))?;
+
+    thread::spawn(move || {
+        while let Ok(event) = receiver.recv() {
+            let Ok(notify::Event { kind, paths, .. }) = event else {
+                continue;
+            };
+            // Autosaves are written in the background and never loaded as rulesets.
+            let autosaves_only =
+                !paths.is_empty() && paths.iter().all(|path| Ruleset::is_autosave(path));
+            if kind.is_access() || autosaves_only {
+                continue;
+            }
+            // Editors often write a file several times per save; wait for them to finish.
+            while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+            if proxy.emit(RulesetEvent::FilesChanged).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(watcher)
+}